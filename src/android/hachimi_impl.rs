@@ -24,7 +24,9 @@ pub struct Config {
     #[serde(default)]
     pub load_libraries: Vec<String>,
     #[serde(default)]
-    pub hook_libc_dlopen: bool
+    pub hook_libc_dlopen: bool,
+    #[serde(default)]
+    pub tl_auto_updater_unmetered_only: bool
 }
 
 impl Config {
@@ -306,4 +306,36 @@ pub fn get_available_disk_space() -> Option<u64> {
             None
         }
     }
-}
\ No newline at end of file
+}
+
+/// Whether the active network is metered, None if it couldn't be determined.
+pub fn is_active_network_metered() -> Option<bool> {
+    let vm = java_vm().expect("JavaVM not initialized");
+    let mut env = vm.attach_current_thread().expect("Failed to attach thread");
+
+    let result = (|| -> jni::errors::Result<bool> {
+        let activity = get_activity(unsafe { env.unsafe_clone() })
+            .ok_or(jni::errors::Error::JavaException)?;
+
+        // activity.getSystemService(Context.CONNECTIVITY_SERVICE)
+        let service_name = env.new_string("connectivity")?;
+        let connectivity_manager = env.call_method(
+            &activity, "getSystemService", "(Ljava/lang/String;)Ljava/lang/Object;",
+            &[JValue::Object(&service_name)]
+        )?.l()?;
+        if connectivity_manager.is_null() {
+            return Err(jni::errors::Error::NullPtr("ConnectivityManager"));
+        }
+
+        // connectivityManager.isActiveNetworkMetered()
+        env.call_method(&connectivity_manager, "isActiveNetworkMetered", "()Z", &[])?.z()
+    })();
+
+    match result {
+        Ok(metered) => Some(metered),
+        Err(e) => {
+            info!("is_active_network_metered: JNI Error: {:?}", e);
+            None
+        }
+    }
+}
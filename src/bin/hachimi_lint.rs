@@ -0,0 +1,26 @@
+// Lints a translation repo without the game, for use in translation repo CI.
+// Usage: hachimi-lint <REPO_DIR> [REPORT_JSON]
+// Exits with 1 if any errors were found, 2 on invalid arguments.
+use std::{path::Path, process::ExitCode};
+
+use hachimi::core::lint;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 2 || args.len() > 3 {
+        eprintln!("Usage: hachimi-lint <REPO_DIR> [REPORT_JSON]");
+        return ExitCode::from(2);
+    }
+
+    let report = lint::lint_dir(Path::new(&args[1]));
+    print!("{}", report.to_text());
+
+    if let Some(report_path) = args.get(2) {
+        if let Err(e) = report.save(Path::new(report_path)) {
+            eprintln!("Failed to save lint report: {}", e);
+            return ExitCode::from(2);
+        }
+    }
+
+    if report.error_count() > 0 { ExitCode::from(1) } else { ExitCode::SUCCESS }
+}
@@ -0,0 +1,63 @@
+use std::{fs, path::PathBuf, sync::Mutex};
+
+use fnv::FnvHashSet;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+use super::{utils, Error, Hachimi};
+
+/// Dumped assets in the data dir, laid out like the assets dir of a localized data dir
+/// (textures/, atlas/, story/...) so they can be edited and copied into a repo as is.
+pub const DUMP_DIR: &str = "asset_dump";
+
+// Checked before touching the filesystem, most assets get loaded many times per session
+static SEEN: Lazy<Mutex<FnvHashSet<String>>> = Lazy::new(|| Mutex::default());
+
+pub fn is_enabled() -> bool {
+    Hachimi::instance().config.load().dump_assets
+}
+
+fn get_path(rel_path: &str) -> PathBuf {
+    Hachimi::instance().get_data_path(DUMP_DIR).join(rel_path)
+}
+
+fn prepare_path(rel_path: &str) -> Result<PathBuf, Error> {
+    let path = get_path(rel_path);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    Ok(path)
+}
+
+/// Whether the asset should be dumped. Only returns true the first time it's seen in a session,
+/// and never for assets that have already been dumped before.
+pub fn should_dump(rel_path: &str) -> bool {
+    if !SEEN.lock().unwrap().insert(rel_path.to_owned()) {
+        return false;
+    }
+    !get_path(rel_path).exists()
+}
+
+pub fn write_json<T: Serialize>(rel_path: &str, data: &T) {
+    match prepare_path(rel_path).and_then(|path| utils::write_json_file(data, path)) {
+        Ok(_) => info!("Dumped {}", rel_path),
+        Err(e) => error!("Failed to dump '{}': {}", rel_path, e)
+    }
+}
+
+/// Encodes the pixels (RGBA, bottom row first) as PNG and writes it in the background.
+pub fn write_texture(rel_path: String, pixels: Vec<u8>, width: usize, height: usize) {
+    std::thread::spawn(move || {
+        // Textures are stored upside down
+        let flipped: Vec<u8> = pixels.chunks_exact(width * 4).rev().flatten().copied().collect();
+
+        let res = utils::encode_rgba_png(&flipped, width as u32, height as u32).and_then(|png| {
+            fs::write(prepare_path(&rel_path)?, png)?;
+            Ok(())
+        });
+        match res {
+            Ok(_) => info!("Dumped {}", rel_path),
+            Err(e) => error!("Failed to dump '{}': {}", rel_path, e)
+        }
+    });
+}
@@ -3,7 +3,7 @@ use std::{
     sync::Mutex
 };
 use crate::{
-    core::Hachimi,
+    core::{plugin_events, Hachimi},
     il2cpp::{
         ext::{Il2CppStringExt, StringExt},
         hook::{
@@ -167,6 +167,7 @@ pub fn process_caption_request() {
         config.caption.caption_bg_alpha,
     );
     Captions::show(&localized_text, config.caption.caption_lines_char_count);
+    plugin_events::emit(plugin_events::Event::CaptionShown { text: &localized_text });
 }
 
 struct CaptionState {
@@ -0,0 +1,154 @@
+use std::{collections::BTreeMap, fmt::Write as _, fs, path::PathBuf};
+
+use fnv::{FnvHashMap, FnvHashSet};
+use serde::Serialize;
+
+use crate::il2cpp::{hook::umamusume::Localize, sql};
+
+use super::{utils, Error, Hachimi};
+
+const REPORT_FILENAME: &str = "coverage_report";
+
+/// How much of the game's text the current localized data translates.
+#[derive(Serialize)]
+pub struct CoverageReport {
+    pub generated_at: String,
+    pub sections: Vec<CoverageSection>
+}
+
+#[derive(Serialize)]
+pub struct CoverageSection {
+    pub name: &'static str,
+    pub total: usize,
+    pub translated: usize,
+    /// Dict entries without a matching row in the game data
+    pub unused: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<CategoryCoverage>
+}
+
+#[derive(Serialize)]
+pub struct CategoryCoverage {
+    pub id: i32,
+    pub total: usize,
+    pub translated: usize,
+    pub unused: usize
+}
+
+pub fn percentage(translated: usize, total: usize) -> f32 {
+    if total == 0 {
+        return 100.0;
+    }
+    translated as f32 / total as f32 * 100.0
+}
+
+impl CoverageReport {
+    /// Queries master.mdb and the Localize strings, must be called from the main thread.
+    pub fn generate() -> CoverageReport {
+        let localized_data = Hachimi::instance().localized_data.load();
+
+        let mut sections = Vec::new();
+        sections.push(Self::categorized_section(
+            "text_data_dict", sql::get_all_text_data_keys(), &localized_data.text_data_dict
+        ));
+        sections.push(Self::categorized_section(
+            "character_system_text_dict", sql::get_all_character_system_text_keys(), &localized_data.character_system_text_dict
+        ));
+        sections.push(Self::flat_section(
+            "race_jikkyo_comment_dict",
+            sql::get_all_race_jikkyo_comment_ids().into_iter().collect(),
+            localized_data.race_jikkyo_comment_dict.keys().copied().collect()
+        ));
+        sections.push(Self::flat_section(
+            "race_jikkyo_message_dict",
+            sql::get_all_race_jikkyo_message_ids().into_iter().collect(),
+            localized_data.race_jikkyo_message_dict.keys().copied().collect()
+        ));
+        sections.push(Self::flat_section(
+            "localize_dict",
+            Localize::dump_strings().into_keys().collect(),
+            localized_data.localize_dict.keys().cloned().collect()
+        ));
+
+        CoverageReport {
+            generated_at: chrono::Utc::now().to_rfc3339(),
+            sections
+        }
+    }
+
+    fn flat_section<T: Eq + std::hash::Hash>(name: &'static str, keys: FnvHashSet<T>, dict_keys: FnvHashSet<T>) -> CoverageSection {
+        let translated = keys.intersection(&dict_keys).count();
+        CoverageSection {
+            name,
+            total: keys.len(),
+            translated,
+            unused: dict_keys.len() - translated,
+            categories: Vec::new()
+        }
+    }
+
+    fn categorized_section(name: &'static str, keys: Vec<(i32, i32)>, dict: &FnvHashMap<i32, FnvHashMap<i32, String>>) -> CoverageSection {
+        let mut categories: BTreeMap<i32, CategoryCoverage> = BTreeMap::new();
+        for (category, index) in keys {
+            let coverage = categories.entry(category)
+                .or_insert_with(|| CategoryCoverage { id: category, total: 0, translated: 0, unused: 0 });
+            coverage.total += 1;
+            if dict.get(&category).is_some_and(|c| c.contains_key(&index)) {
+                coverage.translated += 1;
+            }
+        }
+
+        for (category, entries) in dict {
+            let coverage = categories.entry(*category)
+                .or_insert_with(|| CategoryCoverage { id: *category, total: 0, translated: 0, unused: 0 });
+            coverage.unused = entries.len() - coverage.translated;
+        }
+
+        let categories: Vec<CategoryCoverage> = categories.into_values().collect();
+        CoverageSection {
+            name,
+            total: categories.iter().map(|c| c.total).sum(),
+            translated: categories.iter().map(|c| c.translated).sum(),
+            unused: categories.iter().map(|c| c.unused).sum(),
+            categories
+        }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::new();
+        _ = writeln!(md, "# Translation coverage report\n\nGenerated at {}\n", self.generated_at);
+        _ = writeln!(md, "| Dict | Translated | Total | Coverage | Unused |");
+        _ = writeln!(md, "|---|---:|---:|---:|---:|");
+        for section in &self.sections {
+            _ = writeln!(
+                md, "| {} | {} | {} | {:.1}% | {} |",
+                section.name, section.translated, section.total,
+                percentage(section.translated, section.total), section.unused
+            );
+        }
+
+        for section in self.sections.iter().filter(|s| !s.categories.is_empty()) {
+            _ = writeln!(md, "\n## {}\n", section.name);
+            _ = writeln!(md, "| Category | Translated | Total | Coverage | Unused |");
+            _ = writeln!(md, "|---:|---:|---:|---:|---:|");
+            for category in &section.categories {
+                _ = writeln!(
+                    md, "| {} | {} | {} | {:.1}% | {} |",
+                    category.id, category.translated, category.total,
+                    percentage(category.translated, category.total), category.unused
+                );
+            }
+        }
+
+        md
+    }
+
+    /// Saves the report as JSON and Markdown in the data dir, returns the JSON path.
+    pub fn save(&self) -> Result<PathBuf, Error> {
+        let hachimi = Hachimi::instance();
+        let json_path = hachimi.get_data_path(format!("{}.json", REPORT_FILENAME));
+        utils::write_json_file(self, &json_path)?;
+        fs::write(hachimi.get_data_path(format!("{}.md", REPORT_FILENAME)), self.to_markdown())?;
+        Ok(json_path)
+    }
+}
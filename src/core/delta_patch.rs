@@ -0,0 +1,131 @@
+use std::io::{self, Cursor, Write};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::Error;
+
+/// Format of a delta patch published in a translation repo index.
+#[derive(Deserialize, Clone, Copy, Default, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PatchFormat {
+    /// bsdiff 4 patch, works on any file
+    #[default]
+    Bsdiff,
+    /// RFC 7386 JSON merge patch. The patched file is written with serde_json's pretty printer,
+    /// so the new file has to be published in that form for its hash to match. serde_json is
+    /// built without `preserve_order`, so object keys also come out sorted; publishers must
+    /// emit the new file with sorted keys or the patch will never verify.
+    JsonMerge
+}
+
+/// Applies a patch to the old contents of a file, returns the new contents.
+/// Fails if the output would be larger than `max_size`.
+pub fn apply(format: PatchFormat, old: &[u8], patch: &[u8], max_size: usize) -> Result<Vec<u8>, Error> {
+    let mut new = LimitedWriter { buf: Vec::new(), max_size };
+    match format {
+        PatchFormat::Bsdiff => {
+            // Every output byte is read from the patch, which is already size checked
+            bsdiff::patch(old, &mut Cursor::new(patch), &mut new.buf)?;
+            if new.buf.len() > max_size {
+                return Err(Error::RuntimeError("patched file is larger than expected".to_owned()));
+            }
+        }
+        PatchFormat::JsonMerge => {
+            let mut doc: Value = serde_json::from_slice(old)?;
+            merge(&mut doc, serde_json::from_slice(patch)?);
+            serde_json::to_writer_pretty(&mut new, &doc)?;
+        }
+    }
+    Ok(new.buf)
+}
+
+struct LimitedWriter {
+    buf: Vec<u8>,
+    max_size: usize
+}
+
+impl Write for LimitedWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if self.buf.len() + data.len() > self.max_size {
+            return Err(io::Error::new(io::ErrorKind::Other, "patched file is larger than expected"));
+        }
+        self.buf.extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn merge(target: &mut Value, patch: Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch;
+        return;
+    };
+
+    if !target.is_object() {
+        *target = Value::Object(Default::default());
+    }
+    let Value::Object(map) = target else { unreachable!() };
+    for (key, value) in patch {
+        if value.is_null() {
+            map.remove(&key);
+        }
+        else {
+            merge(map.entry(key).or_insert(Value::Null), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn merged(mut target: Value, patch: Value) -> Value {
+        merge(&mut target, patch);
+        target
+    }
+
+    #[test]
+    fn merge_null_deletes_key() {
+        assert_eq!(merged(json!({"a": 1, "b": 2}), json!({"a": null})), json!({"b": 2}));
+        assert_eq!(merged(json!({"a": 1}), json!({"missing": null})), json!({"a": 1}));
+    }
+
+    #[test]
+    fn merge_non_object_replaces_target() {
+        assert_eq!(merged(json!({"a": 1}), json!("text")), json!("text"));
+        assert_eq!(merged(json!({"a": 1}), json!([1, 2])), json!([1, 2]));
+        assert_eq!(merged(json!({"a": [1, 2]}), json!({"a": [3]})), json!({"a": [3]}));
+        assert_eq!(merged(json!({"a": {"b": 1}}), json!({"a": 5})), json!({"a": 5}));
+    }
+
+    #[test]
+    fn merge_object_into_non_object() {
+        assert_eq!(merged(json!([1, 2]), json!({"a": 1})), json!({"a": 1}));
+        assert_eq!(merged(json!({"a": "x"}), json!({"a": {"b": null, "c": 1}})), json!({"a": {"c": 1}}));
+    }
+
+    #[test]
+    fn merge_nested() {
+        assert_eq!(
+            merged(json!({"a": {"b": 1, "c": 2}, "d": 3}), json!({"a": {"b": 10, "e": 4}})),
+            json!({"a": {"b": 10, "c": 2, "e": 4}, "d": 3})
+        );
+    }
+
+    #[test]
+    fn apply_json_merge_output() {
+        let new = apply(PatchFormat::JsonMerge, br#"{"b": 1, "a": 2}"#, br#"{"c": 3}"#, 1024).unwrap();
+        assert_eq!(new, b"{\n  \"a\": 2,\n  \"b\": 1,\n  \"c\": 3\n}");
+    }
+
+    #[test]
+    fn apply_caps_output_size() {
+        assert!(apply(PatchFormat::JsonMerge, b"{}", br#"{"a": "long value"}"#, 8).is_err());
+    }
+}
@@ -16,6 +16,7 @@ pub enum Error {
     PluralParsing,
     OutOfDiskSpace,
     FileHashMismatch(String),
+    SignatureVerification(String),
     ZipError(zip::result::ZipError),
     DiscordRpcError(String),
     RuntimeError(String)
@@ -60,6 +61,9 @@ impl fmt::Display for Error {
             Error::FileHashMismatch(name) => {
                 write!(f, "File hash mismatch: {}", name)
             }
+            Error::SignatureVerification(reason) => {
+                write!(f, "Signature verification failed: {}", reason)
+            }
             Error::ZipError(error) => {
                 write!(f, "Zip error: {}", error)
             },
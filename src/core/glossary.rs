@@ -0,0 +1,208 @@
+use fnv::FnvHashMap;
+
+use super::Hachimi;
+
+// text_data categories
+const CHARA_NAME_CATEGORY: i32 = 170;
+const SKILL_NAME_CATEGORY: i32 = 47;
+
+/// Fixed translations for terms in machine translated text.
+///
+/// Terms are replaced with placeholders before the text is sent to the MT backend and the
+/// placeholders are replaced with the fixed translations afterwards, so names stay consistent.
+#[derive(Default)]
+pub struct Glossary {
+    // Sorted by source length (longest first) so longer terms win over their substrings
+    terms: Vec<(String, String)>,
+    // First char of the source -> indices into terms, in the same order
+    by_first_char: FnvHashMap<char, Vec<usize>>
+}
+
+impl Glossary {
+    /// Builds the glossary from the TL repo's glossary dict, seeded with character and skill names
+    /// that have a translation in text_data_dict. Entries from the glossary dict take priority.
+    pub fn build() -> Glossary {
+        let hachimi = Hachimi::instance();
+        let localized_data = hachimi.localized_data.load();
+        let mut terms: FnvHashMap<String, String> = FnvHashMap::default();
+
+        if let Some(chara_names) = localized_data.text_data_dict.get(&CHARA_NAME_CATEGORY) {
+            for (id, source) in hachimi.chara_data.load().chara_names.iter() {
+                if let Some(name) = chara_names.get(id) {
+                    terms.insert(source.clone(), name.clone());
+                }
+            }
+        }
+
+        if let Some(skill_names) = localized_data.text_data_dict.get(&SKILL_NAME_CATEGORY) {
+            for (id, source) in hachimi.skill_info.load().skill_names.iter() {
+                if let Some(name) = skill_names.get(id) {
+                    terms.insert(source.clone(), name.clone());
+                }
+            }
+        }
+
+        for (source, translation) in localized_data.glossary.iter() {
+            terms.insert(source.clone(), translation.clone());
+        }
+
+        Self::from_terms(terms)
+    }
+
+    pub fn from_terms(terms: impl IntoIterator<Item = (String, String)>) -> Glossary {
+        let mut terms: Vec<(String, String)> = terms.into_iter()
+            .filter(|(source, _)| !source.is_empty())
+            .collect();
+        terms.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then_with(|| a.0.cmp(&b.0)));
+
+        let mut by_first_char: FnvHashMap<char, Vec<usize>> = FnvHashMap::default();
+        for (i, (source, _)) in terms.iter().enumerate() {
+            let c = source.chars().next().unwrap();
+            by_first_char.entry(c).or_default().push(i);
+        }
+        Glossary { terms, by_first_char }
+    }
+
+    pub fn len(&self) -> usize {
+        self.terms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Replaces glossary terms with numbered placeholders.
+    /// Returns the masked text and the translations for each placeholder.
+    pub fn mask<'a>(&'a self, text: &str) -> (String, Vec<&'a str>) {
+        let mut masks: Vec<&str> = Vec::new();
+        if self.terms.is_empty() {
+            return (text.to_owned(), masks);
+        }
+
+        let mut masked = String::with_capacity(text.len());
+        let mut rest = text;
+        'outer: while !rest.is_empty() {
+            let c = rest.chars().next().unwrap();
+            let candidates = self.by_first_char.get(&c).map(|v| v.as_slice()).unwrap_or_default();
+            for &i in candidates {
+                let (source, translation) = &self.terms[i];
+                if rest.starts_with(source.as_str()) {
+                    let index = match masks.iter().position(|t| *t == translation.as_str()) {
+                        Some(i) => i,
+                        None => {
+                            masks.push(translation);
+                            masks.len() - 1
+                        }
+                    };
+                    masked.push_str(&format!("[G{}]", index));
+                    rest = &rest[source.len()..];
+                    continue 'outer;
+                }
+            }
+
+            masked.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+
+        (masked, masks)
+    }
+
+    /// Replaces the placeholders in translated text with their translations.
+    /// Tolerates the whitespace and case changes MT backends like to introduce.
+    pub fn restore(text: &str, masks: &[&str]) -> String {
+        if masks.is_empty() {
+            return text.to_owned();
+        }
+
+        let mut restored = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find('[') {
+            restored.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            if let Some((index, len)) = Self::parse_placeholder(rest) {
+                if let Some(translation) = masks.get(index) {
+                    restored.push_str(translation);
+                    rest = &rest[len..];
+                    continue;
+                }
+            }
+
+            restored.push('[');
+            rest = &rest[1..];
+        }
+        restored.push_str(rest);
+
+        restored
+    }
+
+    // Parses "[G0]", "[ g 0 ]", etc. at the start of the string, returns the index and length
+    fn parse_placeholder(s: &str) -> Option<(usize, usize)> {
+        let mut chars = s.char_indices().skip(1).skip_while(|(_, c)| c.is_whitespace());
+        if !matches!(chars.next(), Some((_, 'G' | 'g'))) {
+            return None;
+        }
+
+        let mut index: Option<usize> = None;
+        for (i, c) in chars {
+            if let Some(digit) = c.to_digit(10) {
+                index = Some(index.unwrap_or(0) * 10 + digit as usize);
+            }
+            else if c == ']' {
+                return index.map(|index| (index, i + 1));
+            }
+            else if !c.is_whitespace() {
+                return None;
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glossary(terms: &[(&str, &str)]) -> Glossary {
+        Glossary::from_terms(terms.iter().map(|(s, t)| (s.to_string(), t.to_string())))
+    }
+
+    #[test]
+    fn mask_longest_match() {
+        let g = glossary(&[("スペ", "Spe"), ("スペシャルウィーク", "Special Week")]);
+        let (masked, masks) = g.mask("スペシャルウィークとスペ");
+        assert_eq!(masked, "[G0]と[G1]");
+        assert_eq!(masks, ["Special Week", "Spe"]);
+    }
+
+    #[test]
+    fn mask_repeated_terms() {
+        let g = glossary(&[("テイオー", "Teio"), ("マックイーン", "McQueen")]);
+        let (masked, masks) = g.mask("テイオーとマックイーンとテイオー");
+        assert_eq!(masked, "[G0]と[G1]と[G0]");
+        assert_eq!(masks, ["Teio", "McQueen"]);
+    }
+
+    #[test]
+    fn mask_without_terms() {
+        let g = glossary(&[("テイオー", "Teio")]);
+        assert_eq!(g.mask("こんにちは"), ("こんにちは".to_owned(), vec![]));
+        assert_eq!(Glossary::default().mask("テイオー").0, "テイオー");
+    }
+
+    #[test]
+    fn restore_placeholders() {
+        let masks = ["Teio", "McQueen"];
+        assert_eq!(Glossary::restore("[G0] and [G1]", &masks), "Teio and McQueen");
+        assert_eq!(Glossary::restore("[g0] and [ G 1 ]", &masks), "Teio and McQueen");
+        assert_eq!(Glossary::restore("[G1][G0][G1]", &masks), "McQueenTeioMcQueen");
+    }
+
+    #[test]
+    fn restore_keeps_unknown_brackets() {
+        let masks = ["Teio"];
+        assert_eq!(Glossary::restore("[G5] [x] [G] [G0", &masks), "[G5] [x] [G] [G0");
+        assert_eq!(Glossary::restore("[[G0]]", &masks), "[Teio]");
+    }
+}
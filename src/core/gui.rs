@@ -4,6 +4,7 @@ use std::{
     ops::RangeInclusive,
     os::raw::c_void,
     panic::{self, AssertUnwindSafe},
+    path::PathBuf,
     sync::{atomic::{self, AtomicBool}, Arc, Mutex},
     thread,
     time::Instant
@@ -36,11 +37,16 @@ use crate::il2cpp::{
 use crate::il2cpp::hook::UnityEngine_CoreModule::QualitySettings;
 
 use super::{
+    coverage::{self, CoverageReport},
+    lint,
     game::Region,
     hachimi::{self, Language, REPO_PATH, WEBSITE_URL},
-    http::{ureq_config, AsyncRequest},
+    http::{rewrite_url, ureq_config, AsyncRequest},
     live_utils,
+    tl_overrides,
     tl_repo::{self, RepoInfo, LocalRepoInfo},
+    mt_cache::MtCache,
+    translator::{TranslationBackendKind, Translator},
     utils::{self, get_localized_string, SendPtr},
     Hachimi
 };
@@ -661,8 +667,9 @@ impl Gui {
         unsafe {
             INSTANCE.set(Mutex::new(instance)).unwrap_unchecked();
 
-            // Doing auto update check here to ensure that the updater can access the gui
-            hachimi.run_auto_update_check();
+            // Doing auto update check here to ensure that the updater can access the gui.
+            // Packages dropped into the data dir are imported first so the check sees them.
+            hachimi.tl_updater.clone().import_dropped_packages(|| Hachimi::instance().run_auto_update_check());
 
             INSTANCE.get().unwrap_unchecked()
         }
@@ -1245,11 +1252,11 @@ impl Gui {
                             show_notification = Some(t!("notification.localized_data_reloaded"));
                         }
                         if ui.button(t!("menu.tl_check_for_updates")).clicked() {
-                            hachimi.tl_updater.skip_update(None);
+                            hachimi.tl_updater.clear_skipped_updates();
                             hachimi.tl_updater.clone().check_for_updates(false, false);
                         }
                         if ui.button(t!("menu.tl_check_for_updates_pedantic")).clicked() {
-                            hachimi.tl_updater.skip_update(None);
+                            hachimi.tl_updater.clear_skipped_updates();
                             hachimi.tl_updater.clone().check_for_updates(true, false);
                         }
                         if hachimi.config.load().translator_mode {
@@ -1266,10 +1273,53 @@ impl Gui {
                                     }
                                 })
                             }
+                            if ui.button(t!("menu.coverage_report")).clicked() {
+                                Thread::main_thread().schedule(|| {
+                                    let report = CoverageReport::generate();
+                                    let save_result = report.save().map_err(|e| e.to_string());
+                                    let mut gui = Gui::instance().unwrap().lock().unwrap();
+                                    gui.show_window(Box::new(CoverageReportWindow::new(report, save_result)));
+                                })
+                            }
+                            if ui.button(t!("menu.lint_repo")).clicked() {
+                                thread::spawn(|| {
+                                    let res = lint::lint_active_repo();
+                                    let Some(mutex) = Gui::instance() else {
+                                        return;
+                                    };
+                                    match res {
+                                        Ok((report, path)) => mutex.lock().unwrap().show_notification(&t!(
+                                            "notification.lint_finished",
+                                            errors = report.error_count(),
+                                            warnings = report.warning_count(),
+                                            path = path.display()
+                                        )),
+                                        Err(e) => mutex.lock().unwrap().show_notification(&e.to_string())
+                                    }
+                                });
+                            }
                         }
                         if ui.button(t!("menu.edit_excludes")).clicked() {
                             show_window = Some(Box::new(ExcludesEditorWindow::new()));
                         }
+                        if ui.button(t!("menu.tl_overrides")).clicked() {
+                            show_window = Some(Box::new(TlOverridesWindow::new()));
+                        }
+                        if hachimi.config.load().mt_cache_enabled && ui.button(t!("menu.export_mt_cache")).clicked() {
+                            thread::spawn(|| {
+                                let (_, namespace) = Translator::instance().backend();
+                                let res = MtCache::instance().lock().unwrap().export(&namespace);
+                                let Some(mutex) = Gui::instance() else {
+                                    return;
+                                };
+                                match res {
+                                    Ok(path) => mutex.lock().unwrap().show_notification(
+                                        &t!("notification.mt_cache_exported", path = path.display())
+                                    ),
+                                    Err(e) => mutex.lock().unwrap().show_notification(&e.to_string())
+                                }
+                            });
+                        }
                         ui.separator();
 
                         let plugin_items = get_plugin_menu_items();
@@ -1953,6 +2003,17 @@ fn paginated_window_layout(
     open
 }
 
+// Signing key the meta index lists for a repo
+fn meta_index_public_key(request: &AsyncRequest<Vec<RepoInfo>>, index: &str) -> Option<String> {
+    let result = request.result.load();
+    let Some(Ok(repos)) = result.as_ref().as_ref() else {
+        return None;
+    };
+    repos.iter()
+        .find(|r| r.index == index)
+        .and_then(|r| r.public_key.clone())
+}
+
 fn tl_repo_list_ui(
     ui: &mut egui::Ui,
     request: &Arc<AsyncRequest<Vec<RepoInfo>>>,
@@ -2363,6 +2424,58 @@ impl ConfigEditor {
                 ui.end_row();
             }
 
+            if should_show_option(search, &t!("config_editor.http_proxy")) {
+                ui.label(t!("config_editor.http_proxy"));
+                let mut proxy_val = config.http_proxy.clone().unwrap_or_default();
+                let _res = ui.add(egui::TextEdit::singleline(&mut proxy_val).hint_text("socks5://127.0.0.1:1080"));
+                #[cfg(target_os = "android")]
+                handle_android_keyboard(&_res, &mut proxy_val);
+                config.http_proxy = if proxy_val.is_empty() { None } else { Some(proxy_val) };
+                ui.end_row();
+            }
+
+            if should_show_option(search, &t!("config_editor.http_ca_bundle")) {
+                ui.label(t!("config_editor.http_ca_bundle")).on_hover_text(t!("config_editor.http_ca_bundle_tooltip"));
+                let mut ca_val = config.http_ca_bundle.clone().unwrap_or_default();
+                let _res = ui.add(egui::TextEdit::singleline(&mut ca_val).hint_text(t!("default")));
+                #[cfg(target_os = "android")]
+                handle_android_keyboard(&_res, &mut ca_val);
+                config.http_ca_bundle = if ca_val.is_empty() { None } else { Some(ca_val) };
+                ui.end_row();
+            }
+
+            if should_show_option(search, &t!("config_editor.mirror_rules")) {
+                ui.label(t!("config_editor.mirror_rules"));
+                ui.vertical(|ui| {
+                    let mut remove_index = None;
+                    for (i, rule) in config.mirror_rules.iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            let _res = ui.add(egui::TextEdit::singleline(&mut rule.from)
+                                .hint_text(t!("config_editor.mirror_rule_from"))
+                                .desired_width(120.0));
+                            #[cfg(target_os = "android")]
+                            handle_android_keyboard(&_res, &mut rule.from);
+                            ui.label("→");
+                            let _res = ui.add(egui::TextEdit::singleline(&mut rule.to)
+                                .hint_text(t!("config_editor.mirror_rule_to"))
+                                .desired_width(120.0));
+                            #[cfg(target_os = "android")]
+                            handle_android_keyboard(&_res, &mut rule.to);
+                            if ui.button("\u{f00d}").clicked() {
+                                remove_index = Some(i);
+                            }
+                        });
+                    }
+                    if let Some(i) = remove_index {
+                        config.mirror_rules.remove(i);
+                    }
+                    if ui.button(t!("config_editor.add_mirror_rule")).clicked() {
+                        config.mirror_rules.push(Default::default());
+                    }
+                });
+                ui.end_row();
+            }
+
             if should_show_option(search, &t!("config_editor.meta_index_url")) {
                 ui.label(t!("config_editor.meta_index_url"));
                 let res = ui.add(egui::TextEdit::singleline(&mut config.meta_index_url).lock_focus(true));
@@ -2486,6 +2599,18 @@ impl ConfigEditor {
                 ui.end_row();
             }
 
+            if should_show_option(search, &t!("config_editor.hot_reload_localized_data")) {
+                ui.label(t!("config_editor.hot_reload_localized_data"));
+                ui.checkbox(&mut config.hot_reload_localized_data, "");
+                ui.end_row();
+            }
+
+            if should_show_option(search, &t!("config_editor.dump_assets")) {
+                ui.label(t!("config_editor.dump_assets"));
+                ui.checkbox(&mut config.dump_assets, "");
+                ui.end_row();
+            }
+
             if should_show_option(search, &t!("config_editor.skip_first_time_setup")) {
                 ui.label(t!("config_editor.skip_first_time_setup"));
                 ui.checkbox(&mut config.skip_first_time_setup, "");
@@ -2504,6 +2629,12 @@ impl ConfigEditor {
                 ui.end_row();
             }
 
+            if should_show_option(search, &t!("config_editor.delta_translation_updates")) {
+                ui.label(t!("config_editor.delta_translation_updates"));
+                ui.checkbox(&mut config.delta_translation_updates, "");
+                ui.end_row();
+            }
+
             if should_show_option(search, &t!("config_editor.disable_auto_update_check")) {
                 ui.label(t!("config_editor.disable_auto_update_check"));
                 ui.checkbox(&mut config.disable_auto_update_check, "");
@@ -2531,6 +2662,32 @@ impl ConfigEditor {
                     config.tl_auto_updater_interval_sec = (minutes as u64) * 60;
                     ui.end_row();
                 }
+
+                if should_show_option(search, &t!("config_editor.tl_auto_updater_avoid_busy_scenes")) {
+                    ui.label(t!("config_editor.tl_auto_updater_avoid_busy_scenes"));
+                    ui.checkbox(&mut config.tl_auto_updater_avoid_busy_scenes, "");
+                    ui.end_row();
+                }
+
+                if should_show_option(search, &t!("config_editor.tl_auto_updater_quiet_hours")) {
+                    ui.label(t!("config_editor.tl_auto_updater_quiet_hours"));
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut config.tl_auto_updater_quiet_hours, "");
+                        ui.add_enabled(config.tl_auto_updater_quiet_hours,
+                            egui::DragValue::new(&mut config.tl_auto_updater_quiet_start_hour).range(0..=23).suffix(":00"));
+                        ui.label("-");
+                        ui.add_enabled(config.tl_auto_updater_quiet_hours,
+                            egui::DragValue::new(&mut config.tl_auto_updater_quiet_end_hour).range(0..=23).suffix(":00"));
+                    });
+                    ui.end_row();
+                }
+
+                #[cfg(target_os = "android")]
+                if should_show_option(search, &t!("config_editor.tl_auto_updater_unmetered_only")) {
+                    ui.label(t!("config_editor.tl_auto_updater_unmetered_only"));
+                    ui.checkbox(&mut config.android.tl_auto_updater_unmetered_only, "");
+                    ui.end_row();
+                }
             }
 
             if should_show_option(search, &t!("config_editor.disable_translations")) {
@@ -2551,6 +2708,35 @@ impl ConfigEditor {
                 ui.end_row();
             }
 
+            if should_show_option(search, &t!("config_editor.ipc_port")) {
+                ui.label(t!("config_editor.ipc_port"));
+                ui.add(egui::DragValue::new(&mut config.ipc_port).range(1024..=65535));
+                ui.end_row();
+            }
+
+            if should_show_option(search, &t!("config_editor.ipc_token")) {
+                ui.label(t!("config_editor.ipc_token"));
+                let mut token = config.ipc_token.clone().unwrap_or_default();
+                ui.add(egui::TextEdit::singleline(&mut token).password(true).hint_text(t!("disabled")));
+                config.ipc_token = if token.is_empty() { None } else { Some(token) };
+                ui.end_row();
+            }
+
+            if should_show_option(search, &t!("config_editor.ipc_allowed_addresses")) {
+                ui.label(t!("config_editor.ipc_allowed_addresses"));
+                let mut addresses = config.ipc_allowed_addresses.join(", ");
+                if ui.add(egui::TextEdit::singleline(&mut addresses).hint_text(t!("config_editor.ipc_allowed_addresses_hint"))).changed() {
+                    // Keep empty entries while editing so a trailing comma isn't eaten
+                    config.ipc_allowed_addresses = if addresses.trim().is_empty() {
+                        Vec::new()
+                    }
+                    else {
+                        addresses.split(',').map(|a| a.trim().to_owned()).collect()
+                    };
+                }
+                ui.end_row();
+            }
+
             if should_show_option(search, &t!("config_editor.hide_now_loading")) {
                 ui.label(t!("config_editor.hide_now_loading"));
                 ui.checkbox(&mut config.hide_now_loading, "");
@@ -2618,6 +2804,67 @@ impl ConfigEditor {
                 ui.end_row();
             }
 
+            if should_show_option(search, &t!("config_editor.mt_backend")) {
+                ui.label(t!("config_editor.mt_backend"));
+                Gui::run_combo(ui, "mt_backend", &mut config.mt.mt_backend, &[
+                    (TranslationBackendKind::Sugoi, "Sugoi"),
+                    (TranslationBackendKind::OpenAi, &t!("config_editor.mt_backend_openai")),
+                    (TranslationBackendKind::LibreTranslate, "LibreTranslate"),
+                    (TranslationBackendKind::DeepL, "DeepL")
+                ]);
+                ui.end_row();
+            }
+
+            if should_show_option(search, &t!("config_editor.mt_url")) {
+                ui.label(t!("config_editor.mt_url"));
+                let mut url = config.mt.mt_url.clone().unwrap_or_default();
+                ui.add(egui::TextEdit::singleline(&mut url).hint_text(t!("default")));
+                config.mt.mt_url = if url.is_empty() { None } else { Some(url) };
+                ui.end_row();
+            }
+
+            if config.mt.mt_backend != TranslationBackendKind::Sugoi {
+                if should_show_option(search, &t!("config_editor.mt_api_key")) {
+                    ui.label(t!("config_editor.mt_api_key"));
+                    let mut api_key = config.mt.mt_api_key.clone().unwrap_or_default();
+                    ui.add(egui::TextEdit::singleline(&mut api_key).password(true));
+                    config.mt.mt_api_key = if api_key.is_empty() { None } else { Some(api_key) };
+                    ui.end_row();
+                }
+
+                if should_show_option(search, &t!("config_editor.mt_languages")) {
+                    ui.label(t!("config_editor.mt_languages"));
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(&mut config.mt.mt_source_lang).desired_width(40.0));
+                        ui.label("→");
+                        ui.add(egui::TextEdit::singleline(&mut config.mt.mt_target_lang).desired_width(40.0));
+                    });
+                    ui.end_row();
+                }
+            }
+
+            if config.mt.mt_backend == TranslationBackendKind::OpenAi {
+                if should_show_option(search, &t!("config_editor.mt_model")) {
+                    ui.label(t!("config_editor.mt_model"));
+                    let mut model = config.mt.mt_model.clone().unwrap_or_default();
+                    ui.add(egui::TextEdit::singleline(&mut model).hint_text(t!("default")));
+                    config.mt.mt_model = if model.is_empty() { None } else { Some(model) };
+                    ui.end_row();
+                }
+            }
+
+            if should_show_option(search, &t!("config_editor.mt_cache_enabled")) {
+                ui.label(t!("config_editor.mt_cache_enabled"));
+                ui.checkbox(&mut config.mt_cache_enabled, "");
+                ui.end_row();
+            }
+
+            if config.mt_cache_enabled && should_show_option(search, &t!("config_editor.mt_cache_max_entries")) {
+                ui.label(t!("config_editor.mt_cache_max_entries"));
+                ui.add(egui::DragValue::new(&mut config.mt_cache_max_entries).speed(100.0).range(0..=10000000));
+                ui.end_row();
+            }
+
             #[cfg(target_os = "windows")]
             {
                 if should_show_option(search, &t!("config_editor.taskbar_show_progress_on_download")) {
@@ -3391,6 +3638,9 @@ impl Window for FirstTimeSetupWindow {
                         existing
                     } else {
                         let new_id = manager.add(index.clone());
+                        if let Some(key) = meta_index_public_key(&self.index_request, index) {
+                            manager.pin_public_key(new_id, &key);
+                        }
                         if let Err(e) = manager.save(&repos_path) {
                             warn!("Failed to persist .tl_repos: {e}");
                         }
@@ -4132,6 +4382,13 @@ impl Window for ExcludesEditorWindow {
     }
 }
 
+enum RepoChainAction {
+    // Makes a fallback repo the selected one
+    Promote(u32, String),
+    MoveFallback(u32, isize),
+    SetFallback(u32, bool)
+}
+
 struct ChangeTranslationRepoWindow {
     id: egui::Id,
     confirm_remove: Option<(u32, String)>,
@@ -4187,6 +4444,9 @@ impl Window for ChangeTranslationRepoWindow {
         let manager = hachimi.tl_repo_manager.lock().unwrap().clone();
         let current_repo_id = hachimi.config.load().selected_tl_repo_id;
         let has_repos = !manager.repos.is_empty();
+        let chain = manager.active_chain(current_repo_id);
+        let fallbacks: Vec<&tl_repo::RepoEntry> = chain.iter().filter(|r| Some(r.id) != current_repo_id).collect();
+        let mut chain_action = None;
 
         let completed_id = REMOVED_TLREPO_ID.load(atomic::Ordering::Relaxed);
         if completed_id != u32::MAX {
@@ -4261,6 +4521,11 @@ impl Window for ChangeTranslationRepoWindow {
                                 }
 
                                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
+                                    if let Some(next) = fallbacks.first() {
+                                        if ui.button(" \u{f063} ").clicked() {
+                                            chain_action = Some(RepoChainAction::Promote(next.id, next.index.clone()));
+                                        }
+                                    }
                                     if let Some(ref info) = info {
                                         if ui.button(t!("remove")).clicked() {
                                             self.confirm_remove = Some((repo.id, info.name.clone()));
@@ -4299,12 +4564,49 @@ impl Window for ChangeTranslationRepoWindow {
                                 });
                             }
 
+                            if !fallbacks.is_empty() {
+                                ui.add_space(4.0 * scale);
+                                ui.label(t!("change_translation_repo.fallbacks"));
+                            }
+                            for (i, repo) in fallbacks.iter().enumerate() {
+                                let cached = self.repo_cache.get(&repo.id);
+                                let name = cached.and_then(|(info, _)| info.as_ref())
+                                    .map(|info| info.name.as_str())
+                                    .unwrap_or(repo.index.as_str());
+
+                                ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
+                                    if ui.button(" \u{f00d} ").on_hover_text(t!("change_translation_repo.disable_fallback")).clicked() {
+                                        chain_action = Some(RepoChainAction::SetFallback(repo.id, false));
+                                    }
+                                    if i + 1 < fallbacks.len() && ui.button(" \u{f063} ").clicked() {
+                                        chain_action = Some(RepoChainAction::MoveFallback(repo.id, 1));
+                                    }
+                                    if ui.button(" \u{f062} ").clicked() {
+                                        chain_action = Some(if i == 0 {
+                                            RepoChainAction::Promote(repo.id, repo.index.clone())
+                                        } else {
+                                            RepoChainAction::MoveFallback(repo.id, -1)
+                                        });
+                                    }
+                                    let name_width = ui.available_width() - 48.0 * scale - ui.style().spacing.item_spacing.x;
+                                    ui.allocate_ui_with_layout(egui::vec2(name_width, 0.0), egui::Layout::left_to_right(egui::Align::Center), |ui| {
+                                        ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Wrap);
+                                        ui.label(format!("{}. {}", chain.len() - fallbacks.len() + i + 1, name));
+                                    });
+                                    ui.add(match cached.and_then(|(_, uri)| uri.as_ref()) {
+                                        Some(uri) => egui::Image::new(uri.clone())
+                                            .fit_to_exact_size(egui::Vec2::new(48.0 * scale, 48.0 * scale)),
+                                        None => Gui::icon_2x(ctx),
+                                    });
+                                });
+                            }
+
                             ui.add_space(8.0 * scale);
                             ui.heading(t!("change_translation_repo.available"));
                             ui.separator();
 
                             for repo in &manager.repos {
-                                let is_active = current_repo_id == Some(repo.id);
+                                let is_active = current_repo_id == Some(repo.id) || manager.is_fallback(repo.id);
                                 if is_active { continue; }
 
                                 let cached = self.repo_cache.get(&repo.id);
@@ -4341,6 +4643,9 @@ impl Window for ChangeTranslationRepoWindow {
                                         if ui.button(t!("remove")).clicked() {
                                             self.confirm_remove = Some((repo.id, info.name.clone()));
                                         }
+                                        if current_repo_id.is_some() && ui.button(" \u{f067} ").on_hover_text(t!("change_translation_repo.add_fallback")).clicked() {
+                                            chain_action = Some(RepoChainAction::SetFallback(repo.id, true));
+                                        }
                                         if ui.button("\u{f05a}").clicked() {
                                             let repo_id = repo.id;
                                             let index = repo.index.clone();
@@ -4369,6 +4674,9 @@ impl Window for ChangeTranslationRepoWindow {
                                         if ui.button(t!("remove")).clicked() {
                                             self.confirm_remove = Some((repo.id, repo.index.clone()));
                                         }
+                                        if current_repo_id.is_some() && ui.button(" \u{f067} ").on_hover_text(t!("change_translation_repo.add_fallback")).clicked() {
+                                            chain_action = Some(RepoChainAction::SetFallback(repo.id, true));
+                                        }
                                         let name_width = ui.available_width() - 48.0 * scale - ui.style().spacing.item_spacing.x;
                                         let name_resp = ui.allocate_ui_with_layout(egui::vec2(name_width, 0.0), egui::Layout::left_to_right(egui::Align::Center), |ui| {
                                             ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Wrap);
@@ -4401,10 +4709,21 @@ impl Window for ChangeTranslationRepoWindow {
                             .show_window(Box::new(AddTranslationRepoWindow::new()));
                         });
                     }
+                    if ui.button(t!("change_translation_repo.import_package")).clicked() {
+                        thread::spawn(|| {
+                            Gui::instance().unwrap()
+                            .lock().unwrap()
+                            .show_window(Box::new(ImportRepoPackageWindow::new()));
+                        });
+                    }
                 }
             );
         });
 
+        if let Some(action) = chain_action {
+            Self::apply_chain_action(action, current_repo_id);
+        }
+
         open &= open2;
         open
     }
@@ -4433,6 +4752,46 @@ impl ChangeTranslationRepoWindow {
         self.repo_cache.insert(repo_id, (info, icon_uri));
     }
 
+    fn apply_chain_action(action: RepoChainAction, selected_id: Option<u32>) {
+        let hachimi = Hachimi::instance();
+        let repos_path = hachimi.get_data_path(".tl_repos");
+        let mut check_updates = false;
+        let promoted = {
+            let mut manager = hachimi.tl_repo_manager.lock().unwrap();
+            let promoted = match action {
+                RepoChainAction::Promote(repo_id, index) => {
+                    manager.promote_fallback(repo_id, selected_id);
+                    Some((repo_id, index))
+                }
+                RepoChainAction::MoveFallback(repo_id, offset) => {
+                    manager.move_fallback(repo_id, offset);
+                    None
+                }
+                RepoChainAction::SetFallback(repo_id, enabled) => {
+                    manager.set_fallback(repo_id, enabled);
+                    check_updates = enabled;
+                    None
+                }
+            };
+            if let Err(e) = manager.save(&repos_path) {
+                warn!("Failed to save .tl_repos: {e}");
+            }
+            promoted
+        };
+
+        if let Some((repo_id, index)) = promoted {
+            // The config change reloads the localized data
+            Self::switch_to_repo(repo_id, &index);
+            return;
+        }
+
+        hachimi.load_localized_data();
+        request_notification(NotificationRequest::TLRepoChanged);
+        if check_updates {
+            hachimi.tl_updater.clone().check_for_updates(false, false);
+        }
+    }
+
     fn switch_to_repo(repo_id: u32, index: &str) {
         let hachimi = Hachimi::instance();
         let config = hachimi.config.load();
@@ -4466,10 +4825,16 @@ impl ChangeTranslationRepoWindow {
                 let _ = std::fs::remove_file(&cache_path);
             }
 
+            let index_path = hachimi.get_data_path(format!(".tl_repo_index_{}", repo_id));
+            if index_path.exists() {
+                let _ = std::fs::remove_file(&index_path);
+                let _ = std::fs::remove_file(index_path.with_extension("sig"));
+            }
+
             let repos_path = hachimi.get_data_path(".tl_repos");
             {
                 let mut manager = hachimi.tl_repo_manager.lock().unwrap();
-                manager.repos.retain(|r| r.id != repo_id);
+                manager.remove(repo_id);
                 if let Err(e) = manager.save(&repos_path) {
                     warn!("Failed to save .tl_repos after removal: {e}");
                 }
@@ -4572,6 +4937,9 @@ impl Window for AddTranslationRepoWindow {
                     save_and_reload_config(new_config);
                 } else {
                     let new_id = manager.add(index.clone());
+                    if let Some(key) = meta_index_public_key(&self.index_request, index) {
+                        manager.pin_public_key(new_id, &key);
+                    }
                     if let Err(e) = manager.save(&repos_path) {
                         warn!("Failed to persist .tl_repos: {e}");
                     }
@@ -4595,7 +4963,14 @@ impl Window for AddTranslationRepoWindow {
 
 struct TranslationRepoInfoWindow {
     id: egui::Id,
+    repo_id: u32,
     index_url: String,
+    installed_revision: Option<String>,
+    pinned_revision: Option<String>,
+    pin_revision_input: String,
+    public_key: Option<String>,
+    has_backup: bool,
+    rollback_thread: Option<thread::JoinHandle<()>>,
     info: Option<LocalRepoInfo>,
     icon_uri: Option<String>,
     contributors_text: Option<String>,
@@ -4626,6 +5001,9 @@ impl TranslationRepoInfoWindow {
 
         let contributors_text = info.as_ref().and_then(|i| i.format_contributors());
 
+        let installed_revision = tl_repo::get_installed_revision(repo_id);
+        let (pinned_revision, public_key) = Self::load_repo_entry(repo_id);
+
         let contributors_fetch_result = Arc::new(Mutex::new(None));
         if let Some(ref i) = info {
             if i.is_contributors_txt_url() {
@@ -4636,7 +5014,7 @@ impl TranslationRepoInfoWindow {
                     std::thread::spawn(move || {
                         let agent = ureq::Agent::new_with_config(ureq_config());
 
-                        if let Ok(res) = agent.get(&url).call() {
+                        if let Ok(res) = agent.get(rewrite_url(&url).as_ref()).call() {
                             if let Ok(text) = res.into_body().read_to_string() {
                                 let sanitized: String = text.chars()
                                     .filter(|c| !c.is_control() || *c == '\n' || *c == '\t' || *c == '\r')
@@ -4657,13 +5035,41 @@ impl TranslationRepoInfoWindow {
 
         TranslationRepoInfoWindow {
             id: random_id(),
+            repo_id,
             index_url,
+            pin_revision_input: installed_revision.clone().unwrap_or_default(),
+            installed_revision,
+            pinned_revision,
+            public_key,
+            has_backup: tl_repo::has_backup(repo_id),
+            rollback_thread: None,
             info,
             icon_uri,
             contributors_text,
             contributors_fetch_result,
         }
     }
+
+    // Pinned revision and signing key
+    fn load_repo_entry(repo_id: u32) -> (Option<String>, Option<String>) {
+        Hachimi::instance().tl_repo_manager.lock().unwrap()
+            .repos.iter()
+            .find(|r| r.id == repo_id)
+            .map(|r| (r.pinned_revision.clone(), r.public_key.clone()))
+            .unwrap_or_default()
+    }
+
+    // Reads the revision state back from disk once the rollback is done, whether it succeeded or not
+    fn poll_rollback(&mut self) {
+        if !self.rollback_thread.as_ref().is_some_and(|t| t.is_finished()) {
+            return;
+        }
+        self.rollback_thread = None;
+
+        self.installed_revision = tl_repo::get_installed_revision(self.repo_id);
+        (self.pinned_revision, self.public_key) = Self::load_repo_entry(self.repo_id);
+        self.has_backup = tl_repo::has_backup(self.repo_id);
+    }
 }
 
 impl Window for TranslationRepoInfoWindow {
@@ -4671,6 +5077,7 @@ impl Window for TranslationRepoInfoWindow {
         let scale = get_scale(ctx);
         let mut open = true;
 
+        self.poll_rollback();
         if self.contributors_text.is_none() {
             if let Ok(mut lock) = self.contributors_fetch_result.try_lock() {
                 if let Some(text) = lock.take() {
@@ -4780,6 +5187,11 @@ impl Window for TranslationRepoInfoWindow {
                     ui.add_space(8.0 * scale);
                     ui.label(&self.index_url);
                 }
+
+                ui.add_space(6.0 * scale);
+                ui.separator();
+                ui.add_space(4.0 * scale);
+                self.run_revision_section(ui, scale);
             });
         });
 
@@ -4787,6 +5199,226 @@ impl Window for TranslationRepoInfoWindow {
     }
 }
 
+impl TranslationRepoInfoWindow {
+    fn run_revision_section(&mut self, ui: &mut egui::Ui, scale: f32) {
+        ui.label(egui::RichText::new(t!("translation_repo_info.revision")).strong());
+        egui::Grid::new(self.id.with("revision_grid"))
+            .num_columns(2)
+            .min_col_width(95.0 * scale)
+            .spacing([12.0 * scale, 6.0 * scale])
+            .show(ui, |ui| {
+                ui.label(t!("translation_repo_info.installed_revision"));
+                ui.label(self.installed_revision.as_deref().unwrap_or("-"));
+                ui.end_row();
+
+                ui.label(t!("translation_repo_info.signing_key"));
+                match &self.public_key {
+                    Some(key) => ui.label(egui::RichText::new(key).monospace().small()),
+                    None => ui.label(egui::RichText::new(t!("translation_repo_info.not_signed")).italics())
+                };
+                ui.end_row();
+            });
+
+        ui.add_space(4.0 * scale);
+        let mut pin_changed = None;
+        match &self.pinned_revision {
+            Some(revision) => {
+                ui.label(if revision.is_empty() {
+                    t!("translation_repo_info.frozen")
+                }
+                else {
+                    t!("translation_repo_info.pinned_to", revision = revision)
+                });
+                if ui.button(t!("translation_repo_info.unpin")).clicked() {
+                    pin_changed = Some(None);
+                }
+            }
+            None => {
+                ui.horizontal(|ui| {
+                    let _res = ui.add(egui::TextEdit::singleline(&mut self.pin_revision_input)
+                        .hint_text(t!("translation_repo_info.revision"))
+                        .desired_width(150.0 * scale));
+                    #[cfg(target_os = "android")]
+                    handle_android_keyboard(&_res, &mut self.pin_revision_input);
+                    if ui.button(t!("translation_repo_info.pin")).clicked() {
+                        pin_changed = Some(Some(self.pin_revision_input.trim().to_owned()));
+                    }
+                });
+            }
+        }
+
+        if let Some(revision) = pin_changed {
+            match tl_repo::set_pinned_revision(self.repo_id, revision.clone()) {
+                Ok(_) => self.pinned_revision = revision,
+                Err(e) => error!("{}", e)
+            }
+        }
+
+        ui.add_space(4.0 * scale);
+        if ui.button(t!("translation_repo_info.export_package")).clicked() {
+            Hachimi::instance().tl_updater.clone().export_package(self.repo_id);
+        }
+
+        if self.has_backup {
+            ui.add_space(4.0 * scale);
+            let button = ui.add_enabled(self.rollback_thread.is_none(), egui::Button::new(t!("translation_repo_info.rollback")));
+            if button.clicked() {
+                self.rollback_thread = Some(Hachimi::instance().tl_updater.clone().rollback(self.repo_id));
+            }
+        }
+    }
+}
+
+struct ImportRepoPackageWindow {
+    id: egui::Id,
+    packages: Vec<PathBuf>,
+    path_input: String
+}
+
+impl ImportRepoPackageWindow {
+    fn new() -> ImportRepoPackageWindow {
+        ImportRepoPackageWindow {
+            id: random_id(),
+            packages: tl_repo::list_repo_packages(),
+            path_input: String::new()
+        }
+    }
+}
+
+impl Window for ImportRepoPackageWindow {
+    fn run(&mut self, ctx: &egui::Context) -> bool {
+        let scale = get_scale(ctx);
+        let mut open = true;
+        let mut open2 = true;
+        let mut import_path = None;
+
+        new_window(ctx, self.id, t!("import_tl_package.title"))
+        .open(&mut open)
+        .show(ctx, |ui| {
+            simple_window_layout(ui, self.id,
+                |ui| {
+                    ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Wrap);
+                    ui.label(t!("import_tl_package.description", dir = tl_repo::REPO_PACKAGES_DIR));
+                    ui.add_space(4.0 * scale);
+
+                    if self.packages.is_empty() {
+                        ui.label(egui::RichText::new(t!("import_tl_package.no_packages")).italics());
+                    }
+                    for path in &self.packages {
+                        ui.horizontal(|ui| {
+                            if ui.button(t!("import_tl_package.import")).clicked() {
+                                import_path = Some(path.clone());
+                            }
+                            ui.label(path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default());
+                        });
+                    }
+
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        let _res = ui.add(egui::TextEdit::singleline(&mut self.path_input)
+                            .hint_text(t!("import_tl_package.path_hint"))
+                            .desired_width(ui.available_width() - 70.0 * scale));
+                        #[cfg(target_os = "android")]
+                        handle_android_keyboard(&_res, &mut self.path_input);
+                        if ui.button(t!("import_tl_package.import")).clicked() && !self.path_input.trim().is_empty() {
+                            import_path = Some(PathBuf::from(self.path_input.trim()));
+                        }
+                    });
+                },
+                |ui| {
+                    if ui.button(t!("cancel")).clicked() {
+                        open2 = false;
+                    }
+                }
+            );
+        });
+
+        if let Some(path) = import_path {
+            Hachimi::instance().tl_updater.clone().import_package(path);
+            open2 = false;
+        }
+
+        open &= open2;
+        open
+    }
+}
+
+struct TlOverridesWindow {
+    id: egui::Id,
+    entries: Vec<tl_overrides::OverrideEntry>
+}
+
+impl TlOverridesWindow {
+    fn new() -> TlOverridesWindow {
+        TlOverridesWindow {
+            id: random_id(),
+            entries: tl_overrides::list()
+        }
+    }
+}
+
+impl Window for TlOverridesWindow {
+    fn run(&mut self, ctx: &egui::Context) -> bool {
+        let scale = get_scale(ctx);
+        let mut open = true;
+        let mut open2 = true;
+        let mut refresh = false;
+
+        new_window(ctx, self.id, t!("tl_overrides.title"))
+        .open(&mut open)
+        .show(ctx, |ui| {
+            simple_window_layout(ui, self.id,
+                |ui| {
+                    ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Wrap);
+                    ui.label(t!("tl_overrides.description", dir = tl_overrides::OVERRIDES_DIR));
+                    ui.add_space(4.0 * scale);
+
+                    if self.entries.is_empty() {
+                        ui.label(egui::RichText::new(t!("tl_overrides.no_overrides")).italics());
+                        return;
+                    }
+
+                    egui::ScrollArea::vertical()
+                    .max_height(300.0 * scale)
+                    .show(ui, |ui| {
+                        egui::Grid::new(self.id.with("tl_overrides_grid"))
+                        .striped(true)
+                        .num_columns(2)
+                        .show(ui, |ui| {
+                            for entry in &self.entries {
+                                ui.label(&entry.path);
+                                ui.label(match entry.status {
+                                    tl_overrides::OverrideStatus::MergedDict(count) => t!("tl_overrides.merged_dict", count = count),
+                                    tl_overrides::OverrideStatus::ReplacedAsset => t!("tl_overrides.replaced_asset"),
+                                    tl_overrides::OverrideStatus::AddedAsset => t!("tl_overrides.added_asset"),
+                                    tl_overrides::OverrideStatus::Unused => t!("tl_overrides.unused")
+                                });
+                                ui.end_row();
+                            }
+                        });
+                    });
+                },
+                |ui| {
+                    if ui.button(t!("done")).clicked() {
+                        open2 = false;
+                    }
+                    if ui.button(t!("menu.reload_localized_data")).clicked() {
+                        Hachimi::instance().load_localized_data();
+                        refresh = true;
+                    }
+                }
+            );
+        });
+
+        if refresh {
+            self.entries = tl_overrides::list();
+        }
+
+        open &= open2;
+        open
+    }
+}
+
 pub struct TranslationRepoUpdateWindow {
     title: String,
     content: String,
@@ -4806,7 +5438,7 @@ impl TranslationRepoUpdateWindow {
 
         std::thread::spawn(move || {
             let agent = ureq::Agent::new_with_config(ureq_config());
-            let result = match agent.get(&url_cloned).call() {
+            let result = match agent.get(rewrite_url(&url_cloned).as_ref()).call() {
                 Ok(res) => {
                     match res.into_body().read_to_string() {
                         Ok(text) => {
@@ -5043,6 +5675,75 @@ impl Window for AboutWindow {
     }
 }
 
+struct CoverageReportWindow {
+    id: egui::Id,
+    report: CoverageReport,
+    // Error message if the report files couldn't be written
+    save_result: Result<PathBuf, String>
+}
+
+impl CoverageReportWindow {
+    fn new(report: CoverageReport, save_result: Result<PathBuf, String>) -> CoverageReportWindow {
+        CoverageReportWindow {
+            id: random_id(),
+            report,
+            save_result
+        }
+    }
+
+    fn coverage_row(ui: &mut egui::Ui, label: &str, translated: usize, total: usize, unused: usize) {
+        let percentage = coverage::percentage(translated, total);
+        ui.label(label);
+        ui.add(
+            egui::ProgressBar::new(percentage / 100.0)
+                .text(format!("{}/{} ({:.1}%)", translated, total, percentage))
+        );
+        ui.label(unused.to_string());
+        ui.end_row();
+    }
+}
+
+impl Window for CoverageReportWindow {
+    fn run(&mut self, ctx: &egui::Context) -> bool {
+        let scale = get_scale(ctx);
+        let mut open = true;
+
+        new_window(ctx, self.id, t!("coverage_report.title"))
+        .max_width(420.0 * scale)
+        .open(&mut open)
+        .show(ctx, |ui| {
+            match &self.save_result {
+                Ok(_) => ui.label(t!("coverage_report.saved")),
+                Err(e) => ui.label(t!("coverage_report.save_failed", reason = e.to_string()))
+            };
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                egui::Grid::new(self.id.with("summary")).striped(true).num_columns(3).show(ui, |ui| {
+                    ui.strong(t!("coverage_report.dict"));
+                    ui.strong(t!("coverage_report.coverage"));
+                    ui.strong(t!("coverage_report.unused"));
+                    ui.end_row();
+
+                    for section in &self.report.sections {
+                        Self::coverage_row(ui, section.name, section.translated, section.total, section.unused);
+                    }
+                });
+
+                for section in self.report.sections.iter().filter(|s| !s.categories.is_empty()) {
+                    ui.collapsing(section.name, |ui| {
+                        egui::Grid::new(self.id.with(section.name)).striped(true).num_columns(3).show(ui, |ui| {
+                            for category in &section.categories {
+                                Self::coverage_row(ui, &category.id.to_string(), category.translated, category.total, category.unused);
+                            }
+                        });
+                    });
+                }
+            });
+        });
+
+        open
+    }
+}
+
 struct LicenseWindow {
     id: egui::Id
 }
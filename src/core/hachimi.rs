@@ -1,4 +1,4 @@
-use std::{fs, path::{Path, PathBuf}, process, sync::{atomic::{self, AtomicBool, AtomicI32}, Arc, Mutex}, time::{Duration, Instant}};
+use std::{fs, hash::Hash, path::{Path, PathBuf}, process, sync::{atomic::{self, AtomicBool, AtomicI32}, Arc, Mutex}, time::{Duration, Instant}};
 use arc_swap::ArcSwap;
 use fnv::{FnvHashMap, FnvHashSet};
 use once_cell::sync::OnceCell;
@@ -7,7 +7,7 @@ use textwrap::wrap_algorithms::Penalties;
 
 use crate::{core::{gui, plugin_api::Plugin, updater}, gui_impl, hachimi_impl, il2cpp::{self, hook::umamusume::{CySpringController::SpringUpdateMode, GameSystem}, sql::{CharacterData, SkillInfo}}};
 
-use super::{game::{Game, Region}, ipc, plurals, template, template_filters, tl_repo, utils, Error, Interceptor};
+use super::{game::{Game, Region}, ipc, ld_watcher, mt_cache::MtCache, plugin_events, plurals, template, template_filters, texture_diff_cache, tl_overrides, tl_repo, translator::TranslationBackendKind, update_policy, utils, Error, Interceptor};
 
 pub const REPO_PATH: &str = "kairusds/Hachimi-Edge";
 pub const GITHUB_API: &str = "https://api.github.com/repos";
@@ -166,7 +166,7 @@ impl Hachimi {
             skill_info: ArcSwap::default(),
 
             game,
-            template_parser: template::Parser::new(&template_filters::LIST),
+            template_parser: template::Parser::new(&template_filters::LIST).with_dynamic_filter(template_filters::repo_filter),
 
             target_fps: AtomicI32::new(config.target_fps.unwrap_or(-1)),
 
@@ -214,6 +214,8 @@ impl Hachimi {
 
         new_config.language.set_locale();
         self.config.store(Arc::new(new_config));
+        // Pending entries are saved with the new cache limits
+        std::thread::spawn(MtCache::flush);
 
         if Hachimi::is_initialized() && self.hooking_finished.load(atomic::Ordering::Relaxed) {
             Hachimi::instance().start_translation_updater_thread();
@@ -240,6 +242,10 @@ impl Hachimi {
             self.load_localized_data();
             gui::request_notification(gui::NotificationRequest::TLRepoChanged);
         }
+        else {
+            ld_watcher::update();
+        }
+        std::thread::spawn(MtCache::flush);
 
         if Hachimi::is_initialized() && self.hooking_finished.load(atomic::Ordering::Relaxed) {
             Hachimi::instance().start_translation_updater_thread();
@@ -253,6 +259,42 @@ impl Hachimi {
         Some(self.get_repo_dir(id))
     }
 
+    /// The selected repo followed by its fallback repos, highest priority first.
+    pub fn get_active_tl_repos(&self) -> Vec<tl_repo::RepoEntry> {
+        let config = self.config.load();
+        let mut chain = self.tl_repo_manager.lock().unwrap().active_chain(config.selected_tl_repo_id);
+
+        // Selected repo that hasn't been registered yet (pre-migration configs)
+        if let (Some(id), Some(index)) = (config.selected_tl_repo_id, &config.translation_repo_index) {
+            if !chain.iter().any(|r| r.id == id) {
+                chain.insert(0, tl_repo::RepoEntry { id, index: index.clone(), ..Default::default() });
+            }
+        }
+
+        chain
+    }
+
+    /// Localized data dirs in priority order: the active repos, then the legacy localized_data_dir.
+    pub fn get_active_tl_dirs(&self) -> Vec<PathBuf> {
+        let config = self.config.load();
+        // The selected repo is always loaded so a missing folder still shows up as such
+        let mut dirs: Vec<PathBuf> = self.get_active_tl_dir().into_iter().collect();
+        for repo in self.get_active_tl_repos() {
+            let dir = self.get_repo_dir(repo.id);
+            if Some(repo.id) != config.selected_tl_repo_id && dir.is_dir() {
+                dirs.push(dir);
+            }
+        }
+
+        if let Some(legacy_dir) = config.localized_data_dir.as_ref().map(|p| self.game.data_dir.join(p)) {
+            if (dirs.is_empty() || legacy_dir.is_dir()) && !dirs.contains(&legacy_dir) {
+                dirs.push(legacy_dir);
+            }
+        }
+
+        dirs
+    }
+
     pub fn load_localized_data(&self) {
         if self.tl_updater.progress().is_some() {
             warn!("Update in progress, not loading localized data");
@@ -260,18 +302,25 @@ impl Hachimi {
         }
 
         let config = self.config.load();
-        let ld_path = self.get_active_tl_dir().or_else(|| {
-            config.localized_data_dir.as_ref().map(|p| self.game.data_dir.join(p))
-        });
+        let ld_paths = self.get_active_tl_dirs();
 
-        let new_data = match LocalizedData::new(&self.config.load(), ld_path) {
+        let overrides_path = self.get_data_path(tl_overrides::OVERRIDES_DIR);
+        let new_data = match LocalizedData::new(&self.config.load(), ld_paths, overrides_path) {
             Ok(v) => v,
             Err(e) => {
                 error!("Failed to load localized data: {}", e);
                 return;
             }
         };
+        if config.translator_mode {
+            for issue in new_data.validate_plural_templates(&self.template_parser) {
+                warn!("{}", issue);
+            }
+        }
         self.localized_data.store(Arc::new(new_data));
+        texture_diff_cache::clear_keys();
+        ld_watcher::update();
+        plugin_events::emit_localized_data_reloaded();
     }
 
     pub fn init_character_data(&self) {
@@ -377,12 +426,13 @@ impl Hachimi {
         }
 
         if config.enable_ipc {
-            ipc::start_http(config.ipc_listen_all);
+            ipc::start_http(config.ipc_listen_all, config.ipc_port);
         }
 
         hachimi_impl::on_hooking_finished(self);
 
         Hachimi::instance().start_translation_updater_thread();
+        texture_diff_cache::collect_garbage();
 
         for plugin in self.plugins.lock().unwrap().iter() {
             info!("Initializing plugin: {}", plugin.name);
@@ -410,7 +460,8 @@ impl Hachimi {
     fn repair_tl_repo_state(&self) -> Result<(), Error> {
         let repos_path = self.get_data_path(".tl_repos");
         let old_data_dir = self.game.data_dir.join("localized_data");
-        let mut manager = self.tl_repo_manager.lock().unwrap();
+        // Work on a copy, config reloads read the repo list through the mutex
+        let mut manager = self.tl_repo_manager.lock().unwrap().clone();
 
         if !repos_path.exists() && old_data_dir.is_dir() {
             info!("Found legacy 'localized_data' folder and no .tl_repos; migrating…");
@@ -428,7 +479,7 @@ impl Hachimi {
             }
         }
 
-        *manager = if repos_path.exists() {
+        manager = if repos_path.exists() {
             tl_repo::RepoList::load(&repos_path).unwrap_or_else(|e| {
                 warn!("Failed to load .tl_repos ({e}); starting fresh");
                 tl_repo::RepoList::default()
@@ -441,7 +492,8 @@ impl Hachimi {
         let index = config.translation_repo_index.clone();
         let current_id = config.selected_tl_repo_id;
 
-        let mut manager_dirty = false;
+        let mut manager_dirty = manager.prune_fallbacks();
+        *self.tl_repo_manager.lock().unwrap() = manager.clone();
 
         match current_id {
             Some(id) => {
@@ -497,6 +549,7 @@ impl Hachimi {
         if manager_dirty {
             manager.save(&repos_path)?;
         }
+        *self.tl_repo_manager.lock().unwrap() = manager;
 
         if let Some(id) = self.config.load().selected_tl_repo_id {
             let old_cache = self.get_data_path(".tl_repo_cache");
@@ -526,6 +579,9 @@ impl Hachimi {
     }
 
     pub fn start_translation_updater_thread(self: Arc<Self>) {
+        // How often a deferred check looks at the conditions again
+        const DEFERRED_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
         let mut cmd_lock = self.tl_update_cmd.lock().unwrap();
 
         // drop the old sender to signal the existing thread to exit.
@@ -551,6 +607,9 @@ impl Hachimi {
             .spawn(move || {
                 let mut next_check = Instant::now() + interval;
                 let mut last_interval = interval;
+                let mut last_check = Instant::now();
+                let mut last_failures = self.tl_updater.failure_count();
+                let mut deferred = false;
 
                 loop {
                     let config = self.config.load();
@@ -575,11 +634,32 @@ impl Hachimi {
                         continue;
                     }
 
+                    // back off exponentially while checks or updates keep failing
+                    let failures = self.tl_updater.failure_count();
+                    if failures != last_failures {
+                        last_failures = failures;
+                        next_check = last_check + update_policy::backoff_delay(interval, failures);
+                        if failures > 0 {
+                            info!("Translation update failed {} time(s) in a row, backing off", failures);
+                        }
+                    }
+
                     if Instant::now() >= next_check {
-                        let silent = config.tl_auto_updater_mode == TLAutoUpdaterMode::Silent;
-                        info!("Running translation updater check (Silent: {})...", silent);
-                        self.tl_updater.clone().check_for_updates(false, silent);
-                        next_check = Instant::now() + interval;
+                        if let Some(reason) = update_policy::deferral_reason(&config) {
+                            if !deferred {
+                                info!("Deferring translation updater check: {}", reason);
+                                deferred = true;
+                            }
+                            next_check = Instant::now() + DEFERRED_CHECK_INTERVAL;
+                        }
+                        else {
+                            deferred = false;
+                            let silent = config.tl_auto_updater_mode == TLAutoUpdaterMode::Silent;
+                            info!("Running translation updater check (Silent: {})...", silent);
+                            self.tl_updater.clone().check_for_updates(false, silent);
+                            last_check = Instant::now();
+                            next_check = last_check + update_policy::backoff_delay(interval, failures);
+                        }
                     }
 
                     // interruptible sleep. wakes at least once/sec,
@@ -614,6 +694,13 @@ impl Default for TLAutoUpdaterMode {
     fn default() -> Self { Self::Disabled }
 }
 
+/// Rewrites urls starting with `from` to start with `to` instead. The first matching rule wins.
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub struct MirrorRule {
+    pub from: String,
+    pub to: String
+}
+
 #[derive(Deserialize, Serialize, Clone)]
 pub struct CaptionConfig {
     #[serde(default)]
@@ -663,6 +750,40 @@ impl CaptionConfig {
     fn default_pos_y() -> f32 { -3.0 }
 }
 
+#[derive(Deserialize, Serialize, Clone, PartialEq)]
+pub struct MtConfig {
+    #[serde(default)]
+    pub mt_backend: TranslationBackendKind,
+    // Falls back to sugoi_url for the Sugoi backend
+    pub mt_url: Option<String>,
+    pub mt_api_key: Option<String>,
+    pub mt_model: Option<String>,
+    pub mt_system_prompt: Option<String>,
+    #[serde(default = "MtConfig::default_source_lang")]
+    pub mt_source_lang: String,
+    #[serde(default = "MtConfig::default_target_lang")]
+    pub mt_target_lang: String
+}
+
+impl Default for MtConfig {
+    fn default() -> Self {
+        Self {
+            mt_backend: TranslationBackendKind::default(),
+            mt_url: None,
+            mt_api_key: None,
+            mt_model: None,
+            mt_system_prompt: None,
+            mt_source_lang: "ja".to_owned(),
+            mt_target_lang: "en".to_owned()
+        }
+    }
+}
+
+impl MtConfig {
+    fn default_source_lang() -> String { "ja".to_owned() }
+    fn default_target_lang() -> String { "en".to_owned() }
+}
+
 #[derive(Deserialize, Serialize, Clone)]
 pub struct Config {
     #[serde(default)]
@@ -674,10 +795,14 @@ pub struct Config {
     #[serde(default)]
     pub translator_mode: bool,
     #[serde(default)]
+    pub hot_reload_localized_data: bool,
+    #[serde(default)]
+    pub dump_assets: bool,
+    #[serde(default)]
     pub disable_gui: bool,
     #[serde(default)]
     pub disable_gui_once: bool,
-    // legacy fallback path. populated by old versions, new code uses selected_tl_repo_id + get_active_tl_dir() exclusively
+    // legacy path, loaded as the lowest priority layer. populated by old versions, new code uses selected_tl_repo_id + get_active_tl_dir()
     // do NOT write this in new code
     pub localized_data_dir: Option<String>,
     pub target_fps: Option<i32>,
@@ -694,6 +819,8 @@ pub struct Config {
     pub lazy_translation_updates: bool,
     #[serde(default)]
     pub etag_translation_updates: bool,
+    #[serde(default = "Config::default_delta_translation_updates")]
+    pub delta_translation_updates: bool,
     #[serde(default)]
     pub disable_auto_update_check: bool,
 
@@ -701,6 +828,14 @@ pub struct Config {
     pub tl_auto_updater_mode: TLAutoUpdaterMode,
     #[serde(default = "Config::default_tl_auto_updater_interval_sec")]
     pub tl_auto_updater_interval_sec: u64,
+    #[serde(default = "Config::default_tl_auto_updater_avoid_busy_scenes")]
+    pub tl_auto_updater_avoid_busy_scenes: bool,
+    #[serde(default)]
+    pub tl_auto_updater_quiet_hours: bool,
+    #[serde(default = "Config::default_tl_auto_updater_quiet_start_hour")]
+    pub tl_auto_updater_quiet_start_hour: u32,
+    #[serde(default = "Config::default_tl_auto_updater_quiet_end_hour")]
+    pub tl_auto_updater_quiet_end_hour: u32,
 
     #[serde(default)]
     pub disable_translations: bool,
@@ -726,6 +861,11 @@ pub struct Config {
     pub enable_ipc: bool,
     #[serde(default)]
     pub ipc_listen_all: bool,
+    #[serde(default = "Config::default_ipc_port")]
+    pub ipc_port: u16,
+    pub ipc_token: Option<String>,
+    #[serde(default)]
+    pub ipc_allowed_addresses: Vec<String>,
     #[serde(default)]
     pub force_allow_dynamic_camera: bool,
     #[serde(default)]
@@ -737,6 +877,12 @@ pub struct Config {
     #[serde(default)]
     pub homescreen_bgseason: crate::il2cpp::hook::umamusume::TimeUtil::BgSeason,
     pub sugoi_url: Option<String>,
+    #[serde(flatten)]
+    pub mt: MtConfig,
+    #[serde(default = "Config::default_mt_cache_enabled")]
+    pub mt_cache_enabled: bool,
+    #[serde(default = "Config::default_mt_cache_max_entries")]
+    pub mt_cache_max_entries: usize,
     #[serde(default)]
     pub auto_translate_stories: bool,
     #[serde(default)]
@@ -753,6 +899,12 @@ pub struct Config {
     pub meta_index_url: String,
     #[serde(default)]
     pub ipv4_only: bool,
+    #[serde(default)]
+    pub http_proxy: Option<String>,
+    #[serde(default)]
+    pub http_ca_bundle: Option<String>,
+    #[serde(default)]
+    pub mirror_rules: Vec<MirrorRule>,
     pub physics_update_mode: Option<SpringUpdateMode>,
     #[serde(default)]
     pub cyspring_mono_uncap_frame_scale: bool,
@@ -818,6 +970,13 @@ impl Config {
     pub fn default_text_color() -> egui::Color32 { egui::Color32::from_gray(170) }
     pub fn default_window_rounding() -> f32 { 10.0 }
     fn default_tl_auto_updater_interval_sec() -> u64 { 3600 }
+    fn default_tl_auto_updater_avoid_busy_scenes() -> bool { true }
+    fn default_tl_auto_updater_quiet_start_hour() -> u32 { 0 }
+    fn default_tl_auto_updater_quiet_end_hour() -> u32 { 7 }
+    fn default_delta_translation_updates() -> bool { true }
+    fn default_ipc_port() -> u16 { 50433 }
+    fn default_mt_cache_enabled() -> bool { true }
+    fn default_mt_cache_max_entries() -> usize { 100000 }
 }
 
 impl Default for Config {
@@ -943,7 +1102,7 @@ impl Language {
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct LocalizedData {
     pub config: LocalizedDataConfig,
     path: Option<PathBuf>,
@@ -954,7 +1113,13 @@ pub struct LocalizedData {
     pub character_system_text_dict: FnvHashMap<i32, FnvHashMap<i32, String>>, // {"character_id": {"voice_id": "text"}}
     pub race_jikkyo_comment_dict: FnvHashMap<i32, String>, // {"id": "text"}
     pub race_jikkyo_message_dict: FnvHashMap<i32, String>, // {"id": "text"}
-    assets_path: Option<PathBuf>,
+    pub glossary: FnvHashMap<String, String>, // {"source term": "translation"}
+    // One per layer, highest priority first
+    assets_paths: Vec<PathBuf>,
+    // User overrides, applied on top of every layer
+    overrides_path: Option<PathBuf>,
+    overrides_assets_path: Option<PathBuf>,
+    asset_exists_cache: AssetExistsCache,
 
     pub plural_form: plurals::Resolver,
     pub ordinal_form: plurals::Resolver,
@@ -962,13 +1127,39 @@ pub struct LocalizedData {
     pub wrapper_penalties: Penalties
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+// Results of the asset lookups across layers. Updates reload the localized data and hot reloaded
+// asset changes clear it, so this lives as long as the LocalizedData.
+#[derive(Default)]
+struct AssetExistsCache(Mutex<FnvHashMap<PathBuf, bool>>);
+
+impl Clone for AssetExistsCache {
+    fn clone(&self) -> Self {
+        // Clones can have different layers
+        AssetExistsCache::default()
+    }
+}
+
+fn merge_fallback_dict<K: Eq + Hash, V>(dict: &mut FnvHashMap<K, V>, fallback: FnvHashMap<K, V>) {
+    for (key, value) in fallback {
+        dict.entry(key).or_insert(value);
+    }
+}
+
+fn merge_fallback_nested_dict<K: Eq + Hash, K2: Eq + Hash, V>(
+    dict: &mut FnvHashMap<K, FnvHashMap<K2, V>>, fallback: FnvHashMap<K, FnvHashMap<K2, V>>
+) {
+    for (key, entries) in fallback {
+        merge_fallback_dict(dict.entry(key).or_default(), entries);
+    }
+}
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct CustomRubyBlock {
     pub block_index: i32,
     pub rubies: Vec<CustomRubyDef>,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct CustomRubyDef {
     pub char_x: f32,
     pub char_y: f32,
@@ -976,33 +1167,161 @@ pub struct CustomRubyDef {
 }
 
 impl LocalizedData {
-    fn new(config: &Config, ld_path: Option<PathBuf>) -> Result<LocalizedData, Error> {
+    fn new(config: &Config, ld_paths: Vec<PathBuf>, overrides_path: PathBuf) -> Result<LocalizedData, Error> {
         if config.disable_translations {
             return Ok(LocalizedData::default());
         }
 
+        let mut data = Self::load_layers(ld_paths)?;
+        if overrides_path.is_dir() {
+            data.apply_overrides(overrides_path);
+        }
+        Ok(data)
+    }
+
+    /// Applies a user override dir that mirrors the layout of the first layer.
+    ///
+    /// Dict entries in it replace the loaded ones key by key and asset files take priority over
+    /// the ones in every layer.
+    fn apply_overrides(&mut self, overrides_path: PathBuf) {
+        fn overlay<T: DeserializeOwned>(dict: &mut T, overrides_path: &Path, rel_path: Option<&String>, merge: fn(&mut T, T)) {
+            let Some(overrides) = LocalizedData::load_dict_static_ex::<T, _>(Some(overrides_path), rel_path, true) else {
+                return;
+            };
+            let base = std::mem::replace(dict, overrides);
+            merge(dict, base);
+        }
+
+        let config = &self.config;
+        overlay(&mut self.localize_dict, &overrides_path, config.localize_dict.as_ref(), merge_fallback_dict);
+        overlay(&mut self.hashed_dict, &overrides_path, config.hashed_dict.as_ref(), merge_fallback_dict);
+        overlay(&mut self.text_data_dict, &overrides_path, config.text_data_dict.as_ref(), merge_fallback_nested_dict);
+        overlay(&mut self.character_system_text_dict, &overrides_path, config.character_system_text_dict.as_ref(), merge_fallback_nested_dict);
+        overlay(&mut self.race_jikkyo_comment_dict, &overrides_path, config.race_jikkyo_comment_dict.as_ref(), merge_fallback_dict);
+        overlay(&mut self.race_jikkyo_message_dict, &overrides_path, config.race_jikkyo_message_dict.as_ref(), merge_fallback_dict);
+        overlay(&mut self.glossary, &overrides_path, config.glossary.as_ref(), merge_fallback_dict);
+
+        self.overrides_assets_path = config.assets_dir.as_ref().map(|dir| overrides_path.join(dir));
+        self.overrides_path = Some(overrides_path);
+    }
+
+    /// Loads several localized data dirs as layers, earlier dirs take priority.
+    ///
+    /// Dict entries fall through to lower layers key by key and asset files fall through file by
+    /// file. Everything else (config, plural rules, wrapper settings) comes from the first layer.
+    pub fn load_layers(ld_paths: Vec<PathBuf>) -> Result<LocalizedData, Error> {
+        let mut paths = ld_paths.into_iter();
+        let Some(first) = paths.next() else {
+            return Self::load(None);
+        };
+
+        let mut data = Self::load(Some(first))?;
+        for path in paths {
+            match Self::load(Some(path.clone())) {
+                Ok(layer) => data.add_fallback_layer(layer),
+                Err(e) => error!("Failed to load fallback localized data '{}': {}", path.display(), e)
+            }
+        }
+
+        Ok(data)
+    }
+
+    fn add_fallback_layer(&mut self, layer: LocalizedData) {
+        merge_fallback_dict(&mut self.localize_dict, layer.localize_dict);
+        merge_fallback_dict(&mut self.hashed_dict, layer.hashed_dict);
+        merge_fallback_nested_dict(&mut self.text_data_dict, layer.text_data_dict);
+        merge_fallback_nested_dict(&mut self.character_system_text_dict, layer.character_system_text_dict);
+        merge_fallback_dict(&mut self.race_jikkyo_comment_dict, layer.race_jikkyo_comment_dict);
+        merge_fallback_dict(&mut self.race_jikkyo_message_dict, layer.race_jikkyo_message_dict);
+        merge_fallback_dict(&mut self.glossary, layer.glossary);
+        merge_fallback_nested_dict(&mut self.config.filter_tables, layer.config.filter_tables);
+        self.assets_paths.extend(layer.assets_paths);
+    }
+
+    /// Reloads a single dict from every layer, leaving the rest of the data as is.
+    ///
+    /// `rel_path` is relative to the first layer and is matched against its config.
+    /// Returns false if it isn't one of the dicts.
+    pub fn reload_dict(&mut self, ld_paths: &[PathBuf], rel_path: &Path) -> bool {
+        type GetPath = fn(&LocalizedDataConfig) -> Option<&String>;
+
+        // (path, config, is_override)
+        fn reload<T: DeserializeOwned + Default>(
+            layers: &[(&PathBuf, LocalizedDataConfig, bool)], get_path: GetPath, merge: fn(&mut T, T)
+        ) -> T {
+            let mut dicts = layers.iter().map(|(path, config, is_override)|
+                LocalizedData::load_dict_static_ex(Some(path.as_path()), get_path(config), *is_override).unwrap_or_default()
+            );
+            let mut dict = dicts.next().unwrap_or_default();
+            for fallback in dicts {
+                merge(&mut dict, fallback);
+            }
+            dict
+        }
+
+        let matches = |get_path: GetPath| get_path(&self.config).is_some_and(|p| Path::new(p) == rel_path);
+        // Overrides follow the layout of the first layer
+        let layers: Vec<_> = self.overrides_path.iter()
+            .map(|path| (path, self.config.clone(), true))
+            .chain(ld_paths.iter().map(|path| (path, Self::load_config(path).unwrap_or_default(), false)))
+            .collect();
+
+        if matches(|c| c.localize_dict.as_ref()) {
+            self.localize_dict = reload(&layers, |c| c.localize_dict.as_ref(), merge_fallback_dict);
+        }
+        else if matches(|c| c.hashed_dict.as_ref()) {
+            self.hashed_dict = reload(&layers, |c| c.hashed_dict.as_ref(), merge_fallback_dict);
+        }
+        else if matches(|c| c.text_data_dict.as_ref()) {
+            self.text_data_dict = reload(&layers, |c| c.text_data_dict.as_ref(), merge_fallback_nested_dict);
+        }
+        else if matches(|c| c.character_system_text_dict.as_ref()) {
+            self.character_system_text_dict = reload(&layers, |c| c.character_system_text_dict.as_ref(), merge_fallback_nested_dict);
+        }
+        else if matches(|c| c.race_jikkyo_comment_dict.as_ref()) {
+            self.race_jikkyo_comment_dict = reload(&layers, |c| c.race_jikkyo_comment_dict.as_ref(), merge_fallback_dict);
+        }
+        else if matches(|c| c.race_jikkyo_message_dict.as_ref()) {
+            self.race_jikkyo_message_dict = reload(&layers, |c| c.race_jikkyo_message_dict.as_ref(), merge_fallback_dict);
+        }
+        else if matches(|c| c.glossary.as_ref()) {
+            self.glossary = reload(&layers, |c| c.glossary.as_ref(), merge_fallback_dict);
+        }
+        else {
+            return false;
+        }
+
+        true
+    }
+
+    fn load_config(ld_path: &Path) -> Result<LocalizedDataConfig, Error> {
+        let ld_config_path = ld_path.join("config.json");
+        if fs::metadata(&ld_config_path).is_ok() {
+            let json = fs::read_to_string(&ld_config_path)?;
+            Ok(serde_json::from_str(&json)?)
+        }
+        else {
+            warn!("Localized data config not found");
+            Ok(LocalizedDataConfig::default())
+        }
+    }
+
+    /// Loads the localized data dir at the path (or the defaults if there's none).
+    pub fn load(ld_path: Option<PathBuf>) -> Result<LocalizedData, Error> {
         let path = ld_path;
         let config: LocalizedDataConfig = if let Some(ref p) = path {
             // Create .nomedia
             #[cfg(target_os = "android")]
             { _ = fs::OpenOptions::new().create_new(true).write(true).open(p.join(".nomedia")); }
 
-            let ld_config_path = p.join("config.json");
-            if fs::metadata(&ld_config_path).is_ok() {
-                let json = fs::read_to_string(&ld_config_path)?;
-                serde_json::from_str(&json)?
-            }
-            else {
-                warn!("Localized data config not found");
-                LocalizedDataConfig::default()
-            }
+            Self::load_config(p)?
         }
         else {
             LocalizedDataConfig::default()
         };
 
-        let plural_form = Self::parse_plural_form_or_default(&config.plural_form)?;
-        let ordinal_form = Self::parse_plural_form_or_default(&config.ordinal_form)?;
+        let plural_form = Self::parse_plural_form_or_default(&config.plural_form, false)?;
+        let ordinal_form = Self::parse_plural_form_or_default(&config.ordinal_form, true)?;
 
         let wrapper_penalties = Self::parse_wrap_penalties_or_default(&config.wrapper_penalties);
 
@@ -1013,11 +1332,14 @@ impl LocalizedData {
             character_system_text_dict: Self::load_dict_static(&path, config.character_system_text_dict.as_ref()).unwrap_or_default(),
             race_jikkyo_comment_dict: Self::load_dict_static(&path, config.race_jikkyo_comment_dict.as_ref()).unwrap_or_default(),
             race_jikkyo_message_dict: Self::load_dict_static(&path, config.race_jikkyo_message_dict.as_ref()).unwrap_or_default(),
-            assets_path: path.as_ref()
-                .map(|p| config.assets_dir.as_ref()
-                    .map(|dir| p.join(dir))
-                )
-                .unwrap_or_default(),
+            glossary: Self::load_dict_static(&path, config.glossary.as_ref()).unwrap_or_default(),
+            assets_paths: path.as_ref()
+                .and_then(|p| config.assets_dir.as_ref().map(|dir| p.join(dir)))
+                .into_iter()
+                .collect(),
+            overrides_path: None,
+            overrides_assets_path: None,
+            asset_exists_cache: AssetExistsCache::default(),
 
             plural_form,
             ordinal_form,
@@ -1029,7 +1351,7 @@ impl LocalizedData {
         })
     }
 
-    fn load_dict_static_ex<T: DeserializeOwned, P: AsRef<Path>>(ld_path_opt: &Option<PathBuf>, rel_path_opt: Option<P>, silent_fs_error: bool) -> Option<T> {
+    fn load_dict_static_ex<T: DeserializeOwned, P: AsRef<Path>>(ld_path_opt: Option<&Path>, rel_path_opt: Option<P>, silent_fs_error: bool) -> Option<T> {
         let Some(ld_path) = ld_path_opt else {
             return None;
         };
@@ -1060,7 +1382,7 @@ impl LocalizedData {
     }
 
     fn load_dict_static<T: DeserializeOwned, P: AsRef<Path>>(ld_path_opt: &Option<PathBuf>, rel_path_opt: Option<P>) -> Option<T> {
-        Self::load_dict_static_ex(ld_path_opt, rel_path_opt, false)
+        Self::load_dict_static_ex(ld_path_opt.as_deref(), rel_path_opt, false)
     }
 
     pub fn load_dict<T: DeserializeOwned, P: AsRef<Path>>(&self, rel_path_opt: Option<P>) -> Option<T> {
@@ -1068,19 +1390,101 @@ impl LocalizedData {
     }
 
     pub fn load_assets_dict<T: DeserializeOwned, P: AsRef<Path>>(&self, rel_path_opt: Option<P>) -> Option<T> {
-        Self::load_dict_static_ex(&self.assets_path, rel_path_opt, true)
+        let rel_path = rel_path_opt?;
+        self.overrides_assets_path.iter().chain(self.assets_paths.iter()).find_map(|assets_path| {
+            Self::load_dict_static_ex(Some(assets_path), Some(rel_path.as_ref()), true)
+        })
     }
 
-    fn parse_plural_form_or_default(opt: &Option<String>) -> Result<plurals::Resolver, Error> {
+    fn parse_plural_form_or_default(opt: &Option<String>, ordinal: bool) -> Result<plurals::Resolver, Error> {
         if let Some(plural_form) = opt {
-            Ok(plurals::Resolver::Expr(plurals::Ast::parse(plural_form)?))
+            plurals::Resolver::parse(plural_form, ordinal)
         }
         else {
             Ok(plurals::Resolver::Function(|_| 0))
         }
     }
 
-    fn parse_wrap_penalties_or_default(opt: &Option<PenaltiesConfig>) -> Penalties {
+    /// Checks that every plural template in the loaded dicts has as many forms as the plural rules,
+    /// and that ordinal_types has one entry per ordinal form. Returns a description of each problem.
+    pub fn validate_plural_templates(&self, parser: &template::Parser) -> Vec<String> {
+        struct PluralCheckContext {
+            form_counts: Vec<usize>
+        }
+
+        impl template::Context for PluralCheckContext {
+            fn on_filter_eval(&mut self, name: &str, args: &[template::Token]) -> Option<String> {
+                if name == "plural" {
+                    self.form_counts.push(args.len().saturating_sub(1));
+                }
+                Some(String::new())
+            }
+
+            fn get_variable(&mut self, _name: &str) -> Option<template::Token> {
+                Some(template::Token::NumberLit(0.0))
+            }
+        }
+
+        let mut issues = Vec::new();
+        let plural_count = self.plural_form.form_count();
+        let mut check = |location: &dyn Fn() -> String, text: &str| {
+            if !text.contains("$(") {
+                return;
+            }
+
+            let mut context = PluralCheckContext { form_counts: Vec::new() };
+            parser.eval_with_context(text, &mut context);
+            for count in context.form_counts {
+                if count != plural_count {
+                    issues.push(format!(
+                        "{}: plural template has {} forms, expected {}", location(), count, plural_count
+                    ));
+                }
+            }
+        };
+
+        for (key, text) in &self.localize_dict {
+            check(&|| format!("localize_dict/{}", key), text);
+        }
+        for (hash, text) in &self.hashed_dict {
+            check(&|| format!("hashed_dict/{}", hash), text);
+        }
+        for (category, entries) in &self.text_data_dict {
+            for (index, text) in entries {
+                check(&|| format!("text_data_dict/{}/{}", category, index), text);
+            }
+        }
+        for (character_id, entries) in &self.character_system_text_dict {
+            for (voice_id, text) in entries {
+                check(&|| format!("character_system_text_dict/{}/{}", character_id, voice_id), text);
+            }
+        }
+        for (id, text) in &self.race_jikkyo_comment_dict {
+            check(&|| format!("race_jikkyo_comment_dict/{}", id), text);
+        }
+        for (id, text) in &self.race_jikkyo_message_dict {
+            check(&|| format!("race_jikkyo_message_dict/{}", id), text);
+        }
+
+        let ordinal_count = self.ordinal_form.form_count();
+        if !self.config.ordinal_types.is_empty() && self.config.ordinal_types.len() != ordinal_count {
+            issues.push(format!(
+                "ordinal_types has {} entries, expected {}", self.config.ordinal_types.len(), ordinal_count
+            ));
+        }
+
+        issues
+    }
+
+    pub fn get_path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn get_overrides_path(&self) -> Option<&Path> {
+        self.overrides_path.as_deref()
+    }
+
+    pub fn parse_wrap_penalties_or_default(opt: &Option<PenaltiesConfig>) -> Penalties {
         let Some(cfg) = opt else {
             return Penalties::new()
         };
@@ -1093,8 +1497,53 @@ impl LocalizedData {
         }
     }
 
+    /// Returns the path of the asset in the user overrides or the highest priority layer that has it
+    /// (or its texture diff), or the path in the first layer if none of them do.
     pub fn get_assets_path<P: AsRef<Path>>(&self, rel_path: P) -> Option<PathBuf> {
-        self.assets_path.as_ref().map(|p| p.join(rel_path))
+        let rel_path = rel_path.as_ref();
+        if let Some(path) = self.overrides_assets_path.as_ref().map(|p| p.join(rel_path)) {
+            if self.cached_asset_exists(&path) {
+                return Some(path);
+            }
+        }
+        self.get_repo_assets_path(rel_path)
+    }
+
+    /// Same as [`get_assets_path`](Self::get_assets_path), but ignores the user overrides.
+    pub fn get_repo_assets_path<P: AsRef<Path>>(&self, rel_path: P) -> Option<PathBuf> {
+        let rel_path = rel_path.as_ref();
+        let first = self.assets_paths.first()?.join(rel_path);
+        if self.assets_paths.len() == 1 {
+            return Some(first);
+        }
+
+        for assets_path in &self.assets_paths {
+            let path = assets_path.join(rel_path);
+            if self.cached_asset_exists(&path) {
+                return Some(path);
+            }
+        }
+        Some(first)
+    }
+
+    /// Whether an asset exists at the path, including textures that only ship a diff.
+    pub fn asset_exists(path: &Path) -> bool {
+        // Textures might only ship a diff
+        path.exists() || path.with_extension("diff.png").is_file() || path.with_extension("diff.json").is_file()
+    }
+
+    /// Forgets which layers have which assets, for when asset files change without a reload.
+    pub fn clear_asset_cache(&self) {
+        self.asset_exists_cache.0.lock().unwrap().clear();
+    }
+
+    fn cached_asset_exists(&self, path: &Path) -> bool {
+        if let Some(exists) = self.asset_exists_cache.0.lock().unwrap().get(path) {
+            return *exists;
+        }
+        let exists = Self::asset_exists(path);
+        self.asset_exists_cache.0.lock().unwrap().insert(path.to_owned(), exists);
+        exists
     }
 
     pub fn get_data_path<P: AsRef<Path>>(&self, rel_path: P) -> Option<PathBuf> {
@@ -1114,6 +1563,11 @@ impl LocalizedData {
     }
 
     pub fn load_custom_story_ruby(&self, ast_ruby_name: &str) -> Option<Vec<CustomRubyBlock>> {
+        self.load_assets_dict(Some(Self::custom_story_ruby_path(ast_ruby_name)?))
+    }
+
+    /// Dict path of a story ruby asset (e.g. ast_ruby_xxyyyyzzz -> story/data/xx/yyyy/ast_ruby_xxyyyyzzz.json)
+    pub fn custom_story_ruby_path(ast_ruby_name: &str) -> Option<String> {
         let filename = ast_ruby_name.split('/').last().unwrap_or(ast_ruby_name);
 
         let filename_no_ext = filename.strip_suffix(".asset").unwrap_or(filename);
@@ -1125,9 +1579,7 @@ impl LocalizedData {
         let category_id = &id_str[0..2];
         let story_id = &id_str[2..6];
 
-        let path = format!("story/data/{}/{}/{}.json", category_id, story_id, filename_no_ext);
-
-        self.load_assets_dict(Some(path))
+        Some(format!("story/data/{}/{}/{}.json", category_id, story_id, filename_no_ext))
     }
 }
 
@@ -1139,6 +1591,8 @@ pub struct LocalizedDataConfig {
     pub character_system_text_dict: Option<String>,
     pub race_jikkyo_comment_dict: Option<String>,
     pub race_jikkyo_message_dict: Option<String>,
+    // Fixed translations applied around machine translation requests
+    pub glossary: Option<String>,
     pub assets_dir: Option<String>,
     #[serde(default)]
     pub extra_asset_bundle: OsOption<String>,
@@ -1151,6 +1605,15 @@ pub struct LocalizedDataConfig {
     #[serde(default)]
     pub months: Vec<String>,
     pub month_text_format: Option<String>,
+    // Starting from Sunday
+    #[serde(default)]
+    pub weekdays: Vec<String>,
+    pub date_format: Option<String>,
+    pub number_group_separator: Option<String>,
+    pub number_decimal_separator: Option<String>,
+    // Simple lookup filters: $(table_name key)
+    #[serde(default)]
+    pub filter_tables: FnvHashMap<String, FnvHashMap<String, String>>,
 
     #[serde(default)]
     pub use_text_wrapper: bool,
@@ -1,4 +1,5 @@
 use std::{
+    borrow::Cow,
     fs,
     io::{
         Read,
@@ -6,20 +7,21 @@ use std::{
         Seek,
         SeekFrom
     },
-    path::Path,
+    path::{Path, PathBuf},
     sync::{
         atomic::{self, AtomicBool},
         Arc,
         Mutex
-    }
+    },
+    time::{Duration, Instant}
 };
 use std::thread;
 use thread_priority::{ThreadBuilderExt, ThreadPriority};
 
 use arc_swap::ArcSwap;
-use serde::de::DeserializeOwned;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
-use super::{Error, Hachimi};
+use super::{utils, Error, Hachimi};
 
 pub struct AsyncRequest<T: Send + Sync> {
     request: Mutex<Option<http::Request<ureq::Body>>>,
@@ -31,9 +33,96 @@ pub struct AsyncRequest<T: Send + Sync> {
 pub fn ureq_config() -> ureq::config::Config {
     use ureq::config::IpFamily::*;
 
-    ureq::config::Config::builder()
-        .ip_family(if Hachimi::instance().config.load().ipv4_only { Ipv4Only } else { Any })
-        .build()
+    let config = Hachimi::instance().config.load();
+    let mut builder = ureq::config::Config::builder()
+        .ip_family(if config.ipv4_only { Ipv4Only } else { Any });
+
+    if let Some(proxy_url) = config.http_proxy.as_ref().filter(|s| !s.is_empty()) {
+        match ureq::Proxy::new(proxy_url) {
+            Ok(proxy) => builder = builder.proxy(Some(proxy)),
+            Err(e) => warn!("Invalid proxy '{}': {}", redact_url_credentials(proxy_url), e)
+        }
+    }
+
+    // ureq can't add roots on top of the WebPKI ones, so the bundle has to include every CA
+    // the game's servers and the translation repos need, not just the custom ones
+    if let Some(ca_path) = config.http_ca_bundle.as_ref().filter(|s| !s.is_empty()) {
+        if let Some(certs) = load_ca_bundle(ca_path) {
+            let tls_config = ureq::tls::TlsConfig::builder()
+                .root_certs(ureq::tls::RootCerts::new_with_certs(&certs))
+                .build();
+            builder = builder.tls_config(tls_config);
+        }
+    }
+
+    builder.build()
+}
+
+/// Replaces the `user:pass@` part of a URL, if any.
+pub fn redact_url_credentials(url: &str) -> Cow<'_, str> {
+    let authority_start = url.find("://").map(|i| i + 3).unwrap_or(0);
+    let authority_end = url[authority_start..].find('/').map(|i| authority_start + i).unwrap_or(url.len());
+    match url[authority_start..authority_end].rfind('@') {
+        Some(at) => Cow::Owned(format!("{}<redacted>{}", &url[..authority_start], &url[authority_start + at..])),
+        None => Cow::Borrowed(url)
+    }
+}
+
+// Cached by path so the bundle isn't re-read (and errors aren't re-logged) for every agent
+static CA_BUNDLE_CACHE: Mutex<Option<(String, Option<Arc<Vec<ureq::tls::Certificate<'static>>>>)>> = Mutex::new(None);
+
+fn load_ca_bundle(path: &str) -> Option<Arc<Vec<ureq::tls::Certificate<'static>>>> {
+    let mut cache = CA_BUNDLE_CACHE.lock().unwrap();
+    if let Some((cached_path, certs)) = cache.as_ref() {
+        if cached_path == path {
+            return certs.clone();
+        }
+    }
+
+    // Relative paths are resolved against the data dir
+    let full_path = Hachimi::instance().get_data_path(path);
+    let certs = match fs::read(&full_path) {
+        Ok(pem) => {
+            let certs: Vec<_> = ureq::tls::parse_pem(&pem)
+                .filter_map(|item| match item {
+                    Ok(ureq::tls::PemItem::Certificate(cert)) => Some(cert.to_owned()),
+                    _ => None
+                })
+                .collect();
+
+            if certs.is_empty() {
+                warn!("No certificates found in CA bundle: {}", full_path.display());
+                None
+            }
+            else {
+                info!("Loaded {} certificates from CA bundle, they replace the default root certificates", certs.len());
+                Some(Arc::new(certs))
+            }
+        }
+        Err(e) => {
+            warn!("Failed to read CA bundle {}: {}", full_path.display(), e);
+            None
+        }
+    };
+
+    *cache = Some((path.to_owned(), certs.clone()));
+    certs
+}
+
+/// Applies the first matching mirror rule from the config to the url.
+pub fn rewrite_url(url: &str) -> Cow<'_, str> {
+    let config = Hachimi::instance().config.load();
+    for rule in config.mirror_rules.iter() {
+        if rule.from.is_empty() {
+            continue;
+        }
+        if let Some(rest) = url.strip_prefix(rule.from.as_str()) {
+            let rewritten = format!("{}{}", rule.to, rest);
+            debug!("Mirror rewrite: {} -> {}", url, rewritten);
+            return Cow::Owned(rewritten);
+        }
+    }
+    Cow::Borrowed(url)
 }
 
 impl<T: Send + Sync + 'static> AsyncRequest<T> {
@@ -49,7 +138,14 @@ impl<T: Send + Sync + 'static> AsyncRequest<T> {
     pub fn call(self: Arc<Self>) {
         self.result.store(Arc::new(None));
         self.running.store(true, atomic::Ordering::Release);
-        let req = self.request.lock().unwrap().take().expect("Request run twice");
+        let mut req = self.request.lock().unwrap().take().expect("Request run twice");
+        let uri = req.uri().to_string();
+        if let Cow::Owned(rewritten) = rewrite_url(&uri) {
+            match rewritten.parse() {
+                Ok(new_uri) => *req.uri_mut() = new_uri,
+                Err(e) => warn!("Invalid rewritten url '{}': {}", rewritten, e)
+            }
+        }
         std::thread::spawn(move || {
             let agent = ureq::Agent::new_with_config(ureq_config());
 
@@ -77,21 +173,88 @@ impl<T: Send + Sync + 'static + DeserializeOwned> AsyncRequest<T> {
 
 pub fn get_json<T: DeserializeOwned>(url: &str) -> Result<T, Error> {
     let agent: ureq::Agent = ureq::Agent::new_with_config(ureq_config());
-    let res = agent.get(url).call()?;
+    let res = agent.get(rewrite_url(url).as_ref()).call()?;
     Ok(serde_json::from_str(&res.into_body().read_to_string()?)?)
 }
 
 pub fn get_github_json<T: DeserializeOwned>(url: &str) -> Result<T, Error> {
-    let res = ureq::get(url)
+    let agent: ureq::Agent = ureq::Agent::new_with_config(ureq_config());
+    let res = agent.get(rewrite_url(url).as_ref())
         .header("Accept", "application/vnd.github+json")
         .header("X-GitHub-Api-Version", "2022-11-28")
         .call()?;
     Ok(serde_json::from_str(&res.into_body().read_to_string()?)?)
 }
 
-pub fn download_file_parallel(url: &str, file_path: &Path, num_threads: usize,
+// Chunk progress of a parallel download, stored next to the partial file so it can be resumed
+#[derive(Serialize, Deserialize)]
+struct DownloadState {
+    url: String,
+    length: u64,
+    // ETag or Last-Modified of the remote file, a resume is only safe if it hasn't changed
+    validator: Option<String>,
+    chunk_size: u64,
+    // Bytes written so far for each chunk
+    chunks: Vec<u64>,
+    #[serde(skip)]
+    last_saved: Option<Instant>
+}
+
+const DOWNLOAD_STATE_SAVE_INTERVAL: Duration = Duration::from_secs(1);
+
+impl DownloadState {
+    fn get_path(file_path: &Path) -> PathBuf {
+        let mut path = file_path.as_os_str().to_owned();
+        path.push(".dlstate");
+        PathBuf::from(path)
+    }
+
+    fn load(state_path: &Path) -> Option<Self> {
+        let json = fs::read_to_string(state_path).ok()?;
+        serde_json::from_str(&json).ok()
+    }
+
+    fn save(&mut self, state_path: &Path) {
+        self.last_saved = Some(Instant::now());
+        if let Err(e) = utils::write_json_file(self, state_path) {
+            warn!("Failed to save download state '{}': {}", state_path.display(), e);
+        }
+    }
+
+    fn chunk_range(&self, index: usize) -> (u64, u64) {
+        let start = index as u64 * self.chunk_size;
+        let end = (start + self.chunk_size).min(self.length) - 1;
+        (start + self.chunks[index], end)
+    }
+
+    fn is_chunk_complete(&self, index: usize) -> bool {
+        let (start, end) = self.chunk_range(index);
+        start > end
+    }
+
+    fn downloaded_bytes(&self) -> u64 {
+        self.chunks.iter().sum()
+    }
+}
+
+/// Removes a partial download and its resume state.
+pub fn discard_partial_download(file_path: &Path) {
+    let _ = fs::remove_file(file_path);
+    let _ = fs::remove_file(DownloadState::get_path(file_path));
+}
+
+/// Downloads a file with multiple threads when the server supports range requests.
+///
+/// The progress of a parallel download is persisted next to the file, so a failed download is
+/// resumed on the next call with the same url instead of starting over. The partial file is left
+/// in place on error; use [`discard_partial_download`] to remove it. If `expected_hash` is provided,
+/// the finished file is verified against it and removed on mismatch.
+pub fn download_file_parallel(url: &str, file_path: &Path, expected_hash: Option<&str>, num_threads: usize,
     min_chunk_size: u64, chunk_size: usize, progress_callback: Arc<dyn Fn(usize) + Send + Sync>
 ) -> Result<(), Error> {
+    let original_url = url;
+    let url = rewrite_url(url);
+    let url = url.as_ref();
     let agent: ureq::Agent = ureq::Agent::new_with_config(ureq_config());
     let res = agent.head(url).call()?;
 
@@ -100,6 +263,11 @@ pub fn download_file_parallel(url: &str, file_path: &Path, num_threads: usize,
         .and_then(|h| h.to_str().ok())
         .and_then(|s| s.parse::<u64>().ok());
     let accepts_ranges = res.headers().get("Accept-Ranges").map_or(false, |v| v == "bytes");
+    let validator = res.headers()
+        .get("ETag")
+        .or_else(|| res.headers().get("Last-Modified"))
+        .and_then(|h| h.to_str().ok())
+        .map(|s| s.to_owned());
 
     let mut actual_length = 0u64;
     let mut use_parallel = false;
@@ -115,24 +283,58 @@ pub fn download_file_parallel(url: &str, file_path: &Path, num_threads: usize,
         }
     }
 
-    if use_parallel {
-        let downloaded_file = fs::File::create(file_path)?;
-        downloaded_file.set_len(actual_length)?;
-        drop(downloaded_file);
+    let state_path = DownloadState::get_path(file_path);
 
-        let chunk_size_per_thread = (actual_length / num_threads as u64).max(min_chunk_size);
-        let num_chunks = (actual_length + chunk_size_per_thread - 1) / chunk_size_per_thread;
+    if use_parallel {
+        let file_len = fs::metadata(file_path).map(|m| m.len()).ok();
+        let resumed_state = DownloadState::load(&state_path).filter(|state|
+            state.url == original_url &&
+            state.length == actual_length &&
+            state.validator == validator &&
+            state.chunk_size > 0 &&
+            state.chunks.len() as u64 == (actual_length + state.chunk_size - 1) / state.chunk_size &&
+            file_len == Some(actual_length)
+        );
+
+        let mut state = match resumed_state {
+            Some(state) => {
+                info!("Resuming download of {} ({} of {} bytes done)", url, state.downloaded_bytes(), actual_length);
+                progress_callback(state.downloaded_bytes() as usize);
+                state
+            }
+            None => {
+                let downloaded_file = fs::File::create(file_path)?;
+                downloaded_file.set_len(actual_length)?;
+                drop(downloaded_file);
+
+                let chunk_size_per_thread = (actual_length / num_threads as u64).max(min_chunk_size);
+                let num_chunks = (actual_length + chunk_size_per_thread - 1) / chunk_size_per_thread;
+                DownloadState {
+                    url: original_url.to_owned(),
+                    length: actual_length,
+                    validator: validator.clone(),
+                    chunk_size: chunk_size_per_thread,
+                    chunks: vec![0; num_chunks as usize],
+                    last_saved: None
+                }
+            }
+        };
+        state.save(&state_path);
+        let num_chunks = state.chunks.len();
+        let state = Arc::new(Mutex::new(state));
 
         let fatal_error = Arc::new(Mutex::new(None::<Error>));
         let needs_fallback = Arc::new(AtomicBool::new(false));
         let stop_signal = Arc::new(AtomicBool::new(false));
-        let (sender, receiver) = crossbeam_channel::unbounded::<(u64, u64)>();
+        let (sender, receiver) = crossbeam_channel::unbounded::<usize>();
         let mut handles = Vec::with_capacity(num_threads);
 
         for _ in 0..num_threads {
             let agent_clone = agent.clone();
             let url_clone = url.to_string();
             let path_clone = file_path.to_path_buf();
+            let state_path_clone = state_path.clone();
+            let state_clone = Arc::clone(&state);
             let receiver_clone = receiver.clone();
             let progress_callback_clone = Arc::clone(&progress_callback);
             let fatal_error_clone = Arc::clone(&fatal_error);
@@ -148,9 +350,10 @@ pub fn download_file_parallel(url: &str, file_path: &Path, num_threads: usize,
                         Err(e) => { *fatal_error_clone.lock().unwrap() = Some(e.into()); return; }
                     };
                     let mut buffer = vec![0u8; chunk_size];
-                    while let Ok((start, end)) = receiver_clone.recv() {
+                    while let Ok(index) = receiver_clone.recv() {
                         if stop_signal_clone.load(atomic::Ordering::Relaxed) { break; }
 
+                        let (start, end) = state_clone.lock().unwrap().chunk_range(index);
                         let expected_bytes = end - start + 1;
                         let range_header = format!("bytes={}-{}", start, end);
                         let result = (|| -> Result<(), Error> {
@@ -181,6 +384,14 @@ pub fn download_file_parallel(url: &str, file_path: &Path, num_threads: usize,
                                 file.write_all(&buffer[..bytes_read])?;
                                 progress_callback_clone(bytes_read);
 
+                                // Only counted once written, the state may lag behind the file but never run ahead of it
+                                let mut state = state_clone.lock().unwrap();
+                                state.chunks[index] += bytes_read as u64;
+                                if state.last_saved.map_or(true, |t| t.elapsed() >= DOWNLOAD_STATE_SAVE_INTERVAL) {
+                                    state.save(&state_path_clone);
+                                }
+                                drop(state);
+
                                 remaining -= bytes_read as u64;
                                 if remaining == 0 { break; }
                             }
@@ -200,10 +411,12 @@ pub fn download_file_parallel(url: &str, file_path: &Path, num_threads: usize,
             handles.push(handle);
         }
 
-        for i in 0..num_chunks {
-            let start = i * chunk_size_per_thread;
-            let end = (start + chunk_size_per_thread - 1).min(actual_length - 1);
-            if sender.send((start, end)).is_err() { break; }
+        {
+            let state = state.lock().unwrap();
+            for i in 0..num_chunks {
+                if state.is_chunk_complete(i) { continue; }
+                if sender.send(i).is_err() { break; }
+            }
         }
         drop(sender);
 
@@ -213,15 +426,25 @@ pub fn download_file_parallel(url: &str, file_path: &Path, num_threads: usize,
 
         if needs_fallback.load(atomic::Ordering::Relaxed) {
             debug!("Server returned 200 instead of 206, falling back to single-threaded download for: {}", url);
-            let _ = fs::remove_file(file_path);
+            discard_partial_download(file_path);
         } else if let Some(e) = fatal_error.lock().unwrap().take() {
+            // Keep the partial file around so the next attempt can pick up from here
+            if let Ok(downloaded_file) = fs::File::options().write(true).open(file_path) {
+                let _ = downloaded_file.sync_data();
+            }
+            state.lock().unwrap().save(&state_path);
             return Err(e);
         } else {
             let downloaded_file = fs::File::options().write(true).open(file_path)?;
             downloaded_file.sync_data()?;
-            return Ok(());
+            let _ = fs::remove_file(&state_path);
+            return verify_downloaded_file(file_path, expected_hash);
         }
     }
+    else {
+        // Can't be resumed without range requests
+        let _ = fs::remove_file(&state_path);
+    }
 
     debug!("Using single-threaded download for: {}", url);
     let res = agent.get(url).call()?;
@@ -249,6 +472,27 @@ pub fn download_file_parallel(url: &str, file_path: &Path, num_threads: usize,
             )));
         }
     }
+    verify_downloaded_file(file_path, expected_hash)
+}
+
+fn verify_downloaded_file(file_path: &Path, expected_hash: Option<&str>) -> Result<(), Error> {
+    let Some(expected_hash) = expected_hash else {
+        return Ok(());
+    };
+
+    let mut file = fs::File::open(file_path)?;
+    let mut hasher = blake3::Hasher::new();
+    let mut buffer = [0u8; 8192];
+    loop {
+        let n = file.read(&mut buffer)?;
+        if n == 0 { break; }
+        hasher.update(&buffer[..n]);
+    }
+
+    if !hasher.finalize().to_hex().as_str().eq_ignore_ascii_case(expected_hash) {
+        discard_partial_download(file_path);
+        return Err(Error::FileHashMismatch(file_path.to_string_lossy().into()));
+    }
     Ok(())
 }
 
@@ -1,21 +1,29 @@
-use std::sync::{Condvar, Mutex};
+use std::{io::Write, net::{IpAddr, SocketAddr}, sync::{atomic::{self, AtomicUsize}, mpsc, Condvar, Mutex}, time::Duration};
 
+use fnv::FnvHashSet;
+use once_cell::sync::Lazy;
 use rust_i18n::t;
 use serde::{Deserialize, Serialize};
 use tiny_http::{Header, Method, Request, Response, Server};
-use crate::{core::utils::notify_error, il2cpp::{hook::umamusume::{GameSystem, StoryTimelineController, StoryTimelineData}, symbols::{IList, Thread}}};
-use super::{Error, Gui, Hachimi};
+use crate::{core::utils::notify_error, il2cpp::{hook::umamusume::{GameSystem, SceneManager, StoryTimelineController, StoryTimelineData}, symbols::{IList, Thread}}};
+use super::{hachimi::Config, tl_repo::{LocalRepoInfo, UpdateProgress}, Error, Gui, Hachimi};
 
-pub fn start_http(listen_all: bool) {
-    std::thread::spawn(move || http_thread(listen_all));
+/// Bumped whenever commands or responses are added or changed.
+/// 1: StoryGotoBlock, ReloadLocalizedData, SoftReset
+/// 2: GetVersion, GetSceneInfo, GetStoryInfo, GetConfig, GetTlRepoInfo
+/// 3: GET /events (Server-Sent Events stream)
+pub const API_VERSION: u32 = 3;
+
+pub fn start_http(listen_all: bool, port: u16) {
+    std::thread::spawn(move || http_thread(listen_all, port));
 }
 
-fn http_thread(listen_all: bool) {
+fn http_thread(listen_all: bool, port: u16) {
     let address = if listen_all {
-        "0.0.0.0:50433"
+        SocketAddr::from(([0, 0, 0, 0], port))
     }
     else {
-        "127.0.0.1:50433"
+        SocketAddr::from(([127, 0, 0, 1], port))
     };
 
     let server = match Server::http(address) {
@@ -29,6 +37,24 @@ fn http_thread(listen_all: bool) {
     info!("IPC server listening on {}", address);
 
     for mut request in server.incoming_requests() {
+        if let Err((status, message)) = check_access(&request) {
+            warn!("Rejected IPC request from {:?}: {}", request.remote_addr(), message);
+            let data = serde_json::to_string(&CommandResponse::error(message.to_owned())).unwrap();
+            if let Err(e) = request.respond(
+                Response::from_string(data)
+                    .with_header(Header::from_bytes("content-type", "application/json").unwrap())
+                    .with_status_code(status)
+            ) {
+                error!("Failed to send HTTP response: {}", e);
+            }
+            continue;
+        }
+
+        if *request.method() == Method::Get && request.url().split('?').next() == Some(EVENTS_PATH) {
+            start_event_stream(request);
+            continue;
+        }
+
         let command_response = match on_http_request(&mut request) {
             Ok(v) => v,
             Err(e) => {
@@ -58,13 +84,199 @@ fn http_thread(listen_all: bool) {
     }
 }
 
+/// Checks the client address, origin and token of a request against the current config.
+/// Returns the status code and message to reject it with.
+fn check_access(request: &Request) -> Result<(), (u16, &'static str)> {
+    let config = Hachimi::instance().config.load();
+
+    // Loopback is always allowed, other addresses must be explicitly listed if the allowlist is used
+    let Some(remote_ip) = request.remote_addr().map(|a| a.ip()) else {
+        return Err((403, "Unknown client address"));
+    };
+    let mut allowlist = config.ipc_allowed_addresses.iter().filter(|a| !a.trim().is_empty()).peekable();
+    if !remote_ip.is_loopback() && allowlist.peek().is_some() &&
+        !allowlist.any(|a| a.trim().parse::<IpAddr>().is_ok_and(|ip| ip == remote_ip))
+    {
+        return Err((403, "Client address not allowed"));
+    }
+
+    // Browsers always attach these to cross-origin requests, real IPC clients don't
+    let headers = Headers { headers: request.headers() };
+    if headers.get("origin").is_some() ||
+        headers.get("sec-fetch-site").is_some_and(|v| !v.eq_ignore_ascii_case("none"))
+    {
+        return Err((403, "Cross-origin requests are not allowed"));
+    }
+
+    if let Some(token) = config.ipc_token.as_deref().filter(|t| !t.is_empty()) {
+        let provided = headers.get("authorization")
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .unwrap_or_default();
+        if !constant_time_eq(provided.as_bytes(), token.as_bytes()) {
+            return Err((401, "Invalid or missing token"));
+        }
+    }
+
+    Ok(())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+const EVENTS_PATH: &str = "/events";
+const EVENT_QUEUE_SIZE: usize = 256;
+const MAX_EVENT_SUBSCRIBERS: usize = 8;
+const EVENT_KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+static EVENT_SUBSCRIBERS: Mutex<Vec<mpsc::SyncSender<String>>> = Mutex::new(Vec::new());
+static EVENT_SUBSCRIBER_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Events pushed to clients subscribed to the /events stream.
+#[derive(Serialize)]
+#[serde(tag = "type")]
+pub enum Event {
+    StoryBlockChanged {
+        timeline_id: Option<String>,
+        block_id: i32,
+        block_count: i32
+    },
+
+    TextShown {
+        timeline_id: Option<String>,
+        block_id: i32,
+        name: Option<String>,
+        text: String
+    },
+
+    MissingTranslation {
+        dict: &'static str,
+        key: String,
+        value: String
+    },
+
+    SceneChanged {
+        view_id: i32
+    },
+
+    TlUpdateFinished {
+        success: bool,
+        error_count: usize,
+        message: Option<String>
+    }
+}
+
+// Keys already sent as MissingTranslation events in this session
+static REPORTED_MISSING: Lazy<Mutex<FnvHashSet<(&'static str, String)>>> = Lazy::new(|| Mutex::default());
+
+/// Emits a MissingTranslation event the first time a key is missed in this session.
+pub fn emit_missing_translation(dict: &'static str, key: &str, value: impl FnOnce() -> String) {
+    if !has_event_subscribers() {
+        return;
+    }
+    if !REPORTED_MISSING.lock().unwrap().insert((dict, key.to_owned())) {
+        return;
+    }
+    emit_event(Event::MissingTranslation { dict, key: key.to_owned(), value: value() });
+}
+
+/// Cheap check for hooks, so they can skip building events when nobody is listening.
+pub fn has_event_subscribers() -> bool {
+    EVENT_SUBSCRIBER_COUNT.load(atomic::Ordering::Relaxed) != 0
+}
+
+pub fn emit_event(event: Event) {
+    if !has_event_subscribers() {
+        return;
+    }
+
+    let data = match serde_json::to_string(&event) {
+        Ok(v) => v,
+        Err(e) => {
+            error!("Failed to encode IPC event: {}", e);
+            return;
+        }
+    };
+
+    let mut subscribers = EVENT_SUBSCRIBERS.lock().unwrap();
+    subscribers.retain(|sender| match sender.try_send(data.clone()) {
+        Ok(_) => true,
+        Err(mpsc::TrySendError::Full(_)) => {
+            // Client isn't keeping up, drop the event instead of stalling the game thread
+            debug!("IPC event queue full, dropping event");
+            true
+        },
+        Err(mpsc::TrySendError::Disconnected(_)) => false
+    });
+    EVENT_SUBSCRIBER_COUNT.store(subscribers.len(), atomic::Ordering::Relaxed);
+}
+
+fn start_event_stream(request: Request) {
+    let receiver = {
+        let mut subscribers = EVENT_SUBSCRIBERS.lock().unwrap();
+        if subscribers.len() >= MAX_EVENT_SUBSCRIBERS {
+            drop(subscribers);
+            let data = serde_json::to_string(&CommandResponse::error("Too many event subscribers".to_owned())).unwrap();
+            if let Err(e) = request.respond(
+                Response::from_string(data)
+                    .with_header(Header::from_bytes("content-type", "application/json").unwrap())
+                    .with_status_code(503)
+            ) {
+                error!("Failed to send HTTP response: {}", e);
+            }
+            return;
+        }
+
+        let (sender, receiver) = mpsc::sync_channel(EVENT_QUEUE_SIZE);
+        subscribers.push(sender);
+        EVENT_SUBSCRIBER_COUNT.store(subscribers.len(), atomic::Ordering::Relaxed);
+        receiver
+    };
+
+    std::thread::spawn(move || {
+        info!("IPC event subscriber connected");
+        if let Err(e) = event_stream_thread(request, receiver) {
+            debug!("IPC event stream closed: {}", e);
+        }
+        info!("IPC event subscriber disconnected");
+    });
+}
+
+fn event_stream_thread(request: Request, receiver: mpsc::Receiver<String>) -> std::io::Result<()> {
+    // tiny_http buffers chunked responses, so write the stream directly to the socket
+    let mut writer = request.into_writer();
+    writer.write_all(
+        b"HTTP/1.1 200 OK\r\n\
+        Content-Type: text/event-stream\r\n\
+        Cache-Control: no-cache\r\n\
+        Connection: close\r\n\r\n"
+    )?;
+    write!(writer, "retry: 1000\n\n")?;
+    writer.flush()?;
+
+    loop {
+        match receiver.recv_timeout(EVENT_KEEPALIVE_INTERVAL) {
+            Ok(data) => write!(writer, "data: {}\n\n", data)?,
+            Err(mpsc::RecvTimeoutError::Timeout) => writer.write_all(b": keepalive\n\n")?,
+            Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(())
+        }
+        writer.flush()?;
+    }
+}
+
 static STORY_GOTO_BLOCK_PARAMS: Mutex<(i32, bool)> = Mutex::new((0, false));
 static STORY_GOTO_BLOCK_CVAR: Condvar = Condvar::new();
 
 fn on_http_request(request: &mut Request) -> Result<CommandResponse, Error> {
     let method = request.method();
     if *method == Method::Get {
-        return Ok(CommandResponse::HelloWorld { message: "Hachimi's IPC server is working!" });
+        return Ok(CommandResponse::HelloWorld {
+            message: "Hachimi's IPC server is working!",
+            api_version: API_VERSION
+        });
     }
     else if *method != Method::Post {
         return Ok(CommandResponse::error("Invalid request method".to_owned()));
@@ -160,6 +372,61 @@ fn on_http_request(request: &mut Request) -> Result<CommandResponse, Error> {
                     mutex.lock().unwrap().show_notification(&t!("notification.ipc_softreset_exec"));
                 }
             } else { notify_error("SoftReset needs exec=true"); }
+        },
+
+        Command::GetVersion => {
+            return Ok(CommandResponse::Version {
+                version: env!("HACHIMI_DISPLAY_VERSION"),
+                api_version: API_VERSION
+            });
+        },
+
+        Command::GetSceneInfo => {
+            return Ok(CommandResponse::SceneInfo {
+                view_id: SceneManager::current_view_id(),
+                is_home_init: SceneManager::is_home_init()
+            });
+        },
+
+        Command::GetStoryInfo => {
+            let active = StoryTimelineController::CURRENT.lock().unwrap()
+                .as_ref()
+                .is_some_and(|h| !h.target().is_null());
+
+            return Ok(CommandResponse::StoryInfo {
+                active,
+                timeline_id: if active { StoryTimelineController::current_timeline_id() } else { None },
+                block_id: if active { Some(StoryTimelineController::last_block_id()) } else { None },
+                block_count: if active { Some(StoryTimelineController::current_block_count()) } else { None }
+            });
+        },
+
+        Command::GetConfig => {
+            let hachimi = Hachimi::instance();
+            let config = RedactedConfig::new((**hachimi.config.load()).clone());
+            return Ok(CommandResponse::Config { config });
+        },
+
+        Command::GetTlRepoInfo => {
+            let hachimi = Hachimi::instance();
+            let config = hachimi.config.load();
+            let repo_id = config.selected_tl_repo_id;
+
+            return Ok(CommandResponse::TlRepoInfo {
+                repo_id,
+                fallback_ids: hachimi.get_active_tl_repos().into_iter()
+                    .map(|r| r.id)
+                    .filter(|&id| Some(id) != repo_id)
+                    .collect(),
+                index: config.translation_repo_index.clone(),
+                dir: hachimi.get_active_tl_dir().map(|p| p.to_string_lossy().into_owned()),
+                info: repo_id.and_then(|id| LocalRepoInfo::load(id).ok().flatten()),
+                update_status: TlUpdateStatus {
+                    pending_update: hachimi.tl_updater.has_pending_update(),
+                    updating: hachimi.tl_updater.is_updating(),
+                    progress: hachimi.tl_updater.progress()
+                }
+            });
         }
     }
 
@@ -193,6 +460,37 @@ enum Command {
     ReloadLocalizedData,
     SoftReset {
         exec: bool
+    },
+
+    // Queries (API version 2)
+    GetVersion,
+    GetSceneInfo,
+    GetStoryInfo,
+    GetConfig,
+    GetTlRepoInfo
+}
+
+const REDACTED: &str = "<redacted>";
+
+/// Config as sent to IPC clients. Secrets are replaced with a placeholder so clients can still tell
+/// whether they're set.
+#[derive(Serialize)]
+#[serde(transparent)]
+struct RedactedConfig(Config);
+
+impl RedactedConfig {
+    fn new(mut config: Config) -> Self {
+        let redact = |value: &mut Option<String>| {
+            if value.is_some() {
+                *value = Some(REDACTED.to_owned());
+            }
+        };
+        redact(&mut config.ipc_token);
+        redact(&mut config.mt.mt_api_key);
+        if let Some(proxy) = &mut config.http_proxy {
+            *proxy = super::http::redact_url_credentials(proxy).into_owned();
+        }
+        RedactedConfig(config)
     }
 }
 
@@ -206,10 +504,49 @@ enum CommandResponse {
     },
 
     HelloWorld {
-        message: &'static str
+        message: &'static str,
+        api_version: u32
+    },
+
+    Version {
+        version: &'static str,
+        api_version: u32
+    },
+
+    SceneInfo {
+        view_id: Option<i32>,
+        is_home_init: bool
+    },
+
+    StoryInfo {
+        active: bool,
+        timeline_id: Option<String>,
+        block_id: Option<i32>,
+        block_count: Option<i32>
+    },
+
+    Config {
+        config: RedactedConfig
+    },
+
+    TlRepoInfo {
+        repo_id: Option<u32>,
+        // Active fallback repos, highest priority first
+        fallback_ids: Vec<u32>,
+        index: Option<String>,
+        dir: Option<String>,
+        info: Option<LocalRepoInfo>,
+        update_status: TlUpdateStatus
     }
 }
 
+#[derive(Serialize)]
+struct TlUpdateStatus {
+    pending_update: bool,
+    updating: bool,
+    progress: Option<UpdateProgress>
+}
+
 impl CommandResponse {
     fn error(message: impl Into<Option<String>>) -> Self {
         Self::Error { message: message.into() }
@@ -0,0 +1,172 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration
+};
+
+use crossbeam_channel::{unbounded, Receiver, RecvTimeoutError};
+use fnv::FnvHashSet;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use rust_i18n::t;
+
+use crate::il2cpp::{hook::umamusume::StoryTimelineController, symbols::Thread};
+
+use super::{plugin_events, texture_diff_cache, tl_overrides, Error, Gui, Hachimi};
+
+// Editors usually write a file in several steps (truncate, write, rename), wait for them to settle
+const DEBOUNCE_DELAY: Duration = Duration::from_millis(300);
+
+static WATCHER: Mutex<Option<(PathBuf, RecommendedWatcher)>> = Mutex::new(None);
+
+/// Starts or stops watching the active localized data dir to match the config.
+pub fn update() {
+    let hachimi = Hachimi::instance();
+    let path = if hachimi.config.load().hot_reload_localized_data {
+        hachimi.localized_data.load().get_path().map(|p| p.to_owned())
+    }
+    else {
+        None
+    };
+
+    let mut watcher = WATCHER.lock().unwrap();
+    if watcher.as_ref().map(|(p, _)| p) == path.as_ref() {
+        return;
+    }
+
+    // Dropping the watcher also stops its debounce thread
+    *watcher = None;
+    let Some(path) = path else {
+        return;
+    };
+
+    match start(&path) {
+        Ok(w) => {
+            info!("Watching localized data for changes: {}", path.display());
+            *watcher = Some((path, w));
+        }
+        Err(e) => error!("Failed to watch localized data dir '{}': {}", path.display(), e)
+    }
+}
+
+fn start(path: &Path) -> Result<RecommendedWatcher, Error> {
+    let (sender, receiver) = unbounded::<PathBuf>();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let event = match res {
+            Ok(v) => v,
+            Err(e) => {
+                warn!("File watcher error: {}", e);
+                return;
+            }
+        };
+
+        if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)) {
+            return;
+        }
+        for path in event.paths {
+            _ = sender.send(path);
+        }
+    }).map_err(|e| Error::RuntimeError(e.to_string()))?;
+
+    watcher.watch(path, RecursiveMode::Recursive)
+        .map_err(|e| Error::RuntimeError(e.to_string()))?;
+
+    // Created up front so overrides added later are picked up without a restart
+    let overrides_root = tl_overrides::get_dir();
+    if let Err(e) = fs::create_dir_all(&overrides_root) {
+        warn!("Failed to create overrides dir: {}", e);
+    }
+    else if let Err(e) = watcher.watch(&overrides_root, RecursiveMode::Recursive) {
+        warn!("Failed to watch overrides dir: {}", e);
+    }
+
+    let root = path.to_owned();
+    std::thread::Builder::new()
+        .name("ld_watcher".into())
+        .spawn(move || debounce_thread(root, overrides_root, receiver))?;
+
+    Ok(watcher)
+}
+
+fn debounce_thread(root: PathBuf, overrides_root: PathBuf, receiver: Receiver<PathBuf>) {
+    while let Ok(path) = receiver.recv() {
+        let mut changed = FnvHashSet::default();
+        changed.insert(path);
+
+        loop {
+            match receiver.recv_timeout(DEBOUNCE_DELAY) {
+                Ok(path) => { changed.insert(path); }
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return
+            }
+        }
+
+        on_files_changed(&root, &overrides_root, changed);
+    }
+}
+
+fn on_files_changed(root: &Path, overrides_root: &Path, changed: FnvHashSet<PathBuf>) {
+    let hachimi = Hachimi::instance();
+    // The updater writes into the dir and reloads everything once it's done
+    if hachimi.tl_updater.progress().is_some() {
+        return;
+    }
+
+    // Overrides mirror the repo layout
+    let rel_paths: Vec<&Path> = changed.iter()
+        .filter_map(|p| p.strip_prefix(root).or_else(|_| p.strip_prefix(overrides_root)).ok())
+        .filter(|p| !p.as_os_str().is_empty())
+        .collect();
+    if rel_paths.is_empty() {
+        return;
+    }
+
+    let ld_paths = hachimi.get_active_tl_dirs();
+    let current = hachimi.localized_data.load_full();
+    let full_reload = ld_paths.first().map(|p| p.as_path()) != Some(root) ||
+        rel_paths.iter().any(|p| *p == Path::new("config.json"));
+
+    if full_reload {
+        info!("Reloading all localized data");
+        hachimi.load_localized_data();
+    }
+    else {
+        let assets_dir = current.config.assets_dir.as_ref().map(Path::new);
+        let mut data = None;
+        let mut dicts_changed = false;
+        let mut assets_changed = false;
+        for rel_path in rel_paths {
+            if assets_dir.is_some_and(|dir| rel_path.starts_with(dir)) {
+                // Assets are read when they're loaded, nothing to reload
+                assets_changed = true;
+                continue;
+            }
+
+            let data = data.get_or_insert_with(|| (*current).clone());
+            if data.reload_dict(&ld_paths, rel_path) {
+                info!("Reloaded {}", rel_path.display());
+                dicts_changed = true;
+            }
+        }
+
+        if dicts_changed {
+            hachimi.localized_data.store(Arc::new(data.unwrap()));
+            plugin_events::emit_localized_data_reloaded();
+        }
+        else if !assets_changed {
+            return;
+        }
+        if assets_changed {
+            // Files might have been added to or removed from a layer
+            hachimi.localized_data.load().clear_asset_cache();
+            texture_diff_cache::clear_keys();
+        }
+    }
+
+    // Show the changes in the story block that's currently being displayed
+    Thread::main_thread().schedule(StoryTimelineController::reload_current_block);
+
+    if let Some(mutex) = Gui::instance() {
+        mutex.lock().unwrap().show_notification(&t!("notification.localized_data_reloaded"));
+    }
+}
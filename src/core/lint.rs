@@ -0,0 +1,443 @@
+use std::{fmt::Write as _, fs, path::{Path, PathBuf}, sync::Arc};
+
+use fnv::FnvHashMap;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::il2cpp::hook::{
+    umamusume::StoryTimelineData::{self, StoryTimelineDataDict},
+    Plugins::AnimateToUnity::AnText::IgnoreATFiltersContext,
+    UnityEngine_TextRenderingModule::TextGenerator::IgnoreTGFiltersContext
+};
+
+use super::{hachimi::{LocalizedData, LocalizedDataConfig}, template, template_filters, utils, Error};
+
+const REPORT_FILENAME: &str = "lint_report.json";
+// Unity rich text tags that need to be closed
+const PAIRED_TAGS: &[&str] = &["b", "i", "size", "color", "material"];
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LintSeverity {
+    Error,
+    Warning
+}
+
+#[derive(Serialize)]
+pub struct LintIssue {
+    pub severity: LintSeverity,
+    /// Path relative to the repo dir
+    pub file: String,
+    pub line: Option<usize>,
+    /// Dict key of the entry, if any
+    pub key: Option<String>,
+    pub message: String
+}
+
+/// Problems found in a localized data dir.
+#[derive(Serialize, Default)]
+pub struct LintReport {
+    pub issues: Vec<LintIssue>,
+    pub files_checked: usize,
+    pub entries_checked: usize
+}
+
+impl LintReport {
+    pub fn error_count(&self) -> usize {
+        self.issues.iter().filter(|i| i.severity == LintSeverity::Error).count()
+    }
+
+    pub fn warning_count(&self) -> usize {
+        self.issues.iter().filter(|i| i.severity == LintSeverity::Warning).count()
+    }
+
+    fn push(&mut self, severity: LintSeverity, file: &str, key: Option<String>, message: String) {
+        self.issues.push(LintIssue {
+            severity,
+            file: file.to_owned(),
+            line: None,
+            key,
+            message
+        });
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for issue in &self.issues {
+            let severity = match issue.severity {
+                LintSeverity::Error => "error",
+                LintSeverity::Warning => "warning"
+            };
+            _ = write!(text, "{}: {}", severity, issue.file);
+            if let Some(line) = issue.line {
+                _ = write!(text, ":{}", line);
+            }
+            if let Some(key) = &issue.key {
+                _ = write!(text, " [{}]", key);
+            }
+            _ = writeln!(text, ": {}", issue.message);
+        }
+        _ = writeln!(
+            text, "{} files, {} entries checked: {} errors, {} warnings",
+            self.files_checked, self.entries_checked, self.error_count(), self.warning_count()
+        );
+        text
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        utils::write_json_file(self, path)
+    }
+}
+
+// Leaves the filters to the parser so they're evaluated for real (variables get a dummy value),
+// only the ones handled by the text hooks are accepted as is. Records the ones that fail.
+struct LintContext {
+    errors: Vec<String>
+}
+
+impl template::Context for LintContext {
+    fn on_filter_eval(&mut self, name: &str, args: &[template::Token]) -> Option<String> {
+        template::Context::on_filter_eval(&mut IgnoreTGFiltersContext(), name, args)
+            .or_else(|| template::Context::on_filter_eval(&mut IgnoreATFiltersContext(), name, args))
+    }
+
+    fn get_variable(&mut self, _name: &str) -> Option<template::Token> {
+        // Variables are provided at runtime, any of them could be defined
+        Some(template::Token::NumberLit(1.0))
+    }
+
+    fn on_eval_error(&mut self, pos: usize, message: &str) {
+        self.errors.push(format!("template error at pos {}: {}", pos, message));
+    }
+}
+
+struct Linter<'a> {
+    dir: &'a Path,
+    config: &'a LocalizedDataConfig,
+    parser: template::Parser,
+    report: LintReport
+}
+
+impl Linter<'_> {
+    fn relative_path(&self, path: &Path) -> String {
+        path.strip_prefix(self.dir).unwrap_or(path).to_string_lossy().replace('\\', "/")
+    }
+
+    // Parses the dict the same way LocalizedData does, but keeps the error location
+    fn load_dict<T: DeserializeOwned>(&mut self, path: &Path) -> Option<T> {
+        let file = self.relative_path(path);
+        let json = match fs::read_to_string(path) {
+            Ok(v) => v,
+            Err(e) => {
+                self.report.push(LintSeverity::Error, &file, None, format!("failed to read: {}", e));
+                return None;
+            }
+        };
+        self.report.files_checked += 1;
+
+        match serde_json::from_str(&json) {
+            Ok(v) => Some(v),
+            Err(e) => {
+                self.report.issues.push(LintIssue {
+                    severity: LintSeverity::Error,
+                    file,
+                    line: Some(e.line()),
+                    key: None,
+                    message: format!("failed to parse: {}", e)
+                });
+                None
+            }
+        }
+    }
+
+    fn check_config_dict<T: DeserializeOwned>(&mut self, rel_path: Option<&String>) -> Option<(String, T)> {
+        let rel_path = rel_path?;
+        let path = self.dir.join(rel_path);
+        let dict = self.load_dict(&path)?;
+        Some((self.relative_path(&path), dict))
+    }
+
+    fn check_text(&mut self, file: &str, key: &dyn Fn() -> String, text: &str) {
+        self.report.entries_checked += 1;
+
+        if text.contains('$') {
+            let mut context = LintContext { errors: Vec::new() };
+            self.parser.eval_with_context(text, &mut context);
+            for error in context.errors {
+                self.report.push(LintSeverity::Error, file, Some(key()), error);
+            }
+        }
+
+        if text.contains('<') {
+            for error in check_tags(text) {
+                self.report.push(LintSeverity::Error, file, Some(key()), error);
+            }
+        }
+    }
+
+    fn check_flat_dict<K: ToString>(&mut self, file: &str, dict: impl IntoIterator<Item = (K, String)>) {
+        for (key, text) in dict {
+            self.check_text(file, &|| key.to_string(), &text);
+        }
+    }
+
+    fn check_nested_dict(&mut self, file: &str, dict: FnvHashMap<i32, FnvHashMap<i32, String>>) {
+        for (outer, entries) in dict {
+            for (inner, text) in entries {
+                self.check_text(file, &|| format!("{}/{}", outer, inner), &text);
+            }
+        }
+    }
+
+    fn check_assets(&mut self, assets_dir: &Path) {
+        let mut dirs = vec![assets_dir.to_owned()];
+        while let Some(dir) = dirs.pop() {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.is_dir() {
+                    dirs.push(path);
+                    continue;
+                }
+
+                let is_story = path.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with("storytimeline_") || n.starts_with("hometimeline_"));
+                if is_story && path.extension().is_some_and(|e| e == "json") {
+                    let asset_path = path.strip_prefix(assets_dir).unwrap_or(&path).to_string_lossy().replace('\\', "/");
+                    self.check_story(&path, &asset_path);
+                }
+            }
+        }
+    }
+
+    fn check_story(&mut self, path: &Path, asset_path: &str) {
+        let Some(dict) = self.load_dict::<StoryTimelineDataDict>(path) else {
+            return;
+        };
+        let file = self.relative_path(path);
+
+        if let Some(title) = &dict.title {
+            self.check_text(&file, &|| "title".to_owned(), title);
+        }
+
+        for (i, block) in dict.text_block_list.iter().enumerate() {
+            if let Some(name) = &block.name {
+                self.check_text(&file, &|| format!("{}/name", i), name);
+            }
+            if let Some(text) = &block.text {
+                self.check_text(&file, &|| format!("{}/text", i), text);
+                self.check_story_text_fit(&file, i, text, dict.no_wrap, StoryTimelineData::is_story_view(asset_path));
+            }
+            for (j, text) in block.choice_data_list.iter().enumerate() {
+                self.check_text(&file, &|| format!("{}/choice/{}", i, j), text);
+            }
+            for (j, text) in block.color_text_info_list.iter().enumerate() {
+                self.check_text(&file, &|| format!("{}/color_text/{}", i, j), text);
+            }
+        }
+    }
+
+    // Same parameters as StoryTimelineData::on_LoadAsset
+    fn check_story_text_fit(&mut self, file: &str, block: usize, text: &str, no_wrap: bool, is_story_view: bool) {
+        if !self.config.use_text_wrapper {
+            return;
+        }
+        let Some(line_width_multiplier) = self.config.line_width_multiplier else {
+            return;
+        };
+
+        let mut line_count = StoryTimelineData::CLIP_TEXT_LINE_COUNT;
+        if let Some(offset) = self.config.story_line_count_offset {
+            line_count += offset;
+        }
+        let mut line_width = StoryTimelineData::CLIP_TEXT_LINE_WIDTH;
+        let mut story_view_line_width = StoryTimelineData::STORY_VIEW_CLIP_TEXT_LINE_WIDTH;
+        if let Some(mult) = self.config.text_frame_font_size_multiplier {
+            line_width = (line_width as f32 / mult).round() as i32;
+            story_view_line_width = (story_view_line_width as f32 / mult).round() as i32;
+        }
+        let width = if is_story_view { story_view_line_width } else { line_width };
+        let max_columns = (width as f32 * line_width_multiplier).round() as usize;
+
+        let lines: Vec<String> = if no_wrap || text.contains("<size=") {
+            // Not wrapped (or fitted) at runtime, check the lines as written
+            text.split('\n').map(|s| s.to_owned()).collect()
+        }
+        else {
+            let penalties = LocalizedData::parse_wrap_penalties_or_default(&self.config.wrapper_penalties);
+            utils::wrap_text_with(text, width, line_width_multiplier, penalties)
+                .into_iter()
+                .map(|s| s.into_owned())
+                .collect()
+        };
+
+        let key = || Some(format!("{}/text", block));
+        for line in &lines {
+            let len = utils::str_visual_len(line);
+            if len > max_columns {
+                self.report.push(
+                    LintSeverity::Warning, file, key(),
+                    format!("line is {} columns wide, only {} fit: {}", len, max_columns, line)
+                );
+            }
+        }
+
+        // Story view logs have no line limit
+        if !is_story_view && lines.len() as i32 > line_count {
+            self.report.push(
+                LintSeverity::Warning, file, key(),
+                format!("text takes {} lines, only {} fit (it will be shrunk)", lines.len(), line_count)
+            );
+        }
+    }
+}
+
+/// Checks that Unity rich text tags are balanced. Returns a description of each problem.
+pub fn check_tags(text: &str) -> Vec<String> {
+    let mut errors = Vec::new();
+    let mut stack: Vec<&str> = Vec::new();
+
+    for (section, is_text) in utils::IsolateTags::new(text) {
+        if is_text {
+            // IsolateTags leaves opening tags without a matching closing tag in the text
+            for (i, _) in section.match_indices('<') {
+                let rest = &section[i + 1..];
+                let name_len = rest.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(rest.len());
+                let name = &rest[..name_len];
+                let next = rest[name_len..].chars().next();
+                if PAIRED_TAGS.contains(&name) && matches!(next, Some('>' | '=')) {
+                    errors.push(format!("unclosed <{}> tag", name));
+                }
+            }
+            continue;
+        }
+
+        let tag = section.trim_end();
+        if !tag.starts_with('<') {
+            // Template expression
+            continue;
+        }
+
+        if let Some(name) = tag.strip_prefix("</") {
+            let name = name.trim_end_matches('>');
+            match stack.pop() {
+                Some(open) if open == name => (),
+                Some(open) => errors.push(format!("</{}> closes <{}>", name, open)),
+                None => errors.push(format!("</{}> without a matching opening tag", name))
+            }
+        }
+        else {
+            let name = tag[1..].split(|c| c == '=' || c == ' ' || c == '>').next().unwrap_or_default();
+            if PAIRED_TAGS.contains(&name) {
+                stack.push(name);
+            }
+        }
+    }
+
+    for name in stack {
+        errors.push(format!("unclosed <{}> tag", name));
+    }
+
+    errors
+}
+
+/// Checks a localized data dir: dict syntax, templates, rich text tags and story text fitting.
+/// Doesn't need the game or Hachimi to be initialized, can be run headless through the hachimi-lint binary.
+pub fn lint_dir(dir: &Path) -> LintReport {
+    let mut report = LintReport::default();
+
+    let config_path = dir.join("config.json");
+    let config: LocalizedDataConfig = match fs::read_to_string(&config_path) {
+        Ok(json) => match serde_json::from_str(&json) {
+            Ok(v) => v,
+            Err(e) => {
+                report.issues.push(LintIssue {
+                    severity: LintSeverity::Error,
+                    file: "config.json".to_owned(),
+                    line: Some(e.line()),
+                    key: None,
+                    message: format!("failed to parse: {}", e)
+                });
+                return report;
+            }
+        },
+        Err(e) => {
+            report.push(LintSeverity::Error, "config.json", None, format!("failed to read: {}", e));
+            return report;
+        }
+    };
+    report.files_checked += 1;
+
+    // Filters read their settings (plural rules, months, filter tables...) from the repo being linted
+    let localized_data = match LocalizedData::load(Some(dir.to_owned())) {
+        Ok(v) => Some(Arc::new(v)),
+        Err(e) => {
+            report.push(LintSeverity::Error, "config.json", None, e.to_string());
+            None
+        }
+    };
+    let filters_data = localized_data.clone().unwrap_or_else(|| Arc::new(LocalizedData::default()));
+
+    template_filters::with_localized_data(filters_data, || {
+        let mut linter = Linter {
+            dir,
+            config: &config,
+            parser: template::Parser::new(&template_filters::LIST).with_dynamic_filter(template_filters::repo_filter),
+            report
+        };
+
+        if let Some((file, dict)) = linter.check_config_dict::<FnvHashMap<String, String>>(config.localize_dict.as_ref()) {
+            linter.check_flat_dict(&file, dict);
+        }
+        if let Some((file, dict)) = linter.check_config_dict::<FnvHashMap<u64, String>>(config.hashed_dict.as_ref()) {
+            linter.check_flat_dict(&file, dict);
+        }
+        if let Some((file, dict)) = linter.check_config_dict(config.text_data_dict.as_ref()) {
+            linter.check_nested_dict(&file, dict);
+        }
+        if let Some((file, dict)) = linter.check_config_dict(config.character_system_text_dict.as_ref()) {
+            linter.check_nested_dict(&file, dict);
+        }
+        if let Some((file, dict)) = linter.check_config_dict::<FnvHashMap<i32, String>>(config.race_jikkyo_comment_dict.as_ref()) {
+            linter.check_flat_dict(&file, dict);
+        }
+        if let Some((file, dict)) = linter.check_config_dict::<FnvHashMap<i32, String>>(config.race_jikkyo_message_dict.as_ref()) {
+            linter.check_flat_dict(&file, dict);
+        }
+        if let Some((_, dict)) = linter.check_config_dict::<FnvHashMap<String, String>>(config.glossary.as_ref()) {
+            // Only the dict syntax matters here
+            linter.report.entries_checked += dict.len();
+        }
+
+        if let Some(assets_dir) = &config.assets_dir {
+            linter.check_assets(&dir.join(assets_dir));
+        }
+
+        let mut report = linter.report;
+
+        // Plural rules and form counts
+        if let Some(localized_data) = &localized_data {
+            for issue in localized_data.validate_plural_templates(&template::Parser::new(&template_filters::LIST)) {
+                report.push(LintSeverity::Error, "config.json", None, issue);
+            }
+        }
+
+        report
+    })
+}
+
+/// Lints the active localized data dir and saves the report in the data dir.
+/// Returns the report and its path.
+pub fn lint_active_repo() -> Result<(LintReport, PathBuf), Error> {
+    let hachimi = super::Hachimi::instance();
+    let Some(dir) = hachimi.localized_data.load().get_path().map(|p| p.to_owned()) else {
+        return Err(Error::RuntimeError("No localized data loaded".to_owned()));
+    };
+
+    let report = lint_dir(&dir);
+    let report_path = hachimi.get_data_path(REPORT_FILENAME);
+    report.save(&report_path)?;
+    Ok((report, report_path))
+}
@@ -0,0 +1,277 @@
+use std::{collections::BTreeMap, fs, path::{Path, PathBuf}, sync::{atomic::{self, AtomicBool}, Mutex}, time::Duration};
+
+use once_cell::sync::Lazy;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::il2cpp::hook::umamusume::SceneManager;
+
+use super::{utils, Error, Hachimi};
+
+const COLLECTOR_DIR: &str = "missing_translations";
+const META_FILENAME: &str = "_meta.json";
+const SAVE_DELAY: Duration = Duration::from_secs(5);
+
+/// Where an untranslated string came from. Each variant maps to the dict it belongs in.
+pub enum MissingSource<'a> {
+    Localize { key: &'a str },
+    TextData { category: i32, index: i32 },
+    CharacterSystemText { character_id: i32, voice_id: i32 },
+    RaceJikkyoComment { id: i32 },
+    RaceJikkyoMessage { id: i32 },
+    /// Story dict path relative to the assets dir
+    Story { dict_path: &'a str }
+}
+
+impl MissingSource<'_> {
+    fn meta_key(&self) -> String {
+        match self {
+            Self::Localize { key } => format!("localize_dict/{}", key),
+            Self::TextData { category, index } => format!("text_data_dict/{}/{}", category, index),
+            Self::CharacterSystemText { character_id, voice_id } => format!("character_system_text_dict/{}/{}", character_id, voice_id),
+            Self::RaceJikkyoComment { id } => format!("race_jikkyo_comment_dict/{}", id),
+            Self::RaceJikkyoMessage { id } => format!("race_jikkyo_message_dict/{}", id),
+            Self::Story { dict_path } => format!("assets/{}", dict_path)
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct EntryMeta {
+    first_seen: String,
+    // View id of the scene the entry was first seen in
+    scene: Option<i32>
+}
+
+/// Collects untranslated strings seen while translator mode is enabled.
+///
+/// Entries are saved to the missing_translations dir in the data dir, using the same file layout
+/// as a localized data dir (with the original text as values) so they can be translated in place
+/// and copied into a repo.
+#[derive(Default)]
+pub struct MissingTranslations {
+    localize_dict: BTreeMap<String, String>,
+    text_data_dict: BTreeMap<i32, BTreeMap<i32, String>>,
+    character_system_text_dict: BTreeMap<i32, BTreeMap<i32, String>>,
+    race_jikkyo_comment_dict: BTreeMap<i32, String>,
+    race_jikkyo_message_dict: BTreeMap<i32, String>,
+    stories: BTreeMap<String, serde_json::Value>,
+    meta: BTreeMap<String, EntryMeta>
+}
+
+static INSTANCE: Lazy<Mutex<MissingTranslations>> = Lazy::new(|| Mutex::new(MissingTranslations::load()));
+static SAVE_SCHEDULED: AtomicBool = AtomicBool::new(false);
+// Keeps saves from overlapping
+static SAVE_LOCK: Mutex<()> = Mutex::new(());
+
+fn get_collector_dir() -> PathBuf {
+    Hachimi::instance().get_data_path(COLLECTOR_DIR)
+}
+
+fn load_file<T: DeserializeOwned + Default>(dir: &Path, filename: &str) -> T {
+    let path = dir.join(filename);
+    let Ok(json) = fs::read_to_string(&path) else {
+        return T::default();
+    };
+    serde_json::from_str(&json).unwrap_or_else(|e| {
+        error!("Failed to parse '{}': {}", path.display(), e);
+        T::default()
+    })
+}
+
+/// Whether missing translations should be collected.
+pub fn is_enabled() -> bool {
+    Hachimi::instance().config.load().translator_mode
+}
+
+/// Records an untranslated string. Does nothing if it has already been recorded.
+pub fn record(source: MissingSource, text: &str) {
+    let mut collector = INSTANCE.lock().unwrap();
+    if collector.insert(&source, || serde_json::Value::String(text.to_owned())) {
+        info!("{}: {},", serde_json::to_string(&source.meta_key()).unwrap(), serde_json::to_string(text).unwrap());
+        schedule_save();
+    }
+}
+
+/// Records an untranslated story. The dict is only built if the story hasn't been recorded yet.
+pub fn record_story<T: Serialize>(dict_path: &str, build_dict: impl FnOnce() -> Option<T>) {
+    let mut collector = INSTANCE.lock().unwrap();
+    let source = MissingSource::Story { dict_path };
+    let inserted = collector.insert(&source, || {
+        build_dict()
+            .and_then(|d| serde_json::to_value(d).ok())
+            .unwrap_or(serde_json::Value::Null)
+    });
+    if inserted {
+        info!("Missing story: {}", dict_path);
+        schedule_save();
+    }
+}
+
+fn schedule_save() {
+    if SAVE_SCHEDULED.swap(true, atomic::Ordering::AcqRel) {
+        return;
+    }
+
+    // Batch up writes, new entries tend to come in bursts
+    std::thread::spawn(|| {
+        std::thread::sleep(SAVE_DELAY);
+        let _guard = SAVE_LOCK.lock().unwrap();
+
+        // Only hold the collector lock while taking the snapshot so the hooks aren't blocked on disk writes
+        let res = {
+            let mut collector = INSTANCE.lock().unwrap();
+            SAVE_SCHEDULED.store(false, atomic::Ordering::Release);
+            collector.take_snapshot()
+        };
+        let mut snapshot = match res {
+            Ok(v) => v,
+            Err(e) => {
+                error!("Failed to save missing translations: {}", e);
+                return;
+            }
+        };
+
+        if let Err(e) = snapshot.write() {
+            error!("Failed to save missing translations: {}", e);
+            // Keep the stories that weren't written for the next save
+            INSTANCE.lock().unwrap().stories.extend(snapshot.stories);
+        }
+    });
+}
+
+// Serialized dicts and pending story dicts, written out after the collector lock is released
+struct SaveSnapshot {
+    files: Vec<(&'static str, Vec<u8>)>,
+    stories: Vec<(String, serde_json::Value)>
+}
+
+impl SaveSnapshot {
+    fn write(&mut self) -> Result<(), Error> {
+        let dir = get_collector_dir();
+        fs::create_dir_all(&dir)?;
+
+        for (filename, data) in &self.files {
+            fs::write(dir.join(filename), data)?;
+        }
+
+        let assets_dir = dir.join("assets");
+        while let Some((dict_path, dict)) = self.stories.last() {
+            let path = assets_dir.join(dict_path);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            utils::write_json_file(dict, &path)?;
+            self.stories.pop();
+        }
+
+        let config_path = dir.join("config.json");
+        if !config_path.exists() {
+            utils::write_json_file(&serde_json::json!({
+                "localize_dict": "localize_dict.json",
+                "text_data_dict": "text_data_dict.json",
+                "character_system_text_dict": "character_system_text_dict.json",
+                "race_jikkyo_comment_dict": "race_jikkyo_comment_dict.json",
+                "race_jikkyo_message_dict": "race_jikkyo_message_dict.json",
+                "assets_dir": "assets"
+            }), config_path)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl MissingTranslations {
+    fn load() -> MissingTranslations {
+        let dir = get_collector_dir();
+        let mut collector = MissingTranslations {
+            localize_dict: load_file(&dir, "localize_dict.json"),
+            text_data_dict: load_file(&dir, "text_data_dict.json"),
+            character_system_text_dict: load_file(&dir, "character_system_text_dict.json"),
+            race_jikkyo_comment_dict: load_file(&dir, "race_jikkyo_comment_dict.json"),
+            race_jikkyo_message_dict: load_file(&dir, "race_jikkyo_message_dict.json"),
+            stories: BTreeMap::new(),
+            meta: load_file(&dir, META_FILENAME)
+        };
+
+        // Stories are only tracked through the meta file, their dicts are never rewritten
+        for key in collector.meta.keys() {
+            if let Some(dict_path) = key.strip_prefix("assets/") {
+                collector.stories.insert(dict_path.to_owned(), serde_json::Value::Null);
+            }
+        }
+
+        collector
+    }
+
+    // Returns true if the entry is new
+    fn insert(&mut self, source: &MissingSource, value: impl FnOnce() -> serde_json::Value) -> bool {
+        fn text(value: serde_json::Value) -> String {
+            match value {
+                serde_json::Value::String(s) => s,
+                _ => String::new()
+            }
+        }
+
+        let inserted = match *source {
+            MissingSource::Localize { key } => {
+                if self.localize_dict.contains_key(key) { return false; }
+                self.localize_dict.insert(key.to_owned(), text(value()));
+                true
+            },
+            MissingSource::TextData { category, index } => {
+                let category = self.text_data_dict.entry(category).or_default();
+                if category.contains_key(&index) { return false; }
+                category.insert(index, text(value()));
+                true
+            },
+            MissingSource::CharacterSystemText { character_id, voice_id } => {
+                let character = self.character_system_text_dict.entry(character_id).or_default();
+                if character.contains_key(&voice_id) { return false; }
+                character.insert(voice_id, text(value()));
+                true
+            },
+            MissingSource::RaceJikkyoComment { id } => {
+                if self.race_jikkyo_comment_dict.contains_key(&id) { return false; }
+                self.race_jikkyo_comment_dict.insert(id, text(value()));
+                true
+            },
+            MissingSource::RaceJikkyoMessage { id } => {
+                if self.race_jikkyo_message_dict.contains_key(&id) { return false; }
+                self.race_jikkyo_message_dict.insert(id, text(value()));
+                true
+            },
+            MissingSource::Story { dict_path } => {
+                if self.stories.contains_key(dict_path) { return false; }
+                self.stories.insert(dict_path.to_owned(), value());
+                true
+            }
+        };
+
+        if inserted {
+            self.meta.insert(source.meta_key(), EntryMeta {
+                first_seen: chrono::Utc::now().to_rfc3339(),
+                scene: SceneManager::current_view_id()
+            });
+        }
+        inserted
+    }
+
+    fn take_snapshot(&mut self) -> Result<SaveSnapshot, Error> {
+        let files = vec![
+            ("localize_dict.json", serde_json::to_vec_pretty(&self.localize_dict)?),
+            ("text_data_dict.json", serde_json::to_vec_pretty(&self.text_data_dict)?),
+            ("character_system_text_dict.json", serde_json::to_vec_pretty(&self.character_system_text_dict)?),
+            ("race_jikkyo_comment_dict.json", serde_json::to_vec_pretty(&self.race_jikkyo_comment_dict)?),
+            ("race_jikkyo_message_dict.json", serde_json::to_vec_pretty(&self.race_jikkyo_message_dict)?),
+            (META_FILENAME, serde_json::to_vec_pretty(&self.meta)?)
+        ];
+
+        // Story dicts are only written once, null marks them as saved (or couldn't be built)
+        let stories = self.stories.iter_mut()
+            .filter(|(_, dict)| !dict.is_null())
+            .map(|(dict_path, dict)| (dict_path.clone(), std::mem::take(dict)))
+            .collect();
+
+        Ok(SaveSnapshot { files, stories })
+    }
+}
@@ -12,6 +12,7 @@ pub mod gui;
 pub use gui::Gui;
 
 pub mod plurals;
+pub mod plural_rules;
 mod template_filters;
 
 #[macro_use] pub mod interceptor;
@@ -20,11 +21,25 @@ pub use interceptor::Interceptor;
 pub mod utils;
 pub mod http;
 pub mod tl_repo;
+pub mod tl_overrides;
+pub mod update_policy;
+pub mod delta_patch;
+pub mod texture_diff;
+pub mod texture_diff_cache;
 pub mod log;
-mod ipc;
-
-pub mod sugoi_client;
-pub use sugoi_client::SugoiClient;
+pub mod ipc;
+pub mod ld_watcher;
+
+pub mod translator;
+pub use translator::Translator;
+mod translation_backends;
+pub mod mt_cache;
+pub mod glossary;
+pub mod coverage;
+pub mod missing_tl;
+pub mod asset_dump;
+pub mod plugin_events;
+pub mod lint;
 
 pub mod plugin_api;
 
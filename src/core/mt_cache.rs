@@ -0,0 +1,343 @@
+use std::{collections::BTreeMap, fs, path::{Path, PathBuf}, sync::Mutex, time::{Duration, Instant, SystemTime, UNIX_EPOCH}};
+
+use fnv::FnvHashMap;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+
+use super::{utils, Error, Hachimi};
+
+const CACHE_FILENAME: &str = "mt_cache.json";
+const EXPORT_DIR: &str = "mt_export";
+const CACHE_VERSION: u32 = 1;
+// Story layouts are only needed for exporting, keep the most recent ones
+const MAX_STORIES: usize = 2000;
+// The whole file gets rewritten on save, so don't do it after every single translation
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Persistent machine translation cache.
+///
+/// Entries are grouped by backend namespace (see `TranslationBackend::cache_namespace`) and keyed
+/// by source text. Localize keys and story dict layouts seen by the auto translator are remembered
+/// too, so the cache can be exported as a localized data dir.
+#[derive(Serialize, Deserialize, Default)]
+pub struct MtCache {
+    #[serde(default)]
+    version: u32,
+    #[serde(default)]
+    namespaces: FnvHashMap<String, FnvHashMap<String, MtCacheEntry>>,
+    // TextId name -> source text
+    #[serde(default)]
+    localize_keys: BTreeMap<String, String>,
+    // Story dict path (relative to the assets dir) -> dict with source text
+    #[serde(default)]
+    stories: FnvHashMap<String, MtCacheStory>,
+
+    #[serde(skip)]
+    dirty: bool,
+    #[serde(skip)]
+    last_save: Option<Instant>
+}
+
+#[derive(Serialize, Deserialize)]
+struct MtCacheEntry {
+    translation: String,
+    last_used: u64
+}
+
+#[derive(Serialize, Deserialize)]
+struct MtCacheStory {
+    dict: serde_json::Value,
+    last_used: u64
+}
+
+static INSTANCE: OnceCell<Mutex<MtCache>> = OnceCell::new();
+static SAVE_LOCK: Mutex<()> = Mutex::new(());
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+fn get_cache_path() -> PathBuf {
+    Hachimi::instance().get_data_path(CACHE_FILENAME)
+}
+
+impl MtCache {
+    /// Loads the cache if needed. Blocks, don't call this from the main thread.
+    pub fn instance() -> &'static Mutex<MtCache> {
+        INSTANCE.get_or_init(|| Mutex::new(Self::load(&get_cache_path())))
+    }
+
+    /// Returns the cache only if it has already been loaded.
+    pub fn loaded_instance() -> Option<&'static Mutex<MtCache>> {
+        INSTANCE.get()
+    }
+
+    /// Loads the cache in the background so lookups from the main thread can use it.
+    pub fn preload() {
+        if INSTANCE.get().is_some() {
+            return;
+        }
+        std::thread::spawn(|| { Self::instance(); });
+    }
+
+    fn load(path: &Path) -> MtCache {
+        let json = match fs::read_to_string(path) {
+            Ok(v) => v,
+            Err(e) => {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    error!("Failed to read MT cache: {}", e);
+                }
+                return MtCache::default();
+            }
+        };
+
+        match serde_json::from_str::<MtCache>(&json) {
+            Ok(cache) if cache.version == CACHE_VERSION => {
+                info!("Loaded MT cache: {} entries", cache.len());
+                cache
+            },
+            Ok(_) => {
+                warn!("Discarding MT cache with unsupported version");
+                MtCache::default()
+            },
+            Err(e) => {
+                error!("Failed to parse MT cache: {}", e);
+                MtCache::default()
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.namespaces.values().map(|m| m.len()).sum()
+    }
+
+    pub fn get(&mut self, namespace: &str, source: &str) -> Option<String> {
+        let entry = self.namespaces.get_mut(namespace)?.get_mut(source)?;
+        entry.last_used = now();
+        Some(entry.translation.clone())
+    }
+
+    pub fn insert(&mut self, namespace: &str, source: String, translation: String) {
+        let entries = match self.namespaces.get_mut(namespace) {
+            Some(v) => v,
+            None => self.namespaces.entry(namespace.to_owned()).or_default()
+        };
+        entries.insert(source, MtCacheEntry { translation, last_used: now() });
+        self.dirty = true;
+    }
+
+    pub fn register_localize_key(&mut self, key: &str, source: &str) {
+        if self.localize_keys.get(key).is_some_and(|s| s == source) {
+            return;
+        }
+        self.localize_keys.insert(key.to_owned(), source.to_owned());
+        self.dirty = true;
+    }
+
+    pub fn register_story<T: Serialize>(&mut self, dict_path: &str, dict: &T) {
+        let dict = match serde_json::to_value(dict) {
+            Ok(v) => v,
+            Err(e) => {
+                error!("Failed to serialize story dict: {}", e);
+                return;
+            }
+        };
+        self.stories.insert(dict_path.to_owned(), MtCacheStory { dict, last_used: now() });
+        self.dirty = true;
+    }
+
+    /// Removes the least recently used entries until the cache is below the limits.
+    pub fn evict(&mut self, max_entries: usize) {
+        let len = self.len();
+        if len > max_entries {
+            // Evict down to 90% so we don't end up doing this after every insert
+            let target = max_entries - max_entries / 10;
+            if target == 0 {
+                self.namespaces.clear();
+            }
+            else {
+                // By count rather than by a last_used cutoff, many entries can share the same second
+                let mut by_age: Vec<(u64, &String, &String)> = self.namespaces.iter()
+                    .flat_map(|(ns, m)| m.iter().map(move |(source, e)| (e.last_used, ns, source)))
+                    .collect();
+                by_age.sort_unstable_by_key(|(last_used, _, _)| *last_used);
+                let evicted: Vec<(String, String)> = by_age[..len - target].iter()
+                    .map(|(_, ns, source)| ((*ns).clone(), (*source).clone()))
+                    .collect();
+
+                for (ns, source) in evicted {
+                    if let Some(entries) = self.namespaces.get_mut(&ns) {
+                        entries.remove(&source);
+                    }
+                }
+            }
+            self.namespaces.retain(|_, m| !m.is_empty());
+            self.dirty = true;
+            info!("Evicted {} MT cache entries", len - self.len());
+        }
+
+        if self.stories.len() > MAX_STORIES {
+            let mut by_age: Vec<(u64, &String)> = self.stories.iter().map(|(path, s)| (s.last_used, path)).collect();
+            by_age.sort_unstable_by_key(|(last_used, _)| *last_used);
+            let evicted: Vec<String> = by_age[..self.stories.len() - MAX_STORIES].iter()
+                .map(|(_, path)| (*path).clone())
+                .collect();
+            for path in evicted {
+                self.stories.remove(&path);
+            }
+            self.dirty = true;
+        }
+    }
+
+    // Serializes the cache if it has unsaved changes. Only the snapshot is taken under the lock,
+    // writing it out is left to the caller.
+    fn take_snapshot(&mut self, only_if_due: bool) -> Result<Option<Vec<u8>>, Error> {
+        if !self.dirty || (only_if_due && self.last_save.is_some_and(|t| t.elapsed() < SAVE_INTERVAL)) {
+            return Ok(None);
+        }
+
+        let max_entries = Hachimi::instance().config.load().mt_cache_max_entries;
+        self.evict(max_entries);
+        self.version = CACHE_VERSION;
+        let data = serde_json::to_vec_pretty(self)?;
+
+        self.dirty = false;
+        self.last_save = Some(Instant::now());
+        Ok(Some(data))
+    }
+
+    fn save_internal(cache: &Mutex<MtCache>, only_if_due: bool) -> Result<(), Error> {
+        // Keeps concurrent saves in order so an older snapshot can't overwrite a newer one
+        let _guard = SAVE_LOCK.lock().unwrap();
+        let Some(data) = cache.lock().unwrap().take_snapshot(only_if_due)? else {
+            return Ok(());
+        };
+
+        // Write to a temp file first so a crash mid-write doesn't wipe the cache
+        let path = get_cache_path();
+        let tmp_path = path.with_extension("json.tmp");
+        let res = fs::write(&tmp_path, &data).and_then(|_| fs::rename(&tmp_path, &path));
+        if let Err(e) = res {
+            // Try again on the next save
+            cache.lock().unwrap().dirty = true;
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    /// Saves the cache if it has unsaved changes and the last save was long enough ago.
+    /// Must not be called with the cache locked.
+    pub fn save_if_due(cache: &Mutex<MtCache>) -> Result<(), Error> {
+        Self::save_internal(cache, true)
+    }
+
+    /// Writes any unsaved changes to disk, if the cache has been loaded.
+    pub fn flush() {
+        let Some(cache) = Self::loaded_instance() else {
+            return;
+        };
+        if let Err(e) = Self::save_internal(cache, false) {
+            error!("Failed to save MT cache: {}", e);
+        }
+    }
+
+    /// Empties the cache, saved on the next flush.
+    pub fn clear(&mut self) {
+        *self = MtCache::default();
+        self.dirty = true;
+    }
+
+    /// Exports the entries of a namespace as a localized data dir (config.json, localize_dict.json
+    /// and story dicts under assets/). Untranslated strings are left as is.
+    /// Returns the export dir.
+    pub fn export(&self, namespace: &str) -> Result<PathBuf, Error> {
+        let empty = FnvHashMap::default();
+        let entries = self.namespaces.get(namespace).unwrap_or(&empty);
+
+        let export_dir = Hachimi::instance()
+            .get_data_path(EXPORT_DIR)
+            .join(namespace.replace(|c: char| !c.is_ascii_alphanumeric() && c != '-' && c != '.', "_"));
+        fs::create_dir_all(&export_dir)?;
+
+        let localize_dict: BTreeMap<&str, &str> = self.localize_keys.iter()
+            .filter_map(|(key, source)| entries.get(source).map(|e| (key.as_str(), e.translation.as_str())))
+            .collect();
+        utils::write_json_file(&localize_dict, export_dir.join("localize_dict.json"))?;
+
+        let assets_dir = export_dir.join("assets");
+        let mut story_count = 0;
+        for (dict_path, story) in &self.stories {
+            let mut dict = story.dict.clone();
+            if !Self::translate_value(&mut dict, entries) {
+                continue;
+            }
+
+            let path = assets_dir.join(dict_path);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            utils::write_json_file(&dict, &path)?;
+            story_count += 1;
+        }
+
+        utils::write_json_file(&serde_json::json!({
+            "localize_dict": "localize_dict.json",
+            "assets_dir": "assets"
+        }), export_dir.join("config.json"))?;
+
+        info!(
+            "Exported MT cache to {}: {} localize entries, {} stories",
+            export_dir.display(), localize_dict.len(), story_count
+        );
+        Ok(export_dir)
+    }
+
+    // Replaces every string in the value with its translation. Returns whether anything was translated.
+    fn translate_value(value: &mut serde_json::Value, entries: &FnvHashMap<String, MtCacheEntry>) -> bool {
+        match value {
+            serde_json::Value::String(s) => {
+                if let Some(entry) = entries.get(s.as_str()) {
+                    *s = entry.translation.clone();
+                    true
+                }
+                else {
+                    false
+                }
+            },
+            serde_json::Value::Array(values) => values.iter_mut()
+                .fold(false, |acc, v| Self::translate_value(v, entries) || acc),
+            serde_json::Value::Object(map) => map.values_mut()
+                .fold(false, |acc, v| Self::translate_value(v, entries) || acc),
+            _ => false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evict_same_second() {
+        let mut cache = MtCache::default();
+        let entries = cache.namespaces.entry("ns".to_owned()).or_default();
+        for i in 0..100 {
+            entries.insert(i.to_string(), MtCacheEntry { translation: String::new(), last_used: 1000 });
+        }
+        cache.evict(50);
+        assert_eq!(cache.len(), 45);
+    }
+
+    #[test]
+    fn evict_oldest_first() {
+        let mut cache = MtCache::default();
+        let entries = cache.namespaces.entry("ns".to_owned()).or_default();
+        for i in 0..20u64 {
+            entries.insert(i.to_string(), MtCacheEntry { translation: String::new(), last_used: i });
+        }
+        cache.evict(10);
+        let entries = &cache.namespaces["ns"];
+        assert_eq!(entries.len(), 9);
+        assert!((11..20).all(|i| entries.contains_key(&i.to_string())));
+    }
+}
@@ -3,9 +3,9 @@ use std::{ffi::{c_char, c_void, CStr, CString}, sync::atomic::AtomicI32};
 use once_cell::sync::OnceCell;
 use egui::Align;
 
-use crate::{core::{utils::get_data_path, Hachimi, Interceptor, gui}, il2cpp::{self, types::{FieldInfo, Il2CppArray, Il2CppClass, Il2CppImage, Il2CppMethodPointer, Il2CppObject, Il2CppString, Il2CppThread, Il2CppTypeEnum, MethodInfo, il2cpp_array_size_t}}};
+use crate::{core::{plugin_events::{self, EventCallback, EventType}, utils::get_data_path, Hachimi, Interceptor, gui}, il2cpp::{self, hook::UnityEngine_AssetBundleModule::AssetBundle::{self, AssetClassFilter, LoadAssetCallback, LoadAssetHandler, PluginLoadAssetCallback}, types::{FieldInfo, Il2CppArray, Il2CppClass, Il2CppImage, Il2CppMethodPointer, Il2CppObject, Il2CppString, Il2CppThread, Il2CppTypeEnum, MethodInfo, il2cpp_array_size_t}}};
 
-const VERSION: i32 = 3;
+const VERSION: i32 = 4;
 
 static PLUGIN_VTABLE: OnceCell<Vtable> = OnceCell::new();
 static DATA_DIR_CSTR: once_cell::sync::OnceCell<CString> = once_cell::sync::OnceCell::new();
@@ -297,6 +297,77 @@ unsafe extern "C" fn hachimi_register_on_game_initialized(
     true
 }
 
+/// `class` takes precedence over `class_name` ("Namespace.ClassName" or "ClassName") when both are set.
+/// `path_prefix` is optional. Handlers run in ascending priority order, builtin handlers use 0.
+/// A callback that returns true stops the handlers after it, builtin ones included (version >= 4).
+unsafe extern "C" fn hachimi_register_load_asset_handler(
+    class: *mut Il2CppClass,
+    class_name: *const c_char,
+    path_prefix: *const c_char,
+    priority: i32,
+    callback: Option<PluginLoadAssetCallback>,
+    userdata: *mut c_void
+) -> bool {
+    let Some(callback) = callback else {
+        return false;
+    };
+
+    let class_filter = if !class.is_null() {
+        AssetClassFilter::Class(class as usize)
+    }
+    else if !class_name.is_null() {
+        let Ok(class_name) = CStr::from_ptr(class_name).to_str() else {
+            return false;
+        };
+        AssetClassFilter::Name(class_name.to_owned())
+    }
+    else {
+        return false;
+    };
+
+    let path_prefix = if path_prefix.is_null() {
+        None
+    }
+    else {
+        let Ok(path_prefix) = CStr::from_ptr(path_prefix).to_str() else {
+            return false;
+        };
+        Some(path_prefix.to_owned())
+    };
+
+    AssetBundle::register_load_asset_handler(LoadAssetHandler {
+        class: class_filter,
+        path_prefix,
+        priority,
+        callback: LoadAssetCallback::Plugin { callback, userdata: userdata as usize }
+    });
+    true
+}
+
+/// See [`plugin_events::EventType`] for the event types and the structs passed to the callback.
+unsafe extern "C" fn hachimi_subscribe_event(
+    event_type: i32,
+    callback: Option<EventCallback>,
+    userdata: *mut c_void
+) -> bool {
+    let (Some(event_type), Some(callback)) = (EventType::from_i32(event_type), callback) else {
+        return false;
+    };
+    plugin_events::subscribe(event_type, callback, userdata);
+    true
+}
+
+unsafe extern "C" fn hachimi_unsubscribe_event(
+    event_type: i32,
+    callback: Option<EventCallback>,
+    userdata: *mut c_void
+) -> bool {
+    let (Some(event_type), Some(callback)) = (EventType::from_i32(event_type), callback) else {
+        return false;
+    };
+    plugin_events::unsubscribe(event_type, callback, userdata)
+}
+
 #[cfg(target_os = "windows")]
 unsafe extern "C" fn hachimi_register_present_callback(
     callback: Option<PresentCallback>,
@@ -1115,6 +1186,9 @@ pub extern "C" fn hachimi_get_api(name: *const c_char) -> *mut c_void {
         "gui_ui_combo_menu" => gui_ui_combo_menu as *mut c_void,
         "hachimi_register_on_game_initialized" => hachimi_register_on_game_initialized as *mut c_void,
         "hachimi_register_present_callback" => hachimi_register_present_callback as *mut c_void,
+        "hachimi_register_load_asset_handler" => hachimi_register_load_asset_handler as *mut c_void,
+        "hachimi_subscribe_event" => hachimi_subscribe_event as *mut c_void,
+        "hachimi_unsubscribe_event" => hachimi_unsubscribe_event as *mut c_void,
         "gui_get_menu_width" => gui_get_menu_width as *mut c_void,
         "gui_set_menu_width" => gui_set_menu_width as *mut c_void,
         "hachimi_get_base_dir" => hachimi_get_base_dir as *mut c_void,
@@ -0,0 +1,232 @@
+use std::{
+    ffi::{c_char, c_void, CString},
+    ptr::null,
+    sync::{atomic::{self, AtomicUsize}, Mutex}
+};
+
+use crate::il2cpp::symbols::Thread;
+
+/// Game events published to plugins. The discriminants are part of the plugin API.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum EventType {
+    ViewChanged = 0,
+    StoryBlockChanged = 1,
+    TextShown = 2,
+    LiveStarted = 3,
+    LiveStopped = 4,
+    CaptionShown = 5,
+    LocalizedDataReloaded = 6,
+    TlUpdateFinished = 7
+}
+
+const EVENT_TYPE_COUNT: usize = 8;
+
+impl EventType {
+    pub fn from_i32(value: i32) -> Option<EventType> {
+        Some(match value {
+            0 => Self::ViewChanged,
+            1 => Self::StoryBlockChanged,
+            2 => Self::TextShown,
+            3 => Self::LiveStarted,
+            4 => Self::LiveStopped,
+            5 => Self::CaptionShown,
+            6 => Self::LocalizedDataReloaded,
+            7 => Self::TlUpdateFinished,
+            _ => return None
+        })
+    }
+}
+
+/// `data` points to the event's struct (or is null for events without data) and is only valid during the call.
+/// Strings in it are nullable UTF-8 C strings.
+pub type EventCallback = extern "C" fn(event_type: EventType, data: *const c_void, userdata: *mut c_void);
+
+#[repr(C)]
+pub struct ViewChangedEvent {
+    pub view_id: i32
+}
+
+#[repr(C)]
+pub struct StoryBlockChangedEvent {
+    pub timeline_id: *const c_char,
+    pub block_id: i32,
+    pub block_count: i32
+}
+
+#[repr(C)]
+pub struct TextShownEvent {
+    pub timeline_id: *const c_char,
+    pub block_id: i32,
+    pub name: *const c_char,
+    pub text: *const c_char
+}
+
+#[repr(C)]
+pub struct LiveEvent {
+    pub music_id: i32
+}
+
+#[repr(C)]
+pub struct CaptionShownEvent {
+    pub text: *const c_char
+}
+
+#[repr(C)]
+pub struct TlUpdateFinishedEvent {
+    pub success: bool,
+    pub error_count: usize,
+    pub message: *const c_char
+}
+
+/// Events published by the hooks. Game and localized data events are emitted on the main thread,
+/// update events may come from any thread.
+pub enum Event<'a> {
+    ViewChanged { view_id: i32 },
+    StoryBlockChanged { timeline_id: Option<&'a str>, block_id: i32, block_count: i32 },
+    TextShown { timeline_id: Option<&'a str>, block_id: i32, name: Option<&'a str>, text: &'a str },
+    LiveStarted { music_id: i32 },
+    LiveStopped,
+    CaptionShown { text: &'a str },
+    LocalizedDataReloaded,
+    TlUpdateFinished { success: bool, error_count: usize, message: Option<&'a str> }
+}
+
+impl Event<'_> {
+    pub fn event_type(&self) -> EventType {
+        match self {
+            Self::ViewChanged { .. } => EventType::ViewChanged,
+            Self::StoryBlockChanged { .. } => EventType::StoryBlockChanged,
+            Self::TextShown { .. } => EventType::TextShown,
+            Self::LiveStarted { .. } => EventType::LiveStarted,
+            Self::LiveStopped => EventType::LiveStopped,
+            Self::CaptionShown { .. } => EventType::CaptionShown,
+            Self::LocalizedDataReloaded => EventType::LocalizedDataReloaded,
+            Self::TlUpdateFinished { .. } => EventType::TlUpdateFinished
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct Subscriber {
+    event_type: EventType,
+    callback: usize, // EventCallback
+    userdata: usize
+}
+
+static SUBSCRIBERS: Mutex<Vec<Subscriber>> = Mutex::new(Vec::new());
+static SUBSCRIBER_COUNTS: [AtomicUsize; EVENT_TYPE_COUNT] = [const { AtomicUsize::new(0) }; EVENT_TYPE_COUNT];
+
+fn update_counts(subscribers: &[Subscriber]) {
+    for (i, count) in SUBSCRIBER_COUNTS.iter().enumerate() {
+        let n = subscribers.iter().filter(|s| s.event_type as usize == i).count();
+        count.store(n, atomic::Ordering::Relaxed);
+    }
+}
+
+pub fn subscribe(event_type: EventType, callback: EventCallback, userdata: *mut c_void) {
+    let mut subscribers = SUBSCRIBERS.lock().unwrap();
+    subscribers.push(Subscriber { event_type, callback: callback as usize, userdata: userdata as usize });
+    update_counts(&subscribers);
+}
+
+/// Returns false if there was no matching subscription.
+pub fn unsubscribe(event_type: EventType, callback: EventCallback, userdata: *mut c_void) -> bool {
+    let target = Subscriber { event_type, callback: callback as usize, userdata: userdata as usize };
+    let mut subscribers = SUBSCRIBERS.lock().unwrap();
+    let Some(index) = subscribers.iter().position(|s| *s == target) else {
+        return false;
+    };
+    subscribers.remove(index);
+    update_counts(&subscribers);
+    true
+}
+
+/// Whether any plugin is subscribed to the event type. Only reads a counter, so hooks can call it
+/// on every frame before collecting the event's data.
+pub fn has_subscribers(event_type: EventType) -> bool {
+    SUBSCRIBER_COUNTS[event_type as usize].load(atomic::Ordering::Relaxed) != 0
+}
+
+// Keeps the C strings alive while the callbacks run
+fn to_cstring(s: Option<&str>) -> Option<CString> {
+    // Interior nul bytes can't be represented, cut the string there
+    s.map(|s| CString::new(s.split('\0').next().unwrap_or_default()).unwrap_or_default())
+}
+
+fn cstr_ptr(s: &Option<CString>) -> *const c_char {
+    s.as_ref().map_or(null(), |s| s.as_ptr())
+}
+
+/// Emits LocalizedDataReloaded on the main thread. Reloads can happen on any thread, sometimes with
+/// the GUI locked, and subscribers usually want to touch game objects in response.
+pub fn emit_localized_data_reloaded() {
+    if has_subscribers(EventType::LocalizedDataReloaded) {
+        Thread::main_thread().schedule(|| emit(Event::LocalizedDataReloaded));
+    }
+}
+
+pub fn emit(event: Event) {
+    let event_type = event.event_type();
+    if !has_subscribers(event_type) {
+        return;
+    }
+
+    // Copied so callbacks can (un)subscribe without deadlocking
+    let subscribers: Vec<Subscriber> = SUBSCRIBERS.lock().unwrap().iter()
+        .filter(|s| s.event_type == event_type)
+        .copied()
+        .collect();
+
+    let dispatch = |data: *const c_void| {
+        for subscriber in &subscribers {
+            let callback: EventCallback = unsafe { std::mem::transmute(subscriber.callback) };
+            callback(event_type, data, subscriber.userdata as _);
+        }
+    };
+
+    match event {
+        Event::ViewChanged { view_id } => {
+            let data = ViewChangedEvent { view_id };
+            dispatch(&data as *const _ as _);
+        }
+
+        Event::StoryBlockChanged { timeline_id, block_id, block_count } => {
+            let timeline_id = to_cstring(timeline_id);
+            let data = StoryBlockChangedEvent { timeline_id: cstr_ptr(&timeline_id), block_id, block_count };
+            dispatch(&data as *const _ as _);
+        }
+
+        Event::TextShown { timeline_id, block_id, name, text } => {
+            let timeline_id = to_cstring(timeline_id);
+            let name = to_cstring(name);
+            let text = to_cstring(Some(text));
+            let data = TextShownEvent {
+                timeline_id: cstr_ptr(&timeline_id),
+                block_id,
+                name: cstr_ptr(&name),
+                text: cstr_ptr(&text)
+            };
+            dispatch(&data as *const _ as _);
+        }
+
+        Event::LiveStarted { music_id } => {
+            let data = LiveEvent { music_id };
+            dispatch(&data as *const _ as _);
+        }
+
+        Event::CaptionShown { text } => {
+            let text = to_cstring(Some(text));
+            let data = CaptionShownEvent { text: cstr_ptr(&text) };
+            dispatch(&data as *const _ as _);
+        }
+
+        Event::TlUpdateFinished { success, error_count, message } => {
+            let message = to_cstring(message);
+            let data = TlUpdateFinishedEvent { success, error_count, message: cstr_ptr(&message) };
+            dispatch(&data as *const _ as _);
+        }
+
+        Event::LiveStopped | Event::LocalizedDataReloaded => dispatch(null())
+    }
+}
@@ -0,0 +1,409 @@
+/*
+    CLDR plural rule parser / resolver
+
+    Syntax: "one: i = 1 and v = 0; few: n % 10 = 2..4 and n % 100 != 12..14; other"
+    Only whole numbers are ever resolved, so the v, w, f, t, c and e operands are always 0.
+    Samples (@integer, @decimal) are ignored. The "other" category is implied.
+
+    Rule sets are also built in for a number of languages, see get_builtin().
+*/
+use super::Error;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PluralCategory {
+    Zero,
+    One,
+    Two,
+    Few,
+    Many,
+    Other
+}
+
+impl PluralCategory {
+    fn parse(s: &str) -> Option<PluralCategory> {
+        Some(match s {
+            "zero" => PluralCategory::Zero,
+            "one" => PluralCategory::One,
+            "two" => PluralCategory::Two,
+            "few" => PluralCategory::Few,
+            "many" => PluralCategory::Many,
+            "other" => PluralCategory::Other,
+            _ => return None
+        })
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PluralCategory::Zero => "zero",
+            PluralCategory::One => "one",
+            PluralCategory::Two => "two",
+            PluralCategory::Few => "few",
+            PluralCategory::Many => "many",
+            PluralCategory::Other => "other"
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Operand {
+    // n, i
+    Value,
+    // v, w, f, t, c, e
+    Zero
+}
+
+#[derive(Clone, Debug)]
+struct Relation {
+    operand: Operand,
+    modulo: Option<u64>,
+    negate: bool,
+    ranges: Vec<(u64, u64)>
+}
+
+impl Relation {
+    fn matches(&self, n: u64) -> bool {
+        let mut value = match self.operand {
+            Operand::Value => n,
+            Operand::Zero => 0
+        };
+        if let Some(modulo) = self.modulo {
+            value %= modulo;
+        }
+
+        let in_ranges = self.ranges.iter().any(|(start, end)| (*start..=*end).contains(&value));
+        in_ranges != self.negate
+    }
+}
+
+// OR of AND chains
+type Condition = Vec<Vec<Relation>>;
+
+/// A set of CLDR plural rules.
+///
+/// Categories are mapped to form indices in CLDR order (zero, one, two, few, many, other),
+/// skipping the ones that never apply to whole numbers.
+#[derive(Clone, Debug)]
+pub struct PluralRules {
+    rules: Vec<(PluralCategory, Condition)>,
+    categories: Vec<PluralCategory>
+}
+
+/// Whole numbers the categories are sampled with.
+pub fn samples() -> impl Iterator<Item = u64> {
+    (0..=1000).chain((4..=9).map(|e| 10u64.pow(e)))
+}
+
+impl PluralRules {
+    pub fn parse(src: &str) -> Result<PluralRules, Error> {
+        let mut rules = Vec::new();
+        for rule in src.split(';') {
+            // Strip samples
+            let rule = rule.split('@').next().unwrap().trim();
+            if rule.is_empty() {
+                continue;
+            }
+
+            let (category, condition) = match rule.split_once(':') {
+                Some((category, condition)) => (category.trim(), condition.trim()),
+                None => (rule, "")
+            };
+            let category = PluralCategory::parse(category).ok_or(Error::PluralParsing)?;
+
+            if category == PluralCategory::Other {
+                // Always the fallback, its condition doesn't matter
+                continue;
+            }
+            if condition.is_empty() {
+                return Err(Error::PluralParsing);
+            }
+            rules.push((category, Self::parse_condition(condition)?));
+        }
+
+        let mut plural_rules = PluralRules { rules, categories: Vec::new() };
+        let mut categories: Vec<PluralCategory> = samples().map(|n| plural_rules.category(n)).collect();
+        categories.sort();
+        categories.dedup();
+        plural_rules.categories = categories;
+
+        Ok(plural_rules)
+    }
+
+    fn parse_condition(src: &str) -> Result<Condition, Error> {
+        src.split(" or ")
+            .map(|and_chain| and_chain.split(" and ").map(Self::parse_relation).collect::<Result<Vec<_>, _>>())
+            .collect()
+    }
+
+    // operand [(% | mod) value] (= | != | is [not] | [not] in | [not] within) range_list
+    fn parse_relation(src: &str) -> Result<Relation, Error> {
+        // Make sure operators are separated by whitespace
+        let src = src
+            .replace("!=", "\0")
+            .replace('=', " = ")
+            .replace('\0', " != ")
+            .replace('%', " % ");
+        let mut tokens = src.split_whitespace().peekable();
+
+        let operand = match tokens.next() {
+            Some("n" | "i") => Operand::Value,
+            Some("v" | "w" | "f" | "t" | "c" | "e") => Operand::Zero,
+            _ => return Err(Error::PluralParsing)
+        };
+
+        let mut modulo = None;
+        if matches!(tokens.peek(), Some(&"%" | &"mod")) {
+            tokens.next();
+            let value = tokens.next()
+                .and_then(|t| t.parse::<u64>().ok())
+                .filter(|v| *v != 0)
+                .ok_or(Error::PluralParsing)?;
+            modulo = Some(value);
+        }
+
+        let negate = match tokens.next() {
+            Some("=" | "in" | "within") => false,
+            Some("!=") => true,
+            Some("is") => {
+                if tokens.peek() == Some(&"not") {
+                    tokens.next();
+                    true
+                }
+                else {
+                    false
+                }
+            },
+            Some("not") => {
+                if !matches!(tokens.next(), Some("in" | "within")) {
+                    return Err(Error::PluralParsing);
+                }
+                true
+            },
+            _ => return Err(Error::PluralParsing)
+        };
+
+        let range_list: String = tokens.collect();
+        if range_list.is_empty() {
+            return Err(Error::PluralParsing);
+        }
+
+        let mut ranges = Vec::new();
+        for range in range_list.split(',') {
+            let (start, end) = range.split_once("..").unwrap_or((range, range));
+            let start = start.parse::<u64>().map_err(|_| Error::PluralParsing)?;
+            let end = end.parse::<u64>().map_err(|_| Error::PluralParsing)?;
+            ranges.push((start, end));
+        }
+
+        Ok(Relation { operand, modulo, negate, ranges })
+    }
+
+    pub fn category(&self, n: u64) -> PluralCategory {
+        self.rules.iter()
+            .find(|(_, condition)| condition.iter().any(|and_chain| and_chain.iter().all(|r| r.matches(n))))
+            .map(|(category, _)| *category)
+            .unwrap_or(PluralCategory::Other)
+    }
+
+    /// Returns the index of the form to use for `n`.
+    pub fn resolve(&self, n: u64) -> usize {
+        let category = self.category(n);
+        self.categories.iter()
+            .position(|c| *c == category)
+            .unwrap_or(self.categories.len().saturating_sub(1))
+    }
+
+    /// The categories that apply to whole numbers, in form order.
+    pub fn categories(&self) -> &[PluralCategory] {
+        &self.categories
+    }
+}
+
+// Integer rules from CLDR. Compact decimal (e) rules are left out to keep the form counts
+// in line with the usual gettext Plural-Forms for these languages.
+static CARDINAL_RULES: &[(&str, &str)] = &[
+    ("ja", ""), ("zh", ""), ("ko", ""), ("th", ""), ("vi", ""), ("id", ""), ("ms", ""),
+    ("en", "one: i = 1 and v = 0"),
+    ("de", "one: i = 1 and v = 0"),
+    ("nl", "one: i = 1 and v = 0"),
+    ("sv", "one: i = 1 and v = 0"),
+    ("da", "one: n = 1 or t != 0 and i = 0,1"),
+    ("nb", "one: n = 1"),
+    ("no", "one: n = 1"),
+    ("fi", "one: i = 1 and v = 0"),
+    ("et", "one: i = 1 and v = 0"),
+    ("it", "one: i = 1 and v = 0"),
+    ("es", "one: n = 1"),
+    ("ca", "one: i = 1 and v = 0"),
+    ("el", "one: n = 1"),
+    ("hu", "one: n = 1"),
+    ("tr", "one: n = 1"),
+    ("bg", "one: n = 1"),
+    ("pt", "one: i = 0..1"),
+    ("pt-pt", "one: i = 1 and v = 0"),
+    ("fr", "one: i = 0,1"),
+    ("hi", "one: i = 0 or n = 1"),
+    ("fa", "one: i = 0 or n = 1"),
+    ("fil", "one: v = 0 and i = 1,2,3 or v = 0 and i % 10 != 4,6,9"),
+    ("tl", "one: v = 0 and i = 1,2,3 or v = 0 and i % 10 != 4,6,9"),
+    ("is", "one: t = 0 and i % 10 = 1 and i % 100 != 11"),
+    ("mk", "one: v = 0 and i % 10 = 1 and i % 100 != 11"),
+    ("ru", "one: v = 0 and i % 10 = 1 and i % 100 != 11; few: v = 0 and i % 10 = 2..4 and i % 100 != 12..14; many: v = 0 and i % 10 = 0 or v = 0 and i % 10 = 5..9 or v = 0 and i % 100 = 11..14"),
+    ("uk", "one: v = 0 and i % 10 = 1 and i % 100 != 11; few: v = 0 and i % 10 = 2..4 and i % 100 != 12..14; many: v = 0 and i % 10 = 0 or v = 0 and i % 10 = 5..9 or v = 0 and i % 100 = 11..14"),
+    ("be", "one: n % 10 = 1 and n % 100 != 11; few: n % 10 = 2..4 and n % 100 != 12..14; many: n % 10 = 0 or n % 10 = 5..9 or n % 100 = 11..14"),
+    ("pl", "one: i = 1 and v = 0; few: v = 0 and i % 10 = 2..4 and i % 100 != 12..14; many: v = 0 and i != 1 and i % 10 = 0..1 or v = 0 and i % 10 = 5..9 or v = 0 and i % 100 = 12..14"),
+    ("hr", "one: v = 0 and i % 10 = 1 and i % 100 != 11; few: v = 0 and i % 10 = 2..4 and i % 100 != 12..14"),
+    ("sr", "one: v = 0 and i % 10 = 1 and i % 100 != 11; few: v = 0 and i % 10 = 2..4 and i % 100 != 12..14"),
+    ("bs", "one: v = 0 and i % 10 = 1 and i % 100 != 11; few: v = 0 and i % 10 = 2..4 and i % 100 != 12..14"),
+    ("cs", "one: i = 1 and v = 0; few: i = 2..4 and v = 0; many: v != 0"),
+    ("sk", "one: i = 1 and v = 0; few: i = 2..4 and v = 0; many: v != 0"),
+    ("lt", "one: n % 10 = 1 and n % 100 != 11..19; few: n % 10 = 2..9 and n % 100 != 11..19; many: f != 0"),
+    ("lv", "zero: n % 10 = 0 or n % 100 = 11..19; one: n % 10 = 1 and n % 100 != 11"),
+    ("ro", "one: i = 1 and v = 0; few: v != 0 or n = 0 or n != 1 and n % 100 = 1..19"),
+    ("sl", "one: v = 0 and i % 100 = 1; two: v = 0 and i % 100 = 2; few: v = 0 and i % 100 = 3..4 or v != 0"),
+    ("he", "one: i = 1 and v = 0 or i = 0 and v != 0; two: i = 2 and v = 0"),
+    ("ga", "one: n = 1; two: n = 2; few: n = 3..6; many: n = 7..10"),
+    ("cy", "zero: n = 0; one: n = 1; two: n = 2; few: n = 3; many: n = 6"),
+    ("ar", "zero: n = 0; one: n = 1; two: n = 2; few: n % 100 = 3..10; many: n % 100 = 11..99")
+];
+
+static ORDINAL_RULES: &[(&str, &str)] = &[
+    ("en", "one: n % 10 = 1 and n % 100 != 11; two: n % 10 = 2 and n % 100 != 12; few: n % 10 = 3 and n % 100 != 13"),
+    ("fr", "one: n = 1"),
+    ("ms", "one: n = 1"),
+    ("vi", "one: n = 1"),
+    ("fil", "one: n = 1"),
+    ("tl", "one: n = 1"),
+    ("ro", "one: n = 1"),
+    ("hy", "one: n = 1"),
+    ("ga", "one: n = 1"),
+    ("it", "many: n = 11,8,80,800"),
+    ("sv", "one: n % 10 = 1,2 and n % 100 != 11,12"),
+    ("ca", "one: n = 1,3; two: n = 2; few: n = 4"),
+    ("hu", "one: n = 1,5"),
+    ("sq", "one: n = 1; many: n % 10 = 4 and n % 100 != 14"),
+    ("mk", "one: i % 10 = 1 and i % 100 != 11; two: i % 10 = 2 and i % 100 != 12; many: i % 10 = 7,8 and i % 100 != 17,18"),
+    ("kk", "many: n % 10 = 6 or n % 10 = 9 or n % 10 = 0 and n != 0"),
+    ("hi", "one: n = 1; two: n = 2,3; few: n = 4; many: n = 6"),
+    ("bn", "one: n = 1,5,7,8,9,10; two: n = 2,3; few: n = 4; many: n = 6")
+];
+
+fn find_builtin(table: &'static [(&'static str, &'static str)], lang: &str) -> Option<&'static str> {
+    let lang = lang.to_ascii_lowercase().replace('_', "-");
+    let find = |code: &str| table.iter().find(|(c, _)| *c == code).map(|(_, rules)| *rules);
+    find(&lang).or_else(|| find(lang.split('-').next().unwrap()))
+}
+
+/// Looks up the built-in rule set for a language code (e.g. "ru", "pt-PT").
+/// Languages without ordinal rules fall back to the "other" category only.
+pub fn get_builtin(lang: &str, ordinal: bool) -> Option<PluralRules> {
+    let rules = if ordinal {
+        // Known languages without ordinal rules only use "other"
+        find_builtin(ORDINAL_RULES, lang).or_else(|| find_builtin(CARDINAL_RULES, lang).map(|_| ""))?
+    }
+    else {
+        find_builtin(CARDINAL_RULES, lang)?
+    };
+    Some(PluralRules::parse(rules).expect("valid built-in plural rules"))
+}
+
+/// Whether the string looks like a language code rather than a rule or an expression.
+pub fn is_language_code(s: &str) -> bool {
+    s.len() >= 2 && s.chars().all(|c| c.is_ascii_alphabetic() || c == '-' || c == '_')
+}
+
+/// Whether the string is written in CLDR rule syntax (starts with a category name).
+pub fn is_cldr_syntax(s: &str) -> bool {
+    let first = s.split(|c: char| c == ':' || c == ';').next().unwrap_or_default().trim();
+    PluralCategory::parse(first).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use PluralCategory::*;
+
+    #[test]
+    fn parse_conditions() {
+        let cases: &[(&str, &[(u64, PluralCategory)])] = &[
+            ("one: n % 10 = 1 and n % 100 != 11", &[(1, One), (11, Other), (21, One), (101, One), (111, Other), (0, Other)]),
+            ("one: n mod 10 is 1 and n mod 100 is not 11", &[(1, One), (11, Other), (31, One)]),
+            ("few: n = 3..6,10", &[(2, Other), (3, Few), (6, Few), (7, Other), (10, Few)]),
+            ("few: n % 10 = 2..4 and n % 100 != 12..14", &[(2, Few), (12, Other), (14, Other), (22, Few), (25, Other)]),
+            ("one: n not in 2..9", &[(1, One), (2, Other), (9, Other), (10, One)]),
+            ("one: n = 1 or n = 5", &[(1, One), (5, One), (3, Other)]),
+            ("one: n = 1 or n % 10 = 2 and n != 2", &[(1, One), (2, Other), (12, One)]),
+            ("one: i = 1 and v = 0; two: n = 2 @integer 2", &[(1, One), (2, Two), (3, Other)]),
+            ("zero: n = 0; other", &[(0, Zero), (1, Other)]),
+            ("", &[(0, Other), (1, Other)])
+        ];
+
+        for (src, expected) in cases {
+            let rules = PluralRules::parse(src).unwrap();
+            for (n, category) in *expected {
+                assert_eq!(rules.category(*n), *category, "'{}' for {}", src, n);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_errors() {
+        let cases = [
+            "one:",
+            "foo: n = 1",
+            "one: x = 1",
+            "one: n % 0 = 1",
+            "one: n % = 1",
+            "one: n = a",
+            "one: n = 1..",
+            "one: n",
+            "one: n not = 1"
+        ];
+
+        for src in cases {
+            assert!(PluralRules::parse(src).is_err(), "'{}' should fail to parse", src);
+        }
+    }
+
+    #[test]
+    fn builtin_cardinal() {
+        let cases: &[(&str, &[PluralCategory], &[(u64, usize)])] = &[
+            ("en", &[One, Other], &[(0, 1), (1, 0), (2, 1), (11, 1), (21, 1)]),
+            ("ru", &[One, Few, Many], &[
+                (0, 2), (1, 0), (2, 1), (4, 1), (5, 2), (11, 2), (12, 2), (14, 2),
+                (21, 0), (22, 1), (25, 2), (101, 0), (111, 2), (112, 2)
+            ]),
+            ("ar", &[Zero, One, Two, Few, Many, Other], &[
+                (0, 0), (1, 1), (2, 2), (3, 3), (10, 3), (11, 4), (99, 4),
+                (100, 5), (102, 5), (103, 3), (111, 4)
+            ]),
+            ("ja", &[Other], &[(0, 0), (1, 0), (2, 0), (100, 0)])
+        ];
+
+        for (lang, categories, expected) in cases {
+            let rules = get_builtin(lang, false).unwrap();
+            assert_eq!(rules.categories(), *categories, "categories for '{}'", lang);
+            for (n, form) in *expected {
+                assert_eq!(rules.resolve(*n), *form, "'{}' for {}", lang, n);
+            }
+        }
+    }
+
+    #[test]
+    fn builtin_ordinal() {
+        let rules = get_builtin("en", true).unwrap();
+        assert_eq!(rules.categories(), &[One, Two, Few, Other]);
+        for (n, form) in [(1, 0), (2, 1), (3, 2), (4, 3), (11, 3), (12, 3), (13, 3), (21, 0), (22, 1), (23, 2), (111, 3)] {
+            assert_eq!(rules.resolve(n), form, "en ordinal for {}", n);
+        }
+
+        // Known language without ordinal rules
+        assert_eq!(get_builtin("ja", true).unwrap().categories(), &[Other]);
+    }
+
+    #[test]
+    fn builtin_lookup() {
+        assert!(get_builtin("pt-PT", false).is_some());
+        assert!(get_builtin("ru_RU", false).is_some());
+        assert!(get_builtin("xx", false).is_none());
+    }
+}
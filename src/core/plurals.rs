@@ -26,7 +26,7 @@
     SOFTWARE.
 */
 
-use super::Error;
+use super::{plural_rules::{self, PluralRules}, Error};
 use self::Resolver::*;
 
 #[derive(Clone, Debug)]
@@ -36,6 +36,8 @@ pub enum Resolver {
     Expr(Ast),
     /// A function
     Function(fn(u64) -> usize),
+    /// CLDR plural rules
+    Rules(PluralRules),
 }
 
 impl Default for Resolver {
@@ -374,6 +376,34 @@ impl Resolver {
         match *self {
             Expr(ref ast) => ast.resolve(n),
             Function(ref f) => f(n),
+            Rules(ref rules) => rules.resolve(n),
+        }
+    }
+
+    /// Parses a language code with built-in rules (e.g. "ru"), CLDR plural rules or a gettext expression.
+    pub fn parse(src: &str, ordinal: bool) -> Result<Resolver, Error> {
+        let src = src.trim();
+        if plural_rules::is_language_code(src) {
+            return plural_rules::get_builtin(src, ordinal)
+                .map(Rules)
+                .ok_or_else(|| Error::RuntimeError(format!("No built-in plural rules for language: {}", src)));
+        }
+
+        if plural_rules::is_cldr_syntax(src) {
+            return Ok(Rules(PluralRules::parse(src)?));
+        }
+
+        Ok(Expr(Ast::parse(src)?))
+    }
+
+    /// Returns the number of forms the resolver can return.
+    pub fn form_count(&self) -> usize {
+        match *self {
+            Rules(ref rules) => rules.categories().len(),
+            _ => plural_rules::samples()
+                .map(|n| self.resolve(n))
+                .max()
+                .unwrap_or(0) + 1
         }
     }
 }
\ No newline at end of file
@@ -4,22 +4,99 @@
     Syntax:
     - Filter: $(filter_name arg1 arg2 arg3 ...)
       Loosely based on Bash command substitution syntax.
+      Arguments can be identifiers, numbers, 'strings' or $variables.
+    - Variable: ${variable_name}
+      Provided by the context (see Context::get_variable). Undefined variables
+      evaluate to an empty string, both on their own and as filter arguments.
 
-    More expression types might be added later, but the filter expression
-    is already suitable for most if not all use cases.
+    Conditionals are implemented as filters (see template_filters).
 */
+use std::{cell::RefCell, fmt};
+
 use fnv::FnvHashMap;
 
+#[derive(Clone)]
 pub enum Token {
     Identifier(String),
     NumberLit(f64),
-    StringLit(String)
+    StringLit(String),
+    // Resolved before being passed to filters
+    Variable(String)
+}
+
+impl Token {
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Token::NumberLit(n) => Some(*n),
+            Token::StringLit(s) => s.parse().ok(),
+            _ => None
+        }
+    }
+
+    /// Zero, empty strings and `false` (as an identifier or string) are false, anything else is true.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Token::NumberLit(n) => *n != 0.0,
+            Token::StringLit(s) => !(s.is_empty() || s == "0" || s == "false"),
+            Token::Identifier(s) => s != "false",
+            Token::Variable(_) => false
+        }
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Identifier(s) | Token::StringLit(s) => f.write_str(s),
+            Token::NumberLit(n) => write!(f, "{}", n),
+            Token::Variable(name) => write!(f, "${}", name)
+        }
+    }
+}
+
+impl PartialEq for Token {
+    fn eq(&self, other: &Self) -> bool {
+        if let (Token::NumberLit(a), Token::NumberLit(b)) = (self, other) {
+            return a == b;
+        }
+        self.to_string() == other.to_string()
+    }
 }
 
 pub type Filter = fn(args: &[Token]) -> Option<String>;
+/// Fallback for filters that aren't known ahead of time.
+pub type DynamicFilter = fn(name: &str, args: &[Token]) -> Option<String>;
 
 pub trait Context {
     fn on_filter_eval(&mut self, name: &str, args: &[Token]) -> Option<String>;
+
+    /// Returns the value of a variable. Defaults to the global variables set by hooks.
+    fn get_variable(&mut self, name: &str) -> Option<Token> {
+        get_global_variable(name)
+    }
+
+    /// Called when part of the template couldn't be evaluated (and was left as is).
+    fn on_eval_error(&mut self, _pos: usize, _message: &str) {}
+}
+
+thread_local! {
+    static GLOBAL_VARIABLES: RefCell<FnvHashMap<String, Token>> = RefCell::new(FnvHashMap::default());
+}
+
+/// Sets (or removes, if value is None) a variable available to all templates evaluated on this thread.
+pub fn set_global_variable(name: &str, value: Option<Token>) {
+    GLOBAL_VARIABLES.with_borrow_mut(|vars| {
+        if let Some(value) = value {
+            vars.insert(name.to_owned(), value);
+        }
+        else {
+            vars.remove(name);
+        }
+    });
+}
+
+pub fn get_global_variable(name: &str) -> Option<Token> {
+    GLOBAL_VARIABLES.with_borrow(|vars| vars.get(name).cloned())
 }
 
 struct EmptyContext();
@@ -39,7 +116,8 @@ impl Context for FilterRemovalContext {
 }
 
 pub struct Parser {
-    filters: FnvHashMap<String, Filter>
+    filters: FnvHashMap<String, Filter>,
+    dynamic_filter: Option<DynamicFilter>
 }
 
 impl Parser {
@@ -49,26 +127,46 @@ impl Parser {
             filters.insert(name.to_string(), filter.to_owned());
         }
 
-        Parser { filters }
+        Parser { filters, dynamic_filter: None }
+    }
+
+    /// Sets the filter that gets evaluated when no other filter matches the name.
+    pub fn with_dynamic_filter(mut self, filter: DynamicFilter) -> Parser {
+        self.dynamic_filter = Some(filter);
+        self
     }
 
     fn eval_filter(&self, tokens: &Vec<Token>, context: &mut impl Context) -> Option<String> {
         if tokens.is_empty() { return None; }
 
         if let Token::Identifier(filter_name) = tokens.first().unwrap() {
-            let args = &tokens.as_slice()[1..];
-            let context_res = context.on_filter_eval(filter_name, args);
+            let args: Vec<Token> = tokens[1..].iter()
+                .map(|token| match token {
+                    Token::Variable(name) => context.get_variable(name)
+                        .unwrap_or_else(|| Token::StringLit(String::new())),
+                    _ => token.clone()
+                })
+                .collect();
+
+            let context_res = context.on_filter_eval(filter_name, &args);
             if context_res.is_some() {
                 return context_res
             }
             else if let Some(filter) = self.filters.get(filter_name) {
-                return filter(&tokens.as_slice()[1..]);
+                return filter(&args);
+            }
+            else if let Some(dynamic_filter) = self.dynamic_filter {
+                return dynamic_filter(filter_name, &args);
             }
         }
 
         None
     }
 
+    fn is_identifier(input: &str) -> bool {
+        !input.is_empty() && input.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    }
+
     fn parse_token(input: &str) -> Option<Token> {
         let mut iter = input.chars();
         let start_char = iter.next().unwrap(); // guaranteed to have at least one char
@@ -92,7 +190,13 @@ impl Parser {
             }
         }
 
-        if input.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        if let Some(name) = input.strip_prefix('$') {
+            if Self::is_identifier(name) {
+                return Some(Token::Variable(name.to_owned()));
+            }
+        }
+
+        if Self::is_identifier(input) {
             return Some(Token::Identifier(input.to_owned()));
         }
 
@@ -108,7 +212,9 @@ impl Parser {
 
         let mut start_expr = false;
         let mut in_filter = false;
+        let mut in_variable = false;
         let mut checkpoint: usize = 0;
+        let mut expr_start: usize = 0;
         let mut tokens: Vec<Token> = Vec::new();
         let mut token_start: usize = 0;
         let mut in_string = false;
@@ -143,6 +249,7 @@ impl Parser {
                             }
                             else {
                                 warn!("Invalid token in '{}' (at pos {})", input, token_start);
+                                context.on_eval_error(token_start, "invalid token");
                                 token_start = 0;
                                 tokens.clear();
                                 in_filter = false;
@@ -156,6 +263,7 @@ impl Parser {
                         }
                         else {
                             warn!("Filter evaluation failed in '{}' (at pos {})", input, i);
+                            context.on_eval_error(expr_start, "filter evaluation failed");
                         }
 
                         tokens.clear();
@@ -169,6 +277,7 @@ impl Parser {
                         }
                         else {
                             warn!("Invalid token in '{}' (at pos {})", input, token_start);
+                            context.on_eval_error(token_start, "invalid token");
                             tokens.clear();
                             in_filter = false;
                         }
@@ -196,11 +305,38 @@ impl Parser {
                 continue;
             }
 
+            if in_variable {
+                if c == b'}' {
+                    let name = &input[token_start..i];
+                    output.truncate(checkpoint);
+                    if let Some(value) = context.get_variable(name) {
+                        output.extend(value.to_string().bytes());
+                    }
+                    else {
+                        debug!("Undefined variable '{}' in '{}'", name, input);
+                    }
+                    in_variable = false;
+                    token_start = 0;
+                    continue;
+                }
+                else if c.is_ascii_alphanumeric() || c == b'_' {
+                    continue;
+                }
+
+                // Not a variable, leave it as is
+                in_variable = false;
+                token_start = 0;
+            }
+
             if start_expr {
                 // Check expression opening
                 if c == b'(' { // Filter expression
                     in_filter = true;
                 }
+                else if c == b'{' { // Variable expression
+                    in_variable = true;
+                    token_start = i + 1;
+                }
                 start_expr = false;
                 continue;
             }
@@ -209,6 +345,7 @@ impl Parser {
             if c == b'$' {
                 start_expr = true;
                 checkpoint = output.len() - 1; // before the starting char
+                expr_start = i;
             }
         }
 
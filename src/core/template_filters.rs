@@ -1,18 +1,48 @@
-use super::{template, Hachimi};
+use std::{cell::RefCell, sync::Arc};
 
-pub static LIST: [(&str, template::Filter); 3] = [
+use chrono::{DateTime, Datelike, Local, NaiveDate};
+
+use super::{hachimi::LocalizedData, template, Hachimi};
+
+pub static LIST: [(&str, template::Filter); 9] = [
     ("plural", plural),
     ("ordinal", ordinal),
-    ("month", month)
+    ("month", month),
+    ("if", if_),
+    ("if_eq", if_eq),
+    ("select", select),
+    ("number", number),
+    ("date", date),
+    ("weekday", weekday)
 ];
 
+const DEFAULT_DATE_FORMAT: &str = "{year}/{month}/{day}";
+
+thread_local! {
+    // Used instead of the loaded localized data while linting another repo
+    static LOCALIZED_DATA_OVERRIDE: RefCell<Option<Arc<LocalizedData>>> = const { RefCell::new(None) };
+}
+
+fn localized_data() -> Arc<LocalizedData> {
+    LOCALIZED_DATA_OVERRIDE.with_borrow(|ld| ld.clone())
+        .unwrap_or_else(|| Hachimi::instance().localized_data.load_full())
+}
+
+/// Evaluates filters on this thread against the given localized data instead of the loaded one.
+/// Doesn't need Hachimi to be initialized.
+pub fn with_localized_data<R>(localized_data: Arc<LocalizedData>, f: impl FnOnce() -> R) -> R {
+    LOCALIZED_DATA_OVERRIDE.set(Some(localized_data));
+    let res = f();
+    LOCALIZED_DATA_OVERRIDE.set(None);
+    res
+}
+
 // $(plural n 'plural_type_0' 'plural_type_1' ...)
 fn plural(args: &[template::Token]) -> Option<String> {
     if args.len() < 2 { return None; }
 
     if let template::Token::NumberLit(n) = args[0] {
-        let hachimi = Hachimi::instance();
-        let plural_type = 1 + hachimi.localized_data.load().plural_form.resolve(n as u64);
+        let plural_type = 1 + localized_data().plural_form.resolve(n as u64);
         let res = args.get(plural_type)?;
         if let template::Token::StringLit(str) = res {
             return Some(str.replace("$", &n.to_string()));
@@ -25,7 +55,7 @@ fn plural(args: &[template::Token]) -> Option<String> {
 // $(ordinal n)
 fn ordinal(args: &[template::Token]) -> Option<String> {
     if let template::Token::NumberLit(n) = args[0] {
-        let localized_data = Hachimi::instance().localized_data.load();
+        let localized_data = localized_data();
         let i = localized_data.ordinal_form.resolve(n as u64);
         let ordinal_type = localized_data.config.ordinal_types.get(i)?;
         return Some(ordinal_type.replace("$", &n.to_string()));
@@ -37,9 +67,167 @@ fn ordinal(args: &[template::Token]) -> Option<String> {
 // $(month n)
 fn month(args: &[template::Token]) -> Option<String> {
     if let template::Token::NumberLit(i) = args[0] {
-        let localized_data = Hachimi::instance().localized_data.load();
+        let localized_data = localized_data();
         return localized_data.config.months.get((i as usize).saturating_sub(1)).cloned();
     }
 
     None
-}
\ No newline at end of file
+}
+
+fn string_arg(args: &[template::Token], i: usize) -> Option<String> {
+    args.get(i).map(|t| t.to_string())
+}
+
+// $(if value 'then' 'else')
+// else is optional
+fn if_(args: &[template::Token]) -> Option<String> {
+    let cond = args.first()?;
+    if cond.is_truthy() {
+        string_arg(args, 1)
+    }
+    else {
+        Some(string_arg(args, 2).unwrap_or_default())
+    }
+}
+
+// $(if_eq a b 'then' 'else')
+// else is optional
+fn if_eq(args: &[template::Token]) -> Option<String> {
+    if args.len() < 3 { return None; }
+
+    if args[0] == args[1] {
+        string_arg(args, 2)
+    }
+    else {
+        Some(string_arg(args, 3).unwrap_or_default())
+    }
+}
+
+// $(select value key1 'result1' key2 'result2' ... 'default')
+// default is optional
+fn select(args: &[template::Token]) -> Option<String> {
+    let value = args.first()?;
+    let mut pairs = args[1..].chunks_exact(2);
+    for pair in pairs.by_ref() {
+        if pair[0] == *value {
+            return Some(pair[1].to_string());
+        }
+    }
+
+    Some(pairs.remainder().first().map(|t| t.to_string()).unwrap_or_default())
+}
+
+// $(number n decimals)
+// decimals is optional
+fn number(args: &[template::Token]) -> Option<String> {
+    let n = args.first()?.as_number()?;
+    let decimals = match args.get(1) {
+        Some(t) => Some(t.as_number()? as usize),
+        None => None
+    };
+
+    let localized_data = localized_data();
+    let group_separator = localized_data.config.number_group_separator.as_deref().unwrap_or(",");
+    let decimal_separator = localized_data.config.number_decimal_separator.as_deref().unwrap_or(".");
+
+    let digits = match decimals {
+        Some(decimals) => format!("{:.*}", decimals, n.abs()),
+        None => n.abs().to_string()
+    };
+    let (int_part, frac_part) = match digits.split_once('.') {
+        Some((int_part, frac_part)) => (int_part, Some(frac_part)),
+        None => (digits.as_str(), None)
+    };
+
+    let mut res = String::with_capacity(digits.len() + int_part.len() / 3 * group_separator.len() + 1);
+    if n < 0.0 && digits.bytes().any(|c| c.is_ascii_digit() && c != b'0') {
+        res.push('-');
+    }
+    for (i, c) in int_part.chars().enumerate() {
+        if i != 0 && (int_part.len() - i) % 3 == 0 {
+            res.push_str(group_separator);
+        }
+        res.push(c);
+    }
+    if let Some(frac_part) = frac_part {
+        res.push_str(decimal_separator);
+        res.push_str(frac_part);
+    }
+
+    Some(res)
+}
+
+// Parses either "y m d" or a unix timestamp from the start of the args.
+// Returns the date and the number of args consumed.
+fn parse_date(args: &[template::Token]) -> Option<(NaiveDate, usize)> {
+    let first = args.first()?.as_number()?;
+    if let (Some(month), Some(day)) = (
+        args.get(1).and_then(|t| t.as_number()),
+        args.get(2).and_then(|t| t.as_number())
+    ) {
+        return Some((NaiveDate::from_ymd_opt(first as i32, month as u32, day as u32)?, 3));
+    }
+
+    let date = DateTime::from_timestamp(first as i64, 0)?.with_timezone(&Local).date_naive();
+    Some((date, 1))
+}
+
+fn weekday_name(date: &NaiveDate) -> Option<String> {
+    let localized_data = localized_data();
+    localized_data.config.weekdays.get(date.weekday().num_days_from_sunday() as usize).cloned()
+}
+
+// $(date y m d 'format')
+// $(date unix_timestamp 'format')
+// format is optional, defaults to the date_format in the localized data config
+// Placeholders: {year}, {month}, {day}, {month_name}, {weekday}
+fn date(args: &[template::Token]) -> Option<String> {
+    let (date, consumed) = parse_date(args)?;
+
+    let localized_data = localized_data();
+    let format = match args.get(consumed) {
+        Some(template::Token::StringLit(format)) => format.as_str(),
+        _ => localized_data.config.date_format.as_deref().unwrap_or(DEFAULT_DATE_FORMAT)
+    };
+
+    let mut res = format
+        .replace("{year}", &date.year().to_string())
+        .replace("{month}", &date.month().to_string())
+        .replace("{day}", &date.day().to_string());
+    if res.contains("{month_name}") {
+        let month_name = localized_data.config.months.get(date.month0() as usize)?;
+        res = res.replace("{month_name}", month_name);
+    }
+    if res.contains("{weekday}") {
+        res = res.replace("{weekday}", &weekday_name(&date)?);
+    }
+
+    Some(res)
+}
+
+// $(weekday n) (0 = Sunday)
+// $(weekday y m d)
+// $(weekday unix_timestamp)
+fn weekday(args: &[template::Token]) -> Option<String> {
+    if args.len() == 1 {
+        if let template::Token::NumberLit(i) = args[0] {
+            if (0.0..7.0).contains(&i) {
+                let localized_data = localized_data();
+                return localized_data.config.weekdays.get(i as usize).cloned();
+            }
+        }
+    }
+
+    let (date, _) = parse_date(args)?;
+    weekday_name(&date)
+}
+
+// $(table_name key)
+// Lookup tables declared in the localized data config's filter_tables.
+// The "_" entry is used as a fallback for keys that aren't in the table.
+pub fn repo_filter(name: &str, args: &[template::Token]) -> Option<String> {
+    let localized_data = localized_data();
+    let table = localized_data.config.filter_tables.get(name)?;
+    let key = args.first()?.to_string();
+    table.get(&key).or_else(|| table.get("_")).cloned()
+}
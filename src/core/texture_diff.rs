@@ -0,0 +1,193 @@
+use std::{fs, path::Path};
+
+use serde::Deserialize;
+
+use super::{utils, Error};
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum BlendMode {
+    // Transparent pixels keep the original, #FF00FF makes it transparent, anything else replaces it
+    #[default]
+    Replace,
+    AlphaBlend,
+    Multiply
+}
+
+/// A patch image placed at an offset from the top left of the texture.
+#[derive(Deserialize)]
+pub struct DiffLayer {
+    // Relative to the manifest
+    pub path: String,
+    #[serde(default)]
+    pub x: u32,
+    #[serde(default)]
+    pub y: u32,
+    #[serde(default)]
+    pub mode: BlendMode
+}
+
+// .diff.json
+#[derive(Deserialize)]
+struct DiffManifest {
+    // Applied in order, later layers are drawn over earlier ones
+    layers: Vec<DiffLayer>
+}
+
+/// A texture diff, either a full size .diff.png or a .diff.json manifest of layered region patches.
+pub struct TextureDiff {
+    layers: Vec<(DiffLayer, Vec<u8>)>,
+    // Plain diffs must cover the whole texture
+    full_size: bool
+}
+
+impl TextureDiff {
+    /// Loads the diff at the path (a manifest if it ends with .json), returns None if it doesn't exist.
+    pub fn load(path: &Path) -> Option<Result<TextureDiff, Error>> {
+        let data = fs::read(path).ok()?;
+        if path.extension().is_some_and(|e| e == "json") {
+            Some(Self::load_manifest(path, &data))
+        }
+        else {
+            let layer = DiffLayer { path: String::new(), x: 0, y: 0, mode: BlendMode::Replace };
+            Some(Ok(TextureDiff { layers: vec![(layer, data)], full_size: true }))
+        }
+    }
+
+    fn load_manifest(path: &Path, data: &[u8]) -> Result<TextureDiff, Error> {
+        let manifest: DiffManifest = serde_json::from_slice(data)?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+
+        let mut layers = Vec::with_capacity(manifest.layers.len());
+        for layer in manifest.layers {
+            if layer.path.contains("..") || Path::new(&layer.path).has_root() {
+                return Err(Error::RuntimeError(format!("Invalid diff layer path '{}'", layer.path)));
+            }
+            let layer_path = dir.join(&layer.path);
+            let png = fs::read(&layer_path).map_err(|e|
+                Error::RuntimeError(format!("Failed to read diff layer '{}': {}", layer_path.display(), e))
+            )?;
+            layers.push((layer, png));
+        }
+
+        Ok(TextureDiff { layers, full_size: false })
+    }
+
+    /// Checks that every layer fits in a texture of the given size, only reads the PNG headers.
+    pub fn check_size(&self, width: u32, height: u32) -> Result<(), Error> {
+        for (layer, png) in &self.layers {
+            let reader = png::Decoder::new(png.as_slice()).read_info()
+                .map_err(|e| Error::RuntimeError(format!("Failed to load texture diff: {}", e)))?;
+            let info = reader.info();
+
+            let fits = if self.full_size {
+                info.width == width && info.height == height
+            }
+            else {
+                layer.x.checked_add(info.width).is_some_and(|right| right <= width) &&
+                layer.y.checked_add(info.height).is_some_and(|bottom| bottom <= height)
+            };
+            if !fits {
+                return Err(Error::RuntimeError(format!(
+                    "Texture diff size mismatch (expected {}x{}, got {}x{} at {},{})",
+                    width, height, info.width, info.height, layer.x, layer.y
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn update_hasher(&self, hasher: &mut blake3::Hasher) {
+        for (layer, png) in &self.layers {
+            hasher.update(&layer.x.to_le_bytes());
+            hasher.update(&layer.y.to_le_bytes());
+            hasher.update(&[layer.mode as u8]);
+            hasher.update(&(png.len() as u64).to_le_bytes());
+            hasher.update(png);
+        }
+    }
+
+    /// Applies the diff to the original pixels (RGBA, bottom row first) and encodes the result as PNG.
+    pub fn apply(&self, orig_pixels: &[u8], width: usize, height: usize) -> Result<Vec<u8>, Error> {
+        let row_len = width * 4;
+        if orig_pixels.len() != row_len * height {
+            return Err(Error::RuntimeError("Original texture size mismatch".to_owned()));
+        }
+
+        // Original image is flipped
+        let mut pixels = vec![0u8; orig_pixels.len()];
+        for y in 0..height {
+            let src = (height - y - 1) * row_len;
+            pixels[y * row_len..(y + 1) * row_len].copy_from_slice(&orig_pixels[src..src + row_len]);
+        }
+
+        for (layer, png) in &self.layers {
+            let Some((layer_pixels, info)) = utils::load_rgba_png(png.as_slice()) else {
+                return Err(Error::RuntimeError("Failed to load texture diff".to_owned()));
+            };
+            let (layer_width, layer_height) = (info.width as usize, info.height as usize);
+            let (offset_x, offset_y) = (layer.x as usize, layer.y as usize);
+            let fits = offset_x.checked_add(layer_width).is_some_and(|right| right <= width) &&
+                offset_y.checked_add(layer_height).is_some_and(|bottom| bottom <= height);
+            if !fits {
+                return Err(Error::RuntimeError("Texture diff layer out of bounds".to_owned()));
+            }
+
+            for y in 0..layer_height {
+                for x in 0..layer_width {
+                    let src_start = (y * layer_width + x) * 4;
+                    let dst_start = ((offset_y + y) * width + offset_x + x) * 4;
+                    blend(
+                        layer.mode,
+                        &layer_pixels[src_start..src_start + 4],
+                        &mut pixels[dst_start..dst_start + 4]
+                    );
+                }
+            }
+        }
+
+        utils::encode_rgba_png(&pixels, width as u32, height as u32)
+    }
+}
+
+fn blend(mode: BlendMode, src: &[u8], dst: &mut [u8]) {
+    match mode {
+        BlendMode::Replace => {
+            if src[3] == 0 {
+                // Keep the original pixel
+            }
+            else if src == [255, 0, 255, 255] {
+                // Make pixel transparent if it's #FF00FF
+                dst.fill(0);
+            }
+            else {
+                dst.copy_from_slice(src);
+            }
+        }
+
+        BlendMode::AlphaBlend => {
+            // Straight alpha "over" compositing
+            let src_a = src[3] as u32;
+            let dst_a = dst[3] as u32 * (255 - src_a) / 255;
+            let out_a = src_a + dst_a;
+            if out_a == 0 {
+                dst.fill(0);
+                return;
+            }
+            for i in 0..3 {
+                dst[i] = ((src[i] as u32 * src_a + dst[i] as u32 * dst_a) / out_a) as u8;
+            }
+            dst[3] = out_a as u8;
+        }
+
+        BlendMode::Multiply => {
+            // Alpha controls the strength, the original alpha is kept
+            let src_a = src[3] as u32;
+            for i in 0..3 {
+                let d = dst[i] as u32;
+                let multiplied = d * src[i] as u32 / 255;
+                dst[i] = ((multiplied * src_a + d * (255 - src_a)) / 255) as u8;
+            }
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, SystemTime}
+};
+
+use fnv::FnvHashMap;
+
+use super::{texture_diff::TextureDiff, Error, Hachimi};
+
+/// Patched textures generated from texture diffs, stored in the data dir by content hash.
+pub const CACHE_DIR: &str = "texture_cache";
+// Entries are regenerated once they're this old. The key doesn't cover the original pixels, so this
+// is how long a texture the game redraws at the same size can keep showing the old patched version.
+const MAX_ENTRY_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+fn get_path(key: &str) -> PathBuf {
+    Hachimi::instance().get_data_path(CACHE_DIR).join(format!("{}.png", key))
+}
+
+// (asset path, diff path, width, height) -> key, so a cache hit doesn't even need the diff to be loaded.
+// Diffs can be shared by several textures (e.g. the common petit diffs), so the asset path is part of it.
+type MemoKey = (PathBuf, PathBuf, usize, usize);
+static KEYS: Mutex<Option<FnvHashMap<MemoKey, String>>> = Mutex::new(None);
+
+/// Cache key of a texture patched with a diff, from the asset path, the texture size and the diff's
+/// contents. The original pixels aren't hashed since reading them back from the GPU is what the
+/// cache is there to avoid; an original that changes without changing size is picked up when the
+/// diff changes or the entry reaches its max age.
+pub fn get_key(asset_path: &Path, width: usize, height: usize, diff: &TextureDiff) -> String {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(asset_path.to_string_lossy().as_bytes());
+    hasher.update(&(width as u64).to_le_bytes());
+    hasher.update(&(height as u64).to_le_bytes());
+    diff.update_hasher(&mut hasher);
+    hasher.finalize().to_hex().to_string()
+}
+
+/// Key previously computed for the texture and diff, if any.
+pub fn get_memoized_key(asset_path: &Path, diff_path: &Path, width: usize, height: usize) -> Option<String> {
+    let memo_key = (asset_path.to_owned(), diff_path.to_owned(), width, height);
+    KEYS.lock().unwrap().as_ref()?.get(&memo_key).cloned()
+}
+
+pub fn memoize_key(asset_path: &Path, diff_path: &Path, width: usize, height: usize, key: &str) {
+    let memo_key = (asset_path.to_owned(), diff_path.to_owned(), width, height);
+    KEYS.lock().unwrap().get_or_insert_with(FnvHashMap::default).insert(memo_key, key.to_owned());
+}
+
+/// Forgets the memoized keys, for when the diffs on disk change.
+pub fn clear_keys() {
+    *KEYS.lock().unwrap() = None;
+}
+
+/// Loads a cached PNG, entries past their max age count as missing.
+pub fn load(key: &str) -> Option<Vec<u8>> {
+    let path = get_path(key);
+    if is_expired(&path) {
+        return None;
+    }
+    fs::read(&path).ok()
+}
+
+// Age is counted from when the entry was written, using it doesn't extend it
+fn is_expired(path: &Path) -> bool {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .is_ok_and(|mtime| SystemTime::now().duration_since(mtime).unwrap_or_default() > MAX_ENTRY_AGE)
+}
+
+pub fn store(key: &str, png: &[u8]) -> Result<(), Error> {
+    let path = get_path(key);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    // Written separately and swapped in so a concurrent load never sees a partial file
+    let tmp_path = path.with_extension("png.tmp");
+    fs::write(&tmp_path, png)?;
+    fs::rename(&tmp_path, &path)?;
+    Ok(())
+}
+
+/// Removes expired cache entries, runs in the background.
+pub fn collect_garbage() {
+    std::thread::spawn(|| {
+        let cache_dir = Hachimi::instance().get_data_path(CACHE_DIR);
+        let Ok(entries) = fs::read_dir(&cache_dir) else {
+            return;
+        };
+
+        let mut removed = 0;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let is_stale = is_expired(&path);
+            // Leftover temp files from an interrupted write
+            let is_tmp = path.extension().is_some_and(|e| e == "tmp");
+
+            if (is_stale || is_tmp) && fs::remove_file(&path).is_ok() {
+                removed += 1;
+            }
+        }
+
+        if removed > 0 {
+            info!("Removed {} stale texture cache entries", removed);
+        }
+    });
+}
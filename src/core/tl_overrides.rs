@@ -0,0 +1,88 @@
+use std::{fs, path::{Path, PathBuf}};
+
+use super::{hachimi::LocalizedData, Hachimi};
+
+/// User override dir in the data dir, mirrors the layout of the active translation repo.
+/// It's never touched by the updater so the overrides survive repo updates.
+pub const OVERRIDES_DIR: &str = "tl_overrides";
+
+pub enum OverrideStatus {
+    // Number of top level keys merged into the dict
+    MergedDict(usize),
+    ReplacedAsset,
+    AddedAsset,
+    // Not a dict or an asset of the active localized data
+    Unused
+}
+
+pub struct OverrideEntry {
+    pub path: String,
+    pub status: OverrideStatus
+}
+
+pub fn get_dir() -> PathBuf {
+    Hachimi::instance().get_data_path(OVERRIDES_DIR)
+}
+
+/// Lists the files in the override dir and how they're applied to the current localized data.
+pub fn list() -> Vec<OverrideEntry> {
+    let dir = get_dir();
+    let localized_data = Hachimi::instance().localized_data.load();
+
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.clone()];
+    while let Some(current) = dirs.pop() {
+        let Ok(entries) = fs::read_dir(&current) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                dirs.push(path);
+            }
+            else if let Ok(rel_path) = path.strip_prefix(&dir) {
+                files.push(rel_path.to_owned());
+            }
+        }
+    }
+    files.sort();
+
+    files.into_iter()
+        .map(|rel_path| OverrideEntry {
+            status: get_status(&localized_data, &dir, &rel_path),
+            path: rel_path.to_string_lossy().replace('\\', "/")
+        })
+        .collect()
+}
+
+fn get_status(localized_data: &LocalizedData, dir: &Path, rel_path: &Path) -> OverrideStatus {
+    let config = &localized_data.config;
+    let is_dict = [
+        &config.localize_dict,
+        &config.hashed_dict,
+        &config.text_data_dict,
+        &config.character_system_text_dict,
+        &config.race_jikkyo_comment_dict,
+        &config.race_jikkyo_message_dict,
+        &config.glossary
+    ].iter().any(|p| p.as_ref().is_some_and(|p| Path::new(p) == rel_path));
+
+    if is_dict {
+        let key_count = fs::read_to_string(dir.join(rel_path)).ok()
+            .and_then(|json| serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(&json).ok())
+            .map_or(0, |map| map.len());
+        return OverrideStatus::MergedDict(key_count);
+    }
+
+    let Some(asset_path) = config.assets_dir.as_ref().and_then(|d| rel_path.strip_prefix(d).ok()) else {
+        return OverrideStatus::Unused;
+    };
+    let replaces_asset = localized_data.get_repo_assets_path(asset_path)
+        .is_some_and(|p| LocalizedData::asset_exists(&p));
+    if replaces_asset {
+        OverrideStatus::ReplacedAsset
+    }
+    else {
+        OverrideStatus::AddedAsset
+    }
+}
@@ -1,4 +1,4 @@
-use std::{collections::HashSet, fs, io::{Read, Write, Cursor}, path::{Path, PathBuf}, sync::{atomic::{self, AtomicUsize, AtomicBool, AtomicU64}, Arc, Mutex}, thread, cmp::{min, max}};
+use std::{collections::HashSet, fs, io::{Read, Write, Cursor}, path::{Path, PathBuf}, sync::{atomic::{self, AtomicUsize, AtomicBool, AtomicU32, AtomicU64}, Arc, Mutex}, thread, cmp::{min, max}};
 
 use arc_swap::ArcSwap;
 use crossbeam_channel::unbounded;
@@ -9,7 +9,7 @@ use size::Size;
 use thread_priority::{ThreadBuilderExt, ThreadPriority};
 
 use crate::core::game::Region;
-use super::{gui::{NotificationGuard, SimpleYesNoDialog, TranslationRepoUpdateWindow}, hachimi::LocalizedData, http::{self, ureq_config, AsyncRequest}, utils, Error, Gui, Hachimi};
+use super::{delta_patch, gui::{NotificationGuard, SimpleYesNoDialog, TranslationRepoUpdateWindow}, hachimi::LocalizedData, http::{self, rewrite_url, ureq_config, AsyncRequest}, ipc, plugin_events, utils, Error, Gui, Hachimi};
 use once_cell::sync::Lazy;
 
 #[derive(Deserialize)]
@@ -20,7 +20,10 @@ pub struct RepoInfo {
     #[serde(default)]
     pub language: Option<String>,
     #[serde(default)]
-    pub region: Region
+    pub region: Region,
+    // Base64 ed25519 key the repo index is signed with, pinned when the repo is added
+    #[serde(default)]
+    pub public_key: Option<String>
 }
 
 static SYS_LOCALE: Lazy<String> = Lazy::new(|| {
@@ -43,7 +46,7 @@ impl RepoInfo {
 }
 
 // localized_data/info.json
-#[derive(Deserialize, Clone, Default)]
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct LocalRepoInfo {
     #[serde(default)]
     pub name: String,
@@ -61,6 +64,9 @@ pub struct LocalRepoInfo {
     pub contributors: serde_json::Value,
     #[serde(default)]
     pub language: String,
+    // Base64 ed25519 key, pinned on the next update if the repo has no pinned key yet
+    #[serde(default)]
+    pub public_key: String,
 }
 
 impl LocalRepoInfo {
@@ -155,14 +161,45 @@ struct RepoIndex {
     base_url: String,
     zip_url: String,
     zip_dir: String,
-    files: Vec<RepoFile>
+    // blake3 of the zip, verified after downloading it
+    #[serde(default)]
+    zip_hash: Option<String>,
+    files: Vec<RepoFile>,
+    #[serde(default)]
+    revision: Option<String>,
+    // Index of a specific revision, "{revision}" is replaced with the revision
+    #[serde(default)]
+    revision_index_url: Option<String>,
+    // Only used if the repo has no pinned key yet
+    #[serde(default)]
+    public_key: Option<String>
 }
 
 #[derive(Deserialize, Clone)]
 struct RepoFile {
     path: String,
     hash: String,
-    size: usize
+    size: usize,
+    // Patches to this version of the file from older versions
+    #[serde(default)]
+    patches: Vec<FilePatch>,
+    // Patch picked for the installed version of the file
+    #[serde(skip)]
+    delta_patch: Option<FilePatch>
+}
+
+#[derive(Deserialize, Clone)]
+struct FilePatch {
+    // Hash of the version the patch applies to
+    from: String,
+    // Relative to base_url
+    path: String,
+    size: usize,
+    // Hash of the patch file itself, patches without one are never used
+    #[serde(default)]
+    hash: Option<String>,
+    #[serde(default)]
+    format: delta_patch::PatchFormat
 }
 
 impl RepoFile {
@@ -190,24 +227,30 @@ impl RepoFile {
 
 #[derive(Clone)]
 struct UpdateInfo {
+    repo_id: u32,
     base_url: String,
     zip_url: String,
     zip_dir: String,
+    zip_hash: Option<String>,
     files: Vec<RepoFile>, // only contains files needed for update
     is_new_repo: bool,
     cached_files: FnvHashMap<String, String>, // from repo cache
     size: usize,
-    // New fields for better user communication, idk why it complains about these never being read
-    #[allow(dead_code)]
+    // Space needed on disk, patched files take as much space as the full ones
+    disk_size: usize,
+    // New fields for better user communication
     update_size: usize,      // Size of changed files only
+    // idk why it complains about this never being read
     #[allow(dead_code)]
     total_size: usize,       // Total size of all files (for ZIP downloads)
     will_use_zip: bool,      // Whether ZIP download will be used
     modifies_atlas: bool,     // Whether file updates include atlases
     index_etag: Option<String>,
+    revision: Option<String>,
+    signed_index: Option<SignedIndex>,
 }
 
-#[derive(Default, Clone)]
+#[derive(Serialize, Default, Clone)]
 pub struct UpdateProgress {
     pub current: usize,
     pub total: usize
@@ -228,18 +271,249 @@ struct RepoCache {
     base_url: String,
     #[serde(default)]
     index_etag: Option<String>,
+    #[serde(default)]
+    revision: Option<String>,
     files: FnvHashMap<String, String> // path: hash
 }
+
+impl RepoCache {
+    fn load(repo_id: u32) -> RepoCache {
+        fs::read_to_string(Updater::get_repo_cache_path(repo_id))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+}
+
+// Last verified index of a signed repo and its detached signature, packaged with offline exports
+// so they can be verified against the pinned key on import
+#[derive(Clone)]
+struct SignedIndex {
+    index: Vec<u8>,
+    signature: String
+}
+
+impl SignedIndex {
+    fn get_path(repo_id: u32) -> PathBuf {
+        Hachimi::instance().get_data_path(format!(".tl_repo_index_{}", repo_id))
+    }
+
+    fn load(repo_id: u32) -> Option<SignedIndex> {
+        let path = Self::get_path(repo_id);
+        let index = fs::read(&path).ok()?;
+        let signature = fs::read_to_string(path.with_extension("sig")).ok()?;
+        Some(SignedIndex { index, signature })
+    }
+
+    fn save(&self, repo_id: u32) -> Result<(), Error> {
+        let path = Self::get_path(repo_id);
+        fs::write(&path, &self.index)?;
+        fs::write(path.with_extension("sig"), &self.signature)?;
+        Ok(())
+    }
+
+    // Verifies the index and returns its file hashes
+    fn verify(&self, public_key: &str) -> Result<FnvHashMap<String, String>, Error> {
+        verify_index_signature(public_key, &self.index, &self.signature)?;
+        let index: RepoIndex = serde_json::from_slice(&self.index)?;
+        Ok(index.files.into_iter().map(|f| (f.path, f.hash)).collect())
+    }
+}
+
 pub const REPO_EXCLUDES_FILENAME: &str = "excludes.txt";
+const BACKUP_META_FILENAME: &str = "backup.json";
+
+// Files replaced by the last update of a repo, used to roll it back
+#[derive(Serialize, Deserialize)]
+struct RepoBackup {
+    // The whole repo dir was moved into the backup (new repo install)
+    full: bool,
+    // Files that didn't exist before the update
+    added_files: Vec<String>,
+    cache: RepoCache,
+    // False if the update didn't finish
+    complete: bool
+}
+
+fn get_repo_backup_dir(repo_id: u32) -> PathBuf {
+    Hachimi::instance().get_data_path(format!(".tl_repo_backup_{}", repo_id))
+}
+
+impl RepoBackup {
+    fn create(update_info: &UpdateInfo, repo_dir: &Path) -> Result<(), Error> {
+        let backup_dir = get_repo_backup_dir(update_info.repo_id);
+        if let Some(backup) = Self::load(update_info.repo_id) {
+            if !backup.complete {
+                // The files from before the failed update are still in there
+                return Ok(());
+            }
+        }
+
+        Updater::create_dir(&backup_dir, true)?;
+        if !repo_dir.is_dir() {
+            // Nothing to roll back to
+            fs::remove_dir_all(&backup_dir)?;
+            return Ok(());
+        }
+
+        let files_dir = backup_dir.join("files");
+        let mut backup = RepoBackup {
+            full: update_info.is_new_repo,
+            added_files: Vec::new(),
+            cache: RepoCache::load(update_info.repo_id),
+            complete: false
+        };
+
+        if backup.full {
+            fs::rename(repo_dir, &files_dir)?;
+        }
+        else {
+            for file in update_info.files.iter() {
+                let path = file.get_fs_path(repo_dir);
+                if path.is_file() {
+                    let backup_path = file.get_fs_path(&files_dir);
+                    if let Some(parent) = backup_path.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    fs::copy(&path, &backup_path)?;
+                }
+                else {
+                    backup.added_files.push(file.path.clone());
+                }
+            }
+        }
+
+        utils::write_json_file(&backup, backup_dir.join(BACKUP_META_FILENAME))
+    }
+
+    fn load(repo_id: u32) -> Option<RepoBackup> {
+        let json = fs::read_to_string(get_repo_backup_dir(repo_id).join(BACKUP_META_FILENAME)).ok()?;
+        serde_json::from_str(&json).ok()
+    }
+
+    fn mark_complete(repo_id: u32) -> Result<(), Error> {
+        let Some(mut backup) = Self::load(repo_id) else {
+            return Ok(());
+        };
+        backup.complete = true;
+        utils::write_json_file(&backup, get_repo_backup_dir(repo_id).join(BACKUP_META_FILENAME))
+    }
+
+    fn restore(self, repo_id: u32, repo_dir: &Path) -> Result<(), Error> {
+        let backup_dir = get_repo_backup_dir(repo_id);
+        let files_dir = backup_dir.join("files");
+
+        if self.full {
+            if repo_dir.is_dir() {
+                fs::remove_dir_all(repo_dir)?;
+            }
+            fs::rename(&files_dir, repo_dir)?;
+        }
+        else {
+            for path in self.added_files.iter() {
+                let path = repo_dir.join(path);
+                if path.is_file() {
+                    fs::remove_file(path)?;
+                }
+            }
+            if files_dir.is_dir() {
+                copy_dir_all(&files_dir, repo_dir)?;
+            }
+        }
+
+        utils::write_json_file(&self.cache, Updater::get_repo_cache_path(repo_id))?;
+        fs::remove_dir_all(&backup_dir)?;
+        Ok(())
+    }
+}
+
+fn get_temp_path(path: &Path) -> PathBuf {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    PathBuf::from(tmp_path)
+}
+
+fn copy_dir_all(src: &Path, dst: &Path) -> Result<(), Error> {
+    fs::create_dir_all(dst)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let dst_path = dst.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &dst_path)?;
+        }
+        else {
+            fs::copy(entry.path(), dst_path)?;
+        }
+    }
+    Ok(())
+}
+
+fn verify_index_signature(public_key: &str, index: &[u8], signature: &str) -> Result<(), Error> {
+    use base64::Engine;
+    let decode = |s: &str| base64::engine::general_purpose::STANDARD.decode(s.trim()).ok();
+
+    let key_bytes: [u8; 32] = decode(public_key)
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| Error::SignatureVerification("invalid public key".to_owned()))?;
+    let key = ed25519_dalek::VerifyingKey::from_bytes(&key_bytes)
+        .map_err(|_| Error::SignatureVerification("invalid public key".to_owned()))?;
+
+    let signature_bytes: [u8; 64] = decode(signature)
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| Error::SignatureVerification("invalid signature".to_owned()))?;
+    let signature = ed25519_dalek::Signature::from_bytes(&signature_bytes);
+
+    key.verify_strict(index, &signature)
+        .map_err(|_| Error::SignatureVerification("the index was not signed by the pinned key".to_owned()))
+}
+
+/// Revision of the repo's files according to its cache, if the repo index has revisions.
+pub fn get_installed_revision(repo_id: u32) -> Option<String> {
+    RepoCache::load(repo_id).revision
+}
+
+/// Whether the last update of the repo can be rolled back.
+pub fn has_backup(repo_id: u32) -> bool {
+    RepoBackup::load(repo_id).is_some()
+}
+
+/// Pins the repo to an index revision (an empty revision keeps the installed files as they are),
+/// or unpins it.
+pub fn set_pinned_revision(repo_id: u32, revision: Option<String>) -> Result<(), Error> {
+    let hachimi = Hachimi::instance();
+    {
+        let mut manager = hachimi.tl_repo_manager.lock().unwrap();
+        let Some(entry) = manager.repos.iter_mut().find(|r| r.id == repo_id) else {
+            return Err(Error::RuntimeError(format!("Translation repo {} not found", repo_id)));
+        };
+        entry.pinned_revision = revision.clone();
+        manager.save(&hachimi.get_data_path(".tl_repos"))?;
+    }
+
+    if revision.is_none() {
+        // The cached ETag belongs to an index that was skipped while pinned
+        let cache_path = Updater::get_repo_cache_path(repo_id);
+        if cache_path.exists() {
+            let mut cache = RepoCache::load(repo_id);
+            cache.index_etag = None;
+            utils::write_json_file(&cache, &cache_path)?;
+        }
+    }
+
+    Ok(())
+}
 
 #[derive(Default)]
 pub struct Updater {
     update_check_mutex: Mutex<()>,
     run_mutex: Mutex<()>,
-    new_update: ArcSwap<Option<UpdateInfo>>,
+    // One per active repo that has an update
+    new_update: ArcSwap<Vec<UpdateInfo>>,
     progress: ArcSwap<Option<UpdateProgress>>,
     last_progress_ms: AtomicU64,
-    skipped_etag: Mutex<Option<String>>
+    skipped_etags: Mutex<FnvHashMap<u32, String>>, // repo id: index etag
+    // Consecutive failed checks and updates, for the auto updater's backoff
+    failure_count: AtomicU32
 }
 
 const CHUNK_SIZE: usize = 8192; // 8KiB
@@ -347,16 +621,28 @@ fn check_available_disk_space(_path: &Path, required_bytes: u64) -> Result<(), E
 }
 
 impl Updater {
-    pub fn skip_update(&self, etag: Option<String>) {
-        *self.skipped_etag.lock().unwrap() = etag;
+    /// Dismisses the pending update, the same repo versions won't be offered again.
+    pub fn skip_update(&self) {
+        let mut skipped_etags = self.skipped_etags.lock().unwrap();
+        for update_info in self.new_update.load().iter() {
+            if let Some(etag) = &update_info.index_etag {
+                skipped_etags.insert(update_info.repo_id, etag.clone());
+            }
+        }
+        drop(skipped_etags);
+        self.clear_pending_update();
+    }
+
+    pub fn clear_skipped_updates(&self) {
+        self.skipped_etags.lock().unwrap().clear();
     }
 
     pub fn has_pending_update(&self) -> bool {
-        self.new_update.load().is_some()
+        !self.new_update.load().is_empty()
     }
 
     pub fn clear_pending_update(&self) {
-        self.new_update.store(Arc::new(None));
+        self.new_update.store(Arc::default());
     }
 
     pub fn is_updating(&self) -> bool {
@@ -365,17 +651,25 @@ impl Updater {
 
     pub fn check_for_updates(self: Arc<Self>, pedantic: bool, silent: bool) {
         std::thread::spawn(move || {
-            if let Err(e) = self.check_for_updates_internal(pedantic, silent) {
-                if let Some(mutex) = Gui::instance() {
-                    if !silent {
-                        mutex.lock().unwrap().show_notification(&format!("{}", e));
+            match self.check_for_updates_internal(pedantic, silent) {
+                Ok(_) => self.failure_count.store(0, atomic::Ordering::Relaxed),
+                Err(e) => {
+                    self.failure_count.fetch_add(1, atomic::Ordering::Relaxed);
+                    if let Some(mutex) = Gui::instance() {
+                        if !silent {
+                            mutex.lock().unwrap().show_notification(&format!("{}", e));
+                        }
                     }
+                    info!("{}", e);
                 }
-                info!("{}", e);
             }
         });
     }
 
+    pub fn failure_count(&self) -> u32 {
+        self.failure_count.load(atomic::Ordering::Relaxed)
+    }
+
     fn is_github_hosted(url: &str) -> bool {
         url.contains("github.com") ||
         url.contains("githubusercontent.com") ||
@@ -422,9 +716,30 @@ impl Updater {
 
         let hachimi = Hachimi::instance();
         let config = hachimi.config.load();
-        let Some(index_url) = &config.translation_repo_index else {
+        if config.selected_tl_repo_id.is_none() {
+            if let Some(index_url) = &config.translation_repo_index {
+                let id = {
+                    let mut manager = hachimi.tl_repo_manager.lock().unwrap();
+                    let repos_path = hachimi.get_data_path(".tl_repos");
+                    if let Some(existing_id) = manager.find_by_index(index_url) {
+                        existing_id
+                    } else {
+                        let new_id = manager.add(index_url.clone());
+                        manager.save(&repos_path)?;
+                        new_id
+                    }
+                };
+
+                let mut new_config = (**config).clone();
+                new_config.selected_tl_repo_id = Some(id);
+                hachimi.save_and_reload_config(new_config)?;
+            }
+        }
+
+        let repos = hachimi.get_active_tl_repos();
+        if repos.is_empty() {
             return Ok(());
-        };
+        }
 
         let checking_notif_id = if !silent {
             if let Some(mutex) = Gui::instance() {
@@ -437,27 +752,120 @@ impl Updater {
         };
         let _guard = checking_notif_id.map(NotificationGuard);
 
-        let repo_id = if let Some(id) = config.selected_tl_repo_id {
-            id
-        } else {
-            let mut manager = hachimi.tl_repo_manager.lock().unwrap();
-            let repos_path = hachimi.get_data_path(".tl_repos");
-            let id = if let Some(existing_id) = manager.find_by_index(index_url) {
-                existing_id
+        let mut updates: Vec<UpdateInfo> = Vec::new();
+        let mut last_error = None;
+        for repo in repos.iter() {
+            match self.check_repo_for_updates(repo.id, &repo.index, pedantic) {
+                Ok(Some(update_info)) => updates.push(update_info),
+                Ok(None) => (),
+                Err(e) => {
+                    error!("Failed to check '{}' for updates: {}", repo.index, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        if updates.is_empty() {
+            if let Some(e) = last_error {
+                return Err(e);
+            }
+
+            if !silent {
+                if let Some(mutex) = Gui::instance() {
+                    mutex.lock().unwrap().show_notification(&t!("notification.no_tl_updates"));
+                }
+            }
+            return Ok(());
+        }
+
+        let will_use_zip = updates.iter().any(|u| u.will_use_zip);
+        let update_size: usize = updates.iter().map(|u| u.update_size).sum();
+        let download_size: usize = updates.iter().map(|u| u.size).sum();
+        // Highest priority repo with an update
+        let repo_id = updates[0].repo_id;
+
+        self.new_update.store(Arc::new(updates));
+
+        if silent {
+            // don't auto-apply while another update is already in progress
+            if self.progress.load().is_some() {
+                info!("Silent update skipped, another update is already in progress.");
+            } else {
+                Hachimi::instance().tl_updater.clone().run();
+            }
+        } else if let Some(mutex) = Gui::instance() {
+            // Determine the dialog message based on download strategy
+            let dialog_message = if will_use_zip && update_size > 0 {
+                let size_ratio = download_size as f64 / update_size.max(1) as f64;
+
+                if size_ratio >= ZIP_SIZE_WARNING_RATIO {
+                    // Warn user about larger ZIP download
+                    debug!(
+                        "ZIP download warning: changed={} MB, total={} MB, ratio={:.2}x",
+                        update_size / (1024 * 1024),
+                        download_size / (1024 * 1024),
+                        size_ratio
+                    );
+
+                    t!(
+                        "tl_update_dialog.content_zip_warning",
+                        changed_size = Size::from_bytes(update_size),
+                        download_size = Size::from_bytes(download_size)
+                    )
+                } else {
+                    // ZIP is being used but size difference is not significant
+                    t!("tl_update_dialog.content", size = Size::from_bytes(download_size))
+                }
             } else {
-                let new_id = manager.add(index_url.clone());
-                manager.save(&repos_path)?;
-                new_id
+                // Incremental update or no warning needed
+                t!("tl_update_dialog.content", size = Size::from_bytes(download_size))
             };
 
-            let mut new_config = (**config).clone();
-            new_config.selected_tl_repo_id = Some(id);
-            hachimi.save_and_reload_config(new_config)?;
-            id
-        };
+            let updater = Hachimi::instance().tl_updater.clone();
+
+            // Check if the updated repo has a valid changelog URL
+            let repo_info = LocalRepoInfo::load(repo_id)
+                .ok()
+                .flatten()
+                .filter(|info| info.is_valid_changelog_url());
+
+            if let Some(info) = repo_info {
+                mutex.lock().unwrap().show_window(Box::new(TranslationRepoUpdateWindow::new(
+                    &t!("tl_update_dialog.title"),
+                    &dialog_message,
+                    info.changelog_url.as_str(),
+                    info.is_markdown_changelog(),
+                    move |ok| {
+                        if !ok {
+                            updater.skip_update();
+                            return;
+                        }
+                        updater.run();
+                    }
+                )));
+            } else {
+                mutex.lock().unwrap().show_window(Box::new(SimpleYesNoDialog::new(
+                    &t!("tl_update_dialog.title"),
+                    &dialog_message,
+                    move |ok| {
+                        if !ok {
+                            updater.skip_update();
+                            return;
+                        }
+                        updater.run();
+                    }
+                )));
+            }
+        }
+
+        Ok(())
+    }
 
-        let config = hachimi.config.load(); // in case repo id was migrated
-        let ld_dir_path = hachimi.get_active_tl_dir();
+    // Returns None if the repo is up to date
+    fn check_repo_for_updates(&self, repo_id: u32, index_url: &str, pedantic: bool) -> Result<Option<UpdateInfo>, Error> {
+        let hachimi = Hachimi::instance();
+        let config = hachimi.config.load();
+        let ld_dir_path = hachimi.get_repo_dir(repo_id);
 
         let cache_path = Self::get_repo_cache_path(repo_id);
         let repo_cache = if fs::metadata(&cache_path).is_ok() {
@@ -468,49 +876,47 @@ impl Updater {
             RepoCache::default()
         };
 
+        let entry = hachimi.tl_repo_manager.lock().unwrap().repos.iter().find(|r| r.id == repo_id).cloned();
+        let pinned_revision = entry.as_ref().and_then(|e| e.pinned_revision.clone());
+
+        let skipped_etag = self.skipped_etags.lock().unwrap().get(&repo_id).cloned();
         let mut new_etag: Option<String> = None;
+        // ETags are for the latest index, which pinned repos don't follow
+        let use_etag = !pedantic && config.etag_translation_updates && pinned_revision.is_none();
 
         // conditional GET: send If-None-Match with the current best ETag.
         // server replies 304 if nothing changed, 200 + body if it has.
-        let etag_for_request = if !pedantic && config.etag_translation_updates {
-            repo_cache.index_etag.clone()
-                .or_else(|| self.skipped_etag.lock().unwrap().clone())
+        let etag_for_request = if use_etag {
+            repo_cache.index_etag.clone().or_else(|| skipped_etag.clone())
         } else {
             None
         };
 
-        let mut request = ureq::agent().get(index_url);
+        let mut request = ureq::Agent::new_with_config(ureq_config()).get(rewrite_url(index_url).as_ref());
         if let Some(etag) = &etag_for_request {
             request = request.header("If-None-Match", etag);
         }
 
-        let index: RepoIndex = match request.call() {
+        let index_bytes = match request.call() {
             Ok(res) => {
                 if let Some(etag_val) = res.headers().get("ETag") {
                     if let Ok(etag_str) = etag_val.to_str() {
                         let etag_string = etag_str.to_string();
 
                         // user previously dismissed this exact version
-                        if !pedantic && config.etag_translation_updates {
-                            if let Some(skipped) = &*self.skipped_etag.lock().unwrap() {
-                                if skipped == &etag_string {
-                                    debug!("Server ETag matches the skipped ETag. Ignoring update.");
-                                    return Ok(());
-                                }
+                        if use_etag {
+                            if skipped_etag.as_ref() == Some(&etag_string) {
+                                debug!("Server ETag matches the skipped ETag. Ignoring update.");
+                                return Ok(None);
                             }
                         }
 
                         // fallback for servers that ignore If-None-Match
-                        if !pedantic && config.etag_translation_updates {
+                        if use_etag {
                             if let Some(cached) = &repo_cache.index_etag {
                                 if cached == &etag_string {
                                     info!("Server ETag matches cached ETag (server may not support conditional requests). No translation updates available.");
-                                    if !silent {
-                                        if let Some(mutex) = Gui::instance() {
-                                            mutex.lock().unwrap().show_notification(&t!("notification.no_tl_updates"));
-                                        }
-                                    }
-                                    return Ok(());
+                                    return Ok(None);
                                 }
                             }
                         }
@@ -518,22 +924,50 @@ impl Updater {
                         new_etag = Some(etag_string);
                     }
                 }
-                serde_json::from_reader(res.into_body().into_reader())?
+                let mut bytes = Vec::new();
+                res.into_body().into_reader().read_to_end(&mut bytes)?;
+                bytes
             }
             Err(ureq::Error::StatusCode(code)) if code == ureq::http::StatusCode::NOT_MODIFIED => {
                 info!("Server returned 304 Not Modified. No translation updates available.");
-                if !silent {
-                    if let Some(mutex) = Gui::instance() {
-                        mutex.lock().unwrap().show_notification(&t!("notification.no_tl_updates"));
-                    }
-                }
-                return Ok(());
+                return Ok(None);
             }
             Err(e) => {
                 return Err(e.into());
             }
         };
 
+        // Nothing gets written before the index has been verified
+        let mut index: RepoIndex = serde_json::from_slice(&index_bytes)?;
+        let verified = Self::verify_repo_index(repo_id, entry.as_ref(), index_url, &index_bytes, &index)?;
+        let public_key = verified.as_ref().map(|(key, _)| key.clone());
+        let mut signed_index = verified.map(|(_, signature)| SignedIndex { index: index_bytes, signature });
+
+        if let Some(pinned) = &pinned_revision {
+            if index.revision.as_deref() != Some(pinned.as_str()) {
+                let pinned_index_url = index.revision_index_url.as_ref()
+                    .filter(|_| !pinned.is_empty())
+                    .map(|url| url.replace("{revision}", pinned));
+                let Some(pinned_index_url) = pinned_index_url else {
+                    info!("Translation repo {} is pinned, skipping update", repo_id);
+                    return Ok(None);
+                };
+
+                let pinned_bytes = Self::fetch_bytes(&pinned_index_url)?;
+                index = serde_json::from_slice(&pinned_bytes)?;
+                if let Some(key) = &public_key {
+                    let signature = Self::fetch_signature(&pinned_index_url)?;
+                    verify_index_signature(key, &pinned_bytes, &signature)?;
+                    signed_index = Some(SignedIndex { index: pinned_bytes, signature });
+                }
+                if index.revision.as_deref() != Some(pinned.as_str()) {
+                    return Err(Error::RuntimeError(format!(
+                        "Index at '{}' is not revision '{}'", pinned_index_url, pinned
+                    )));
+                }
+            }
+        }
+
         let excludes_path = hachimi.get_data_path(REPO_EXCLUDES_FILENAME);
         let excludes: HashSet<String> = if excludes_path.exists() {
             fs::read_to_string(&excludes_path)
@@ -550,6 +984,7 @@ impl Updater {
         let mut modifies_atlas = false;
         let mut update_files: Vec<RepoFile> = Vec::new();
         let mut update_size: usize = 0;
+        let mut update_files_size: usize = 0;
         let mut total_size: usize = 0;
 
         let total_files = index.files.len();
@@ -571,6 +1006,7 @@ impl Updater {
                 }
                 update_files.push(file.clone());
                 update_size += file.size;
+                update_files_size += file.size;
                 total_size += file.size;
             }
         } else {
@@ -582,8 +1018,8 @@ impl Updater {
                     continue;
                 }
 
-                let path = ld_dir_path.as_ref().map(|p| p.join(&file.path));
-                let exists = path.as_ref().map(|p| p.is_file()).unwrap_or(false);
+                let path = ld_dir_path.join(&file.path);
+                let exists = path.is_file();
 
                 let excluded = excludes.iter().any(|exc| {
                     if file.path == *exc {
@@ -600,32 +1036,35 @@ impl Updater {
                     // lazy auto update, cached hash and repo hash matches. ignored during pedantic
                     if !pedantic && config.lazy_translation_updates && hash == &file.hash {
                         false
-                    } else if let Some(path) = path {
-                        // file doesn't exist -> download
-                        if !exists {
-                            true
-                        } else if hash != &file.hash {
-                            true // index hash changed -> update
-                        } else if fs::metadata(&path).map(|m| m.len() as usize != file.size).unwrap_or(true) {
-                            true // size mismatch -> redownload
-                        } else if pedantic {
-                            !file.verify_integrity(&path) // full blake3 integrity check if user requested pedantic update
-                        } else {
-                            false // everything matches -> skip
-                        }
+                    } else if !exists {
+                        true // file doesn't exist -> download
+                    } else if hash != &file.hash {
+                        true // index hash changed -> update
+                    } else if fs::metadata(&path).map(|m| m.len() as usize != file.size).unwrap_or(true) {
+                        true // size mismatch -> redownload
+                    } else if pedantic {
+                        !file.verify_integrity(&path) // full blake3 integrity check if user requested pedantic update
                     } else {
-                        true // path invalid -> download
+                        false // everything matches -> skip
                     }
                 } else {
                     true // file doesn't exist in cache at all -> download it
                 };
 
                 if updated {
-                    update_files.push(file.clone());
-                    update_size += file.size;
+                    let mut file = file.clone();
+                    if exists && !pedantic && config.delta_translation_updates {
+                        file.delta_patch = repo_cache.files.get(&file.path)
+                            .and_then(|hash| file.patches.iter().find(|p| &p.from == hash))
+                            .filter(|p| p.hash.is_some() && !p.path.contains("..") && !Path::new(&p.path).has_root())
+                            .cloned();
+                    }
                     if file.path.contains("/atlas/") && !modifies_atlas {
                         modifies_atlas = true;
                     }
+                    update_size += file.delta_patch.as_ref().map(|p| p.size).unwrap_or(file.size);
+                    update_files_size += file.size;
+                    update_files.push(file);
                 }
                 total_size += file.size;
             }
@@ -636,124 +1075,109 @@ impl Updater {
             mutex.lock().unwrap().update_progress_visible = false;
         }
 
-        if !update_files.is_empty() {
-            // Determine download strategy
-            let will_use_zip = Self::should_use_zip_download(
-                update_files.len(),
-                update_size,
-                total_size,
-                &index.base_url
-            );
-
-            // Calculate actual download size
-            let actual_download_size = if will_use_zip { total_size } else { update_size };
-
-            // Store update info with all relevant sizes
-            self.new_update.store(Arc::new(Some(UpdateInfo {
-                is_new_repo,
-                base_url: index.base_url,
-                zip_url: index.zip_url,
-                zip_dir: index.zip_dir,
-                files: update_files,
-                cached_files: repo_cache.files,
-                size: actual_download_size,
-                update_size,
-                total_size,
-                will_use_zip,
-                modifies_atlas,
-                index_etag: new_etag.clone(),
-            })));
-
-            if silent {
-                // don't auto-apply while another update is already in progress
-                if self.progress.load().is_some() {
-                    info!("Silent update skipped, another update is already in progress.");
-                } else {
-                    Hachimi::instance().tl_updater.clone().run();
+        if update_files.is_empty() {
+            if new_etag.is_some() || index.revision != repo_cache.revision {
+                let mut updated_cache = repo_cache;
+                if new_etag.is_some() {
+                    updated_cache.index_etag = new_etag;
                 }
-            } else if let Some(mutex) = Gui::instance() {
-                // Determine the dialog message based on download strategy
-                let dialog_message = if will_use_zip && update_size > 0 {
-                    let size_ratio = total_size as f64 / update_size.max(1) as f64;
-
-                    if size_ratio >= ZIP_SIZE_WARNING_RATIO {
-                        // Warn user about larger ZIP download
-                        debug!(
-                            "ZIP download warning: changed={} MB, total={} MB, ratio={:.2}x",
-                            update_size / (1024 * 1024),
-                            total_size / (1024 * 1024),
-                            size_ratio
-                        );
-
-                        t!(
-                            "tl_update_dialog.content_zip_warning",
-                            changed_size = Size::from_bytes(update_size),
-                            download_size = Size::from_bytes(total_size)
-                        )
-                    } else {
-                        // ZIP is being used but size difference is not significant
-                        t!("tl_update_dialog.content", size = Size::from_bytes(actual_download_size))
-                    }
-                } else {
-                    // Incremental update or no warning needed
-                    t!("tl_update_dialog.content", size = Size::from_bytes(actual_download_size))
-                };
+                updated_cache.revision = index.revision;
+                let _ = utils::write_json_file(&updated_cache, &cache_path);
+            }
+            if let Some(signed_index) = &signed_index {
+                let _ = signed_index.save(repo_id);
+            }
+            return Ok(None);
+        }
 
-                let updater = Hachimi::instance().tl_updater.clone();
-                let etag_to_skip = new_etag.clone();
-
-                // Check if the active repo has a valid changelog URL
-                let repo_info = LocalRepoInfo::load(repo_id)
-                    .ok()
-                    .flatten()
-                    .filter(|info| info.is_valid_changelog_url());
-
-                if let Some(info) = repo_info {
-                    mutex.lock().unwrap().show_window(Box::new(TranslationRepoUpdateWindow::new(
-                        &t!("tl_update_dialog.title"),
-                        &dialog_message,
-                        info.changelog_url.as_str(),
-                        info.is_markdown_changelog(),
-                        move |ok| {
-                            if !ok {
-                                updater.skip_update(etag_to_skip);
-                                updater.clear_pending_update();
-                                return;
-                            }
-                            updater.run();
-                        }
-                    )));
-                } else {
-                    mutex.lock().unwrap().show_window(Box::new(SimpleYesNoDialog::new(
-                        &t!("tl_update_dialog.title"),
-                        &dialog_message,
-                        move |ok| {
-                            if !ok {
-                                updater.skip_update(etag_to_skip);
-                                updater.clear_pending_update();
-                                return;
-                            }
-                            updater.run();
+        // Determine download strategy
+        let will_use_zip = Self::should_use_zip_download(
+            update_files.len(),
+            update_size,
+            total_size,
+            &index.base_url
+        );
+
+        // Calculate actual download size
+        let actual_download_size = if will_use_zip { total_size } else { update_size };
+
+        Ok(Some(UpdateInfo {
+            repo_id,
+            is_new_repo,
+            base_url: index.base_url,
+            zip_url: index.zip_url,
+            zip_dir: index.zip_dir,
+            zip_hash: index.zip_hash,
+            files: update_files,
+            cached_files: repo_cache.files,
+            size: actual_download_size,
+            disk_size: if will_use_zip { total_size } else { update_files_size },
+            update_size,
+            total_size,
+            will_use_zip,
+            modifies_atlas,
+            index_etag: new_etag,
+            revision: index.revision,
+            signed_index,
+        }))
+    }
+
+    // Verifies the index with the repo's pinned key, pinning the installed repo's key first if the repo
+    // doesn't have one. Keys are only pinned from trusted sources (the meta index when the repo is added,
+    // or the installed info.json), never from the fetched index itself.
+    // Returns the key and the index signature, or None if the repo isn't signed.
+    fn verify_repo_index(
+        repo_id: u32, entry: Option<&RepoEntry>, index_url: &str, index_bytes: &[u8], index: &RepoIndex
+    ) -> Result<Option<(String, String)>, Error> {
+        let (public_key, newly_pinned) = match entry.and_then(|e| e.public_key.clone()) {
+            Some(key) => (key, false),
+            None => {
+                let installed_key = LocalRepoInfo::load(repo_id).ok().flatten()
+                    .map(|info| info.public_key)
+                    .filter(|key| !key.is_empty());
+                match installed_key {
+                    Some(key) => (key, true),
+                    None => {
+                        if index.public_key.is_some() {
+                            warn!("Index of translation repo {} declares a key but none is pinned, not verifying it", repo_id);
                         }
-                    )));
+                        return Ok(None);
+                    }
                 }
             }
+        };
+
+        if index.public_key.as_ref().is_some_and(|key| key.trim() != public_key.trim()) {
+            warn!("Index of translation repo {} declares a different key than the pinned one", repo_id);
         }
-        else {
-            if let Some(etag) = new_etag {
-                let mut updated_cache = repo_cache;
-                updated_cache.index_etag = Some(etag);
-                let _ = utils::write_json_file(&updated_cache, &cache_path);
-            }
 
-            if !silent {
-                if let Some(mutex) = Gui::instance() {
-                    mutex.lock().unwrap().show_notification(&t!("notification.no_tl_updates"));
-                }
-            }
+        let signature = Self::fetch_signature(index_url)?;
+        verify_index_signature(&public_key, index_bytes, &signature)?;
+
+        if newly_pinned {
+            info!("Pinned the signing key of translation repo {}", repo_id);
+            let hachimi = Hachimi::instance();
+            let mut manager = hachimi.tl_repo_manager.lock().unwrap();
+            manager.pin_public_key(repo_id, &public_key);
+            manager.save(&hachimi.get_data_path(".tl_repos"))?;
         }
 
-        Ok(())
+        Ok(Some((public_key, signature)))
+    }
+
+    // Detached signature of the index, stored next to it
+    fn fetch_signature(index_url: &str) -> Result<String, Error> {
+        let url = format!("{}.sig", index_url);
+        let res = ureq::Agent::new_with_config(ureq_config()).get(rewrite_url(&url).as_ref()).call()
+            .map_err(|e| Error::SignatureVerification(format!("failed to fetch '{}': {}", url, e)))?;
+        Ok(res.into_body().read_to_string()?)
+    }
+
+    fn fetch_bytes(url: &str) -> Result<Vec<u8>, Error> {
+        let res = ureq::Agent::new_with_config(ureq_config()).get(rewrite_url(url).as_ref()).call()?;
+        let mut bytes = Vec::new();
+        res.into_body().into_reader().read_to_end(&mut bytes)?;
+        Ok(bytes)
     }
 
     pub fn run(self: Arc<Self>) {
@@ -762,7 +1186,19 @@ impl Updater {
             .stack_size(8 * 1024 * 1024) // increase stack size to 8MB to prevent 0xc0000409 (Stack Buffer Overrun) during single-threaded downloads
             .spawn(move || {
                 if let Err(e) = self.clone().run_internal() {
+                    self.failure_count.fetch_add(1, atomic::Ordering::Relaxed);
                     error!("{}", e);
+                    let message = e.to_string();
+                    plugin_events::emit(plugin_events::Event::TlUpdateFinished {
+                        success: false,
+                        error_count: 0,
+                        message: Some(&message)
+                    });
+                    ipc::emit_event(ipc::Event::TlUpdateFinished {
+                        success: false,
+                        error_count: 0,
+                        message: Some(message)
+                    });
                     self.progress.store(Arc::new(None));
                     if let Some(mutex) = Gui::instance() {
                         mutex.lock().unwrap().show_notification(&t!("notification.update_failed", reason = e.to_string()));
@@ -772,6 +1208,45 @@ impl Updater {
             .expect("Failed to spawn updater thread");
     }
 
+    /// Restores the files replaced by the last update of the repo and pins it to the revision it was at.
+    /// Runs in the background, the returned handle can be polled to see when it's done.
+    pub fn rollback(self: Arc<Self>, repo_id: u32) -> std::thread::JoinHandle<()> {
+        std::thread::spawn(move || {
+            let result = self.rollback_internal(repo_id);
+            if let Err(e) = &result {
+                error!("Failed to roll back translation repo {}: {}", repo_id, e);
+            }
+            if let Some(mutex) = Gui::instance() {
+                let mut gui = mutex.lock().unwrap();
+                match result {
+                    Ok(_) => gui.show_notification(&t!("notification.rollback_completed")),
+                    Err(e) => gui.show_notification(&t!("notification.rollback_failed", reason = e.to_string()))
+                }
+            }
+        });
+    }
+
+    fn rollback_internal(&self, repo_id: u32) -> Result<(), Error> {
+        let Ok(_run_guard) = self.run_mutex.try_lock() else {
+            return Err(Error::RuntimeError("An update is in progress".to_owned()));
+        };
+        let Some(backup) = RepoBackup::load(repo_id) else {
+            return Err(Error::RuntimeError("No backup available".to_owned()));
+        };
+        let old_revision = backup.cache.revision.clone();
+
+        let hachimi = Hachimi::instance();
+        hachimi.localized_data.store(Arc::new(LocalizedData::default()));
+        let result = backup.restore(repo_id, &hachimi.get_repo_dir(repo_id));
+        let result = result.and_then(|_| {
+            // Otherwise the next update check would undo the rollback
+            self.new_update.store(Arc::default());
+            set_pinned_revision(repo_id, Some(old_revision.unwrap_or_default()))
+        });
+        hachimi.load_localized_data();
+        result
+    }
+
     fn create_dir(path: &Path, override_exists: bool) -> Result<(), Error> {
         if override_exists {
             // rm -rf
@@ -792,47 +1267,54 @@ impl Updater {
             info!("Update already in progress, skipping.");
             return Ok(());
         };
-        let Some(mut update_info) = (**self.new_update.load()).clone() else {
+        let updates = (**self.new_update.load()).clone();
+        if updates.is_empty() {
             return Ok(());
-        };
-        self.new_update.store(Arc::new(None));
+        }
+        self.new_update.store(Arc::default());
         self.last_progress_ms.store(0, atomic::Ordering::Relaxed);
 
-        self.progress.store(Arc::new(Some(UpdateProgress::new(0, update_info.size))));
+        self.progress.store(Arc::new(Some(UpdateProgress::new(0, updates[0].size))));
         if let Some(mutex) = Gui::instance() {
             mutex.lock().unwrap().update_progress_visible = true;
         }
 
-        // Empty the localized data so files couldnt be accessed while update is in progress
-        let hachimi = Hachimi::instance();
-        hachimi.localized_data.store(Arc::new(LocalizedData::default()));
-
-        let localized_data_dir = hachimi.get_active_tl_dir().expect("Active TL repo directory not set.");
-        let disk_check_path = localized_data_dir.parent().unwrap_or(Path::new("."));
-        check_available_disk_space(disk_check_path, update_info.size as u64)?;
+        // Updates that only replace individual dicts are swapped in file by file, so the current
+        // localized data can stay in use and be hot reloaded afterwards
+        let modifies_atlas = updates.iter().any(|u| u.modifies_atlas || u.will_use_zip);
+        let hot_reload = !modifies_atlas && !updates.iter().any(|u| u.is_new_repo);
 
-        if update_info.is_new_repo {
-            Self::create_dir(&localized_data_dir, true)?;
-        } else {
-            Self::create_dir(&localized_data_dir, false)?;
+        // Otherwise empty the localized data so files couldnt be accessed while update is in progress
+        let hachimi = Hachimi::instance();
+        if !hot_reload {
+            hachimi.localized_data.store(Arc::new(LocalizedData::default()));
         }
 
-        // Download the files - use the pre-determined strategy
-        let cached_files = Arc::new(Mutex::new(std::mem::take(&mut update_info.cached_files)));
-        let error_count = if update_info.will_use_zip {
-            self.clone().download_zip(&update_info, &localized_data_dir, cached_files.clone())
+        // A failed repo doesn't stop the others, the localized data has to be reloaded either way
+        let mut error_count = 0;
+        let mut failures = Vec::new();
+        for mut update_info in updates {
+            self.progress.store(Arc::new(Some(UpdateProgress::new(0, update_info.size))));
+            match self.clone().apply_update(&mut update_info) {
+                Ok(count) => error_count += count,
+                Err(e) => {
+                    error!("Failed to update translation repo {}: {}", update_info.repo_id, e);
+                    failures.push(e.to_string());
+                }
+            }
         }
-        else {
-            self.clone().download_incremental(&update_info, &localized_data_dir, cached_files.clone())
-        }?;
 
         let config = hachimi.config.load();
-        if config.apply_atlas_workaround && (update_info.modifies_atlas || update_info.will_use_zip) {
+        if config.apply_atlas_workaround && modifies_atlas {
             let mut new_config = (**config).clone();
             new_config.apply_atlas_workaround = false;
-            hachimi.save_and_reload_config(new_config)?;
-            if let Some(gui_mutex) = Gui::instance() {
-                gui_mutex.lock().unwrap().show_notification(&t!("notification.atlas_workaround_reset"));
+            match hachimi.save_and_reload_config(new_config) {
+                Ok(_) => {
+                    if let Some(gui_mutex) = Gui::instance() {
+                        gui_mutex.lock().unwrap().show_notification(&t!("notification.atlas_workaround_reset"));
+                    }
+                },
+                Err(e) => error!("Failed to reset atlas workaround: {}", e)
             }
         }
 
@@ -842,35 +1324,92 @@ impl Updater {
         // Reload the localized data
         hachimi.load_localized_data();
 
-        // Save the repo cache (done last so if any of the previous fails, the entire update would be voided)
-        let repo_cache = RepoCache {
-            base_url: update_info.base_url.clone(),
-            index_etag: update_info.index_etag.clone(),
-            files: cached_files.lock().unwrap().clone()
-        };
-        let repo_id = hachimi.config.load().selected_tl_repo_id.expect("TL repo ID not set after update");
-        let cache_path = Self::get_repo_cache_path(repo_id);
-        utils::write_json_file(&repo_cache, &cache_path)?;
-
-        *self.skipped_etag.lock().unwrap() = None;
+        let success = failures.is_empty();
+        if success {
+            self.clear_skipped_updates();
+            self.failure_count.store(0, atomic::Ordering::Relaxed);
+        }
+        else {
+            self.failure_count.fetch_add(1, atomic::Ordering::Relaxed);
+        }
+        let message = (!success).then(|| failures.join("\n"));
 
         if let Some(mutex) = Gui::instance() {
             let mut gui = mutex.lock().unwrap();
-            gui.show_notification(&t!("notification.update_completed"));
+            match &message {
+                None => gui.show_notification(&t!("notification.update_completed")),
+                Some(reason) => gui.show_notification(&t!("notification.update_failed", reason = reason))
+            }
+            if modifies_atlas {
+                gui.show_notification(&t!("notification.atlas_update_needs_restart"));
+            }
             if error_count > 0 {
                 gui.show_notification(&t!("notification.errors_during_update", count = error_count));
             }
         }
+        plugin_events::emit(plugin_events::Event::TlUpdateFinished {
+            success,
+            error_count,
+            message: message.as_deref()
+        });
+        ipc::emit_event(ipc::Event::TlUpdateFinished {
+            success,
+            error_count,
+            message
+        });
         Ok(())
     }
 
+    // Downloads a single repo's update, returns the non-fatal error count
+    fn apply_update(self: Arc<Self>, update_info: &mut UpdateInfo) -> Result<usize, Error> {
+        let hachimi = Hachimi::instance();
+        let localized_data_dir = hachimi.get_repo_dir(update_info.repo_id);
+        let disk_check_path = localized_data_dir.parent().unwrap_or(Path::new("."));
+        check_available_disk_space(disk_check_path, update_info.disk_size as u64)?;
+
+        // Keep the files being replaced so the update can be rolled back
+        RepoBackup::create(update_info, &localized_data_dir)?;
+
+        if update_info.is_new_repo {
+            Self::create_dir(&localized_data_dir, true)?;
+        } else {
+            Self::create_dir(&localized_data_dir, false)?;
+        }
+
+        // Download the files - use the pre-determined strategy
+        let cached_files = Arc::new(Mutex::new(std::mem::take(&mut update_info.cached_files)));
+        let error_count = if update_info.will_use_zip {
+            self.clone().download_zip(update_info, &localized_data_dir, cached_files.clone())
+        }
+        else {
+            self.clone().download_incremental(update_info, &localized_data_dir, cached_files.clone())
+        }?;
+
+        // Save the repo cache (done last so if any of the previous fails, the entire update would be voided)
+        let repo_cache = RepoCache {
+            base_url: update_info.base_url.clone(),
+            index_etag: update_info.index_etag.clone(),
+            files: cached_files.lock().unwrap().clone(),
+            revision: update_info.revision.clone()
+        };
+        let cache_path = Self::get_repo_cache_path(update_info.repo_id);
+        utils::write_json_file(&repo_cache, &cache_path)?;
+        if let Some(signed_index) = &update_info.signed_index {
+            signed_index.save(update_info.repo_id)?;
+        }
+        RepoBackup::mark_complete(update_info.repo_id)?;
+
+        Ok(error_count)
+    }
+
     fn download_incremental(
         self: Arc<Self>,
         update_info: &UpdateInfo,
         localized_data_dir: &Path,
         cached_files: Arc<Mutex<FnvHashMap<String, String>>>
     ) -> Result<usize, Error> {
-        let total_size = update_info.size;
+        // Grows when a delta patch fails and the full file has to be downloaded instead
+        let total_size = Arc::new(AtomicUsize::new(update_info.size));
         let current_bytes = Arc::new(AtomicUsize::new(0));
         let non_fatal_error_count = Arc::new(AtomicUsize::new(0));
         let fatal_error = Arc::new(Mutex::new(None::<Error>));
@@ -887,6 +1426,7 @@ impl Updater {
             let base_url_clone = update_info.base_url.clone();
             let cached_files_clone = Arc::clone(&cached_files);
             let current_bytes_clone = Arc::clone(&current_bytes);
+            let total_size_clone = Arc::clone(&total_size);
             let non_fatal_error_count_clone = Arc::clone(&non_fatal_error_count);
             let fatal_error_clone = Arc::clone(&fatal_error);
             let stop_signal_clone = Arc::clone(&stop_signal);
@@ -907,26 +1447,56 @@ impl Updater {
                         if stop_signal_clone.load(atomic::Ordering::Relaxed) { break; }
 
                         let file_path = repo_file.get_fs_path(&localized_data_dir_clone);
-                        let url = utils::concat_unix_path(&base_url_clone, &repo_file.path);
+                        let url = rewrite_url(&utils::concat_unix_path(&base_url_clone, &repo_file.path)).into_owned();
 
                         let execute_result = (|| -> Result<String, Error> {
+                            if let Some(patch) = &repo_file.delta_patch {
+                                let patch_url = rewrite_url(&utils::concat_unix_path(&base_url_clone, &patch.path)).into_owned();
+                                let mut patch_counted = false;
+                                let result = Self::apply_delta_patch(&job.agent, &patch_url, patch, &file_path, &repo_file, |len| {
+                                    patch_counted = true;
+                                    let prev_size = current_bytes_clone.fetch_add(len, atomic::Ordering::Relaxed);
+                                    store_progress(&updater.progress, &updater.last_progress_ms, prev_size + len, total_size_clone.load(atomic::Ordering::Relaxed));
+                                });
+                                match result {
+                                    Ok(hash) => return Ok(hash),
+                                    Err(e) => {
+                                        warn!("Failed to apply delta patch to '{}', downloading the full file: {}", repo_file.path, e);
+                                        // The patch's share of the total is done either way, the full file comes on top
+                                        if !patch_counted {
+                                            current_bytes_clone.fetch_add(patch.size, atomic::Ordering::Relaxed);
+                                        }
+                                        total_size_clone.fetch_add(repo_file.size, atomic::Ordering::Relaxed);
+                                    }
+                                }
+                            }
+
                             if let Some(parent) = Path::new(&file_path).parent() {
                                 Self::create_dir(parent, false)?;
                             }
-                            let mut file = fs::File::create(&file_path)?;
+                            // Written next to the file and swapped in once verified, the old file might still be in use
+                            let tmp_path = get_temp_path(&file_path);
+                            let mut file = fs::File::create(&tmp_path)?;
                             let res = job.agent.get(&url).call()?;
 
-                            http::download_file_buffered(res, &mut file, &mut job.buffer, |bytes| {
+                            let download_result = http::download_file_buffered(res, &mut file, &mut job.buffer, |bytes| {
                                 job.hasher.update(bytes);
                                 let prev_size = current_bytes_clone.fetch_add(bytes.len(), atomic::Ordering::Relaxed);
-                                store_progress(&updater.progress, &updater.last_progress_ms, prev_size + bytes.len(), total_size);
-                            })?;
+                                store_progress(&updater.progress, &updater.last_progress_ms, prev_size + bytes.len(), total_size_clone.load(atomic::Ordering::Relaxed));
+                            });
+                            drop(file);
+                            if let Err(e) = download_result {
+                                _ = fs::remove_file(&tmp_path);
+                                return Err(e);
+                            }
 
                             let hash = job.hasher.finalize().to_hex().to_string();
                             if hash != repo_file.hash {
+                                _ = fs::remove_file(&tmp_path);
                                 return Err(Error::FileHashMismatch(file_path.to_str().unwrap_or("").to_string()));
                             }
                             job.hasher.reset();
+                            fs::rename(&tmp_path, &file_path)?;
                             Ok(hash)
                         })();
 
@@ -967,20 +1537,63 @@ impl Updater {
         Ok(non_fatal_error_count.load(atomic::Ordering::Relaxed))
     }
 
+    // Patches the installed file in place, returns the new hash
+    fn apply_delta_patch(
+        agent: &ureq::Agent,
+        url: &str,
+        patch: &FilePatch,
+        file_path: &Path,
+        file: &RepoFile,
+        add_bytes: impl FnOnce(usize)
+    ) -> Result<String, Error> {
+        let Some(patch_hash) = &patch.hash else {
+            return Err(Error::RuntimeError("patch has no hash".to_owned()));
+        };
+
+        let old = fs::read(file_path)?;
+        if blake3::hash(&old).to_hex().as_str() != patch.from {
+            return Err(Error::RuntimeError("installed file doesn't match the patch base".to_owned()));
+        }
+
+        // Never read more than the index says, a bigger patch is rejected anyway
+        let mut patch_bytes = Vec::with_capacity(patch.size);
+        agent.get(url).call()?.into_body().into_reader()
+            .take(patch.size as u64 + 1)
+            .read_to_end(&mut patch_bytes)?;
+        if patch_bytes.len() != patch.size {
+            return Err(Error::RuntimeError("patch size mismatch".to_owned()));
+        }
+        if blake3::hash(&patch_bytes).to_hex().as_str() != patch_hash {
+            return Err(Error::RuntimeError("patch hash mismatch".to_owned()));
+        }
+        add_bytes(patch_bytes.len());
+
+        let new = delta_patch::apply(patch.format, &old, &patch_bytes, file.size)?;
+        let hash = blake3::hash(&new).to_hex().to_string();
+        if new.len() != file.size || hash != file.hash {
+            return Err(Error::FileHashMismatch(file_path.to_str().unwrap_or("").to_string()));
+        }
+
+        let tmp_path = get_temp_path(file_path);
+        fs::write(&tmp_path, &new)?;
+        fs::rename(&tmp_path, file_path)?;
+        Ok(hash)
+    }
+
     fn download_zip(
         self: Arc<Self>,
         update_info: &UpdateInfo,
         localized_data_dir: &Path,
         cached_files: Arc<Mutex<FnvHashMap<String, String>>>
     ) -> Result<usize, Error> {
-        let zip_path = localized_data_dir.join(".tmp.zip");
-        let _zip_cleanup = ZipCleanupGuard(&zip_path);
+        // Kept outside of the repo dir so a partial download survives until the next attempt and can be resumed
+        let zip_path = Hachimi::instance().get_data_path(format!(".tl_repo_{}.zip", update_info.repo_id));
         // idk compiler going monkey mode unless i add this
         #[allow(unused_assignments)]
         let mut error_count = 0;
 
         {
-            let total_size_header = ureq::Agent::new_with_config(ureq_config()).head(&update_info.zip_url).call()
+            let total_size_header = ureq::Agent::new_with_config(ureq_config()).head(rewrite_url(&update_info.zip_url).as_ref()).call()
                 .ok()
                 .and_then(|res| {
                     res.headers()
@@ -1013,158 +1626,553 @@ impl Updater {
             http::download_file_parallel(
                 &update_info.zip_url,
                 &zip_path,
+                update_info.zip_hash.as_deref(),
                 *NUM_THREADS,
                 MIN_CHUNK_SIZE,
                 CHUNK_SIZE,
                 progress_bar
             )?;
 
-            let files_to_extract = Arc::new(
-                update_info.files.iter()
-                    .map(|f| (utils::concat_unix_path(&update_info.zip_dir, &f.path), f.clone()))
-                    .collect::<FnvHashMap<_, _>>()
-            );
+            // Fully downloaded, there's nothing left to resume if extracting it fails
+            let _zip_cleanup = ZipCleanupGuard(&zip_path);
+            error_count = self.clone().extract_zip(
+                &zip_path, &update_info.zip_dir, &update_info.files, update_info.size,
+                localized_data_dir, cached_files
+            )?;
+        }
 
-            let zip_file = fs::File::open(&zip_path)?;
-            let file_len = zip_file.metadata()?.len();
-            if file_len == 0 {
-                return Err(Error::RuntimeError("Downloaded ZIP file is empty".to_string()));
-            }
-            let mmap = Arc::new(unsafe { memmap2::Mmap::map(&zip_file)? });
-            if mmap.is_empty() {
-                return Err(Error::RuntimeError("Failed to memory-map the downloaded ZIP file".to_string()));
-            }
+        Ok(error_count)
+    }
 
-            let zip_len = zip::ZipArchive::new(Cursor::new(&mmap[..]))?.len();
-
-            let total_size = update_info.size;
-            let current_bytes = Arc::new(AtomicUsize::new(0));
-            let non_fatal_error_count = Arc::new(AtomicUsize::new(0));
-            let fatal_error = Arc::new(Mutex::new(None::<Error>));
-            let stop_signal = Arc::new(AtomicBool::new(false));
-
-            let (sender, receiver) = unbounded::<usize>();
-            let mut handles = Vec::with_capacity(*NUM_THREADS);
-
-            for _ in 0..*NUM_THREADS {
-                let updater = self.clone();
-                let mmap_thread = Arc::clone(&mmap);
-                let files_to_extract_clone = Arc::clone(&files_to_extract);
-                let localized_data_dir_clone = localized_data_dir.to_path_buf();
-                let cached_files_clone = Arc::clone(&cached_files);
-                let current_bytes_clone = Arc::clone(&current_bytes);
-                let non_fatal_error_count_clone = Arc::clone(&non_fatal_error_count);
-                let fatal_error_clone = Arc::clone(&fatal_error);
-                let stop_signal_clone = Arc::clone(&stop_signal);
-                let receiver_clone = receiver.clone();
-
-                let handle = thread::Builder::new()
-                    .name("zip_extractor".into())
-                    .stack_size(8 * 1024 * 1024)
-                    .spawn_with_priority(ThreadPriority::Min, move |result| {
-                        if result.is_err() {
-                            warn!("Failed to set background thread priority for zip extractor.");
-                        }
+    // Extracts the files from the zip and verifies their hashes, returns the non-fatal error count
+    fn extract_zip(
+        self: Arc<Self>,
+        zip_path: &Path,
+        zip_dir: &str,
+        files: &[RepoFile],
+        total_size: usize,
+        localized_data_dir: &Path,
+        cached_files: Arc<Mutex<FnvHashMap<String, String>>>
+    ) -> Result<usize, Error> {
+        let files_to_extract = Arc::new(
+            files.iter()
+                .map(|f| (utils::concat_unix_path(zip_dir, &f.path), f.clone()))
+                .collect::<FnvHashMap<_, _>>()
+        );
+
+        let zip_file = fs::File::open(zip_path)?;
+        let file_len = zip_file.metadata()?.len();
+        if file_len == 0 {
+            return Err(Error::RuntimeError("ZIP file is empty".to_string()));
+        }
+        let mmap = Arc::new(unsafe { memmap2::Mmap::map(&zip_file)? });
+        if mmap.is_empty() {
+            return Err(Error::RuntimeError("Failed to memory-map the ZIP file".to_string()));
+        }
 
-                        let mut archive = match zip::ZipArchive::new(Cursor::new(&mmap_thread[..])) {
-                            Ok(a) => a,
-                            Err(_) => return,
-                        };
+        let zip_len = zip::ZipArchive::new(Cursor::new(&mmap[..]))?.len();
 
-                        let mut buffer = vec![0u8; CHUNK_SIZE];
-                        let mut hasher = blake3::Hasher::new();
+        let current_bytes = Arc::new(AtomicUsize::new(0));
+        let non_fatal_error_count = Arc::new(AtomicUsize::new(0));
+        let fatal_error = Arc::new(Mutex::new(None::<Error>));
+        let stop_signal = Arc::new(AtomicBool::new(false));
 
-                        while let Ok(i) = receiver_clone.recv() {
-                            if stop_signal_clone.load(atomic::Ordering::Relaxed) { break; }
+        let (sender, receiver) = unbounded::<usize>();
+        let mut handles = Vec::with_capacity(*NUM_THREADS);
 
-                            let mut zip_entry = match archive.by_index(i) {
-                                Ok(entry) => entry,
-                                Err(_) => {
-                                    non_fatal_error_count_clone.fetch_add(1, atomic::Ordering::Relaxed);
-                                    continue;
-                                }
-                            };
+        for _ in 0..*NUM_THREADS {
+            let updater = self.clone();
+            let mmap_thread = Arc::clone(&mmap);
+            let files_to_extract_clone = Arc::clone(&files_to_extract);
+            let localized_data_dir_clone = localized_data_dir.to_path_buf();
+            let cached_files_clone = Arc::clone(&cached_files);
+            let current_bytes_clone = Arc::clone(&current_bytes);
+            let non_fatal_error_count_clone = Arc::clone(&non_fatal_error_count);
+            let fatal_error_clone = Arc::clone(&fatal_error);
+            let stop_signal_clone = Arc::clone(&stop_signal);
+            let receiver_clone = receiver.clone();
 
-                            let repo_file = match files_to_extract_clone.get(zip_entry.name()) {
-                                Some(file) => file.clone(),
-                                None => continue,
-                            };
+            let handle = thread::Builder::new()
+                .name("zip_extractor".into())
+                .stack_size(8 * 1024 * 1024)
+                .spawn_with_priority(ThreadPriority::Min, move |result| {
+                    if result.is_err() {
+                        warn!("Failed to set background thread priority for zip extractor.");
+                    }
 
-                            let path = repo_file.get_fs_path(&localized_data_dir_clone);
-                            if let Some(parent) = path.parent() {
-                                if Self::create_dir(parent, false).is_err() {
-                                    non_fatal_error_count_clone.fetch_add(1, atomic::Ordering::Relaxed);
-                                    continue;
-                                }
+                    let mut archive = match zip::ZipArchive::new(Cursor::new(&mmap_thread[..])) {
+                        Ok(a) => a,
+                        Err(_) => return,
+                    };
+
+                    let mut buffer = vec![0u8; CHUNK_SIZE];
+                    let mut hasher = blake3::Hasher::new();
+
+                    while let Ok(i) = receiver_clone.recv() {
+                        if stop_signal_clone.load(atomic::Ordering::Relaxed) { break; }
+
+                        let mut zip_entry = match archive.by_index(i) {
+                            Ok(entry) => entry,
+                            Err(_) => {
+                                non_fatal_error_count_clone.fetch_add(1, atomic::Ordering::Relaxed);
+                                continue;
+                            }
+                        };
+
+                        let repo_file = match files_to_extract_clone.get(zip_entry.name()) {
+                            Some(file) => file.clone(),
+                            None => continue,
+                        };
+
+                        let path = repo_file.get_fs_path(&localized_data_dir_clone);
+                        if let Some(parent) = path.parent() {
+                            if Self::create_dir(parent, false).is_err() {
+                                non_fatal_error_count_clone.fetch_add(1, atomic::Ordering::Relaxed);
+                                continue;
+                            }
+                        }
+
+                        let mut out_file = match fs::File::create(&path) {
+                            Ok(file) => file,
+                            Err(_) => {
+                                non_fatal_error_count_clone.fetch_add(1, atomic::Ordering::Relaxed);
+                                continue;
                             }
+                        };
 
-                            let mut out_file = match fs::File::create(&path) {
-                                Ok(file) => file,
+                        loop {
+                            match zip_entry.read(&mut buffer) {
+                                Ok(0) => break,
+                                Ok(read_bytes) => {
+                                    let data_slice = &buffer[..read_bytes];
+                                    if out_file.write_all(data_slice).is_err() {
+                                        *fatal_error_clone.lock().unwrap() = Some(Error::OutOfDiskSpace);
+                                        stop_signal_clone.store(true, atomic::Ordering::Relaxed);
+                                        return;
+                                    }
+                                    hasher.update(data_slice);
+                                    let prev_size = current_bytes_clone.fetch_add(read_bytes, atomic::Ordering::Relaxed);
+                                    store_progress(&updater.progress, &updater.last_progress_ms, prev_size + read_bytes, total_size);
+                                }
                                 Err(_) => {
                                     non_fatal_error_count_clone.fetch_add(1, atomic::Ordering::Relaxed);
-                                    continue;
+                                    break;
                                 }
-                            };
-
-                            loop {
-                                match zip_entry.read(&mut buffer) {
-                                    Ok(0) => break,
-                                    Ok(read_bytes) => {
-                                        let data_slice = &buffer[..read_bytes];
-                                        if out_file.write_all(data_slice).is_err() {
-                                            *fatal_error_clone.lock().unwrap() = Some(Error::OutOfDiskSpace);
-                                            stop_signal_clone.store(true, atomic::Ordering::Relaxed);
-                                            return;
-                                        }
-                                        hasher.update(data_slice);
-                                        let prev_size = current_bytes_clone.fetch_add(read_bytes, atomic::Ordering::Relaxed);
-                                        store_progress(&updater.progress, &updater.last_progress_ms, prev_size + read_bytes, total_size);
-                                    }
-                                    Err(_) => {
-                                        non_fatal_error_count_clone.fetch_add(1, atomic::Ordering::Relaxed);
-                                        break;
-                                    }
-                                }
-                            }
-
-                            let hash = hasher.finalize().to_hex().to_string();
-                            if hash != repo_file.hash {
-                                let path_str = path.to_str().unwrap_or("").to_string();
-                                *fatal_error_clone.lock().unwrap() = Some(Error::FileHashMismatch(path_str));
-                                stop_signal_clone.store(true, atomic::Ordering::Relaxed);
-                                return;
                             }
+                        }
 
-                            cached_files_clone.lock().unwrap().insert(repo_file.path.clone(), hash);
-                            hasher.reset();
+                        let hash = hasher.finalize().to_hex().to_string();
+                        if hash != repo_file.hash {
+                            let path_str = path.to_str().unwrap_or("").to_string();
+                            *fatal_error_clone.lock().unwrap() = Some(Error::FileHashMismatch(path_str));
+                            stop_signal_clone.store(true, atomic::Ordering::Relaxed);
+                            return;
                         }
-                    }).unwrap();
-                handles.push(handle);
+
+                        cached_files_clone.lock().unwrap().insert(repo_file.path.clone(), hash);
+                        hasher.reset();
+                    }
+                }).unwrap();
+            handles.push(handle);
+        }
+
+        for i in 0..zip_len {
+            if sender.send(i).is_err() { break; }
+        }
+        drop(sender);
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        if let Some(err) = fatal_error.lock().unwrap().take() { return Err(err); }
+        Ok(non_fatal_error_count.load(atomic::Ordering::Relaxed))
+    }
+
+    pub fn progress(&self) -> Option<UpdateProgress> {
+        (**self.progress.load()).clone()
+    }
+}
+
+pub const REPO_PACKAGE_EXTENSION: &str = ".tlrepo.zip";
+pub const REPO_PACKAGES_DIR: &str = "tl_repo_packages";
+const PACKAGE_MANIFEST_FILENAME: &str = "hachimi_tl_package.json";
+const PACKAGE_FILES_DIR: &str = "files";
+const PACKAGE_INDEX_FILENAME: &str = "index.json";
+const PACKAGE_SIGNATURE_FILENAME: &str = "index.json.sig";
+const PACKAGE_FORMAT_VERSION: u32 = 1;
+
+// Manifest of an offline repo package, the files are stored under PACKAGE_FILES_DIR
+#[derive(Serialize, Deserialize)]
+struct RepoPackageManifest {
+    format_version: u32,
+    index_url: String,
+    exported_at: String,
+    // Hashes of the packaged files
+    cache: RepoCache,
+    total_size: usize
+}
+
+fn is_repo_package(path: &Path) -> bool {
+    path.is_file() && path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.ends_with(REPO_PACKAGE_EXTENSION))
+}
+
+fn find_repo_packages(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| is_repo_package(path))
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Offline repo packages in the packages dir.
+pub fn list_repo_packages() -> Vec<PathBuf> {
+    find_repo_packages(&Hachimi::instance().get_data_path(REPO_PACKAGES_DIR))
+}
+
+fn sanitize_filename(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' || c == '.' { c } else { '_' })
+        .collect()
+}
+
+impl Updater {
+    /// Exports the installed files of a repo to an offline package in the packages dir.
+    pub fn export_package(self: Arc<Self>, repo_id: u32) {
+        thread::spawn(move || {
+            let result = self.export_package_internal(repo_id);
+            if let Err(e) = &result {
+                error!("Failed to export translation repo {}: {}", repo_id, e);
+            }
+            if let Some(mutex) = Gui::instance() {
+                let mut gui = mutex.lock().unwrap();
+                match result {
+                    Ok(path) => gui.show_notification(&t!("notification.tl_package_exported", path = path.display().to_string())),
+                    Err(e) => gui.show_notification(&t!("notification.tl_package_export_failed", reason = e.to_string()))
+                }
+            }
+        });
+    }
+
+    fn export_package_internal(&self, repo_id: u32) -> Result<PathBuf, Error> {
+        let Ok(_run_guard) = self.run_mutex.try_lock() else {
+            return Err(Error::RuntimeError("An update is in progress".to_owned()));
+        };
+
+        let hachimi = Hachimi::instance();
+        let Some(index_url) = hachimi.tl_repo_manager.lock().unwrap().find_by_id(repo_id).map(|s| s.to_owned()) else {
+            return Err(Error::RuntimeError(format!("Translation repo {} not found", repo_id)));
+        };
+        let mut cache = RepoCache::load(repo_id);
+        if cache.files.is_empty() {
+            return Err(Error::RuntimeError("The translation repo has not been downloaded".to_owned()));
+        }
+        // Let the importer's first update check compare the files instead
+        cache.index_etag = None;
+        let info = LocalRepoInfo::load(repo_id).ok().flatten();
+        // Signed repos can only be imported with the index their files were verified against
+        let signed_index = SignedIndex::load(repo_id);
+        let index_hashes: Option<FnvHashMap<String, String>> = match &signed_index {
+            Some(signed_index) => {
+                let index: RepoIndex = serde_json::from_slice(&signed_index.index)?;
+                Some(index.files.into_iter().map(|f| (f.path, f.hash)).collect())
+            }
+            None => None
+        };
+
+        let packages_dir = hachimi.get_data_path(REPO_PACKAGES_DIR);
+        fs::create_dir_all(&packages_dir)?;
+        let name = info.as_ref().map(|i| i.name.as_str()).filter(|n| !n.is_empty()).unwrap_or("repo");
+        let version = cache.revision.clone().unwrap_or_else(|| chrono::Utc::now().format("%Y%m%d").to_string());
+        let path = packages_dir.join(format!("{}{}", sanitize_filename(&format!("{}_{}", name, version)), REPO_PACKAGE_EXTENSION));
+        let tmp_path = path.with_extension("tmp");
+
+        let repo_dir = hachimi.get_repo_dir(repo_id);
+        let mut zip = zip::ZipWriter::new(fs::File::create(&tmp_path)?);
+        let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        let mut files = FnvHashMap::default();
+        let mut total_size = 0;
+        let result = (|| -> Result<(), Error> {
+            for (file_path, hash) in cache.files.iter() {
+                if file_path.contains("..") || Path::new(file_path).has_root() {
+                    continue;
+                }
+                let data = match fs::read(repo_dir.join(file_path)) {
+                    Ok(data) => data,
+                    Err(e) => {
+                        warn!("Skipping '{}': {}", file_path, e);
+                        continue;
+                    }
+                };
+                // Would fail verification on import, leave it to the online update
+                if blake3::hash(&data).to_hex().as_str() != hash {
+                    warn!("Skipping modified file '{}'", file_path);
+                    continue;
+                }
+                if index_hashes.as_ref().is_some_and(|h| h.get(file_path) != Some(hash)) {
+                    warn!("Skipping '{}', it doesn't match the signed index", file_path);
+                    continue;
+                }
+
+                zip.start_file(utils::concat_unix_path(PACKAGE_FILES_DIR, file_path), options)?;
+                zip.write_all(&data)?;
+                total_size += data.len();
+                files.insert(file_path.clone(), hash.clone());
+            }
+
+            if let Some(signed_index) = &signed_index {
+                zip.start_file(PACKAGE_INDEX_FILENAME, options)?;
+                zip.write_all(&signed_index.index)?;
+                zip.start_file(PACKAGE_SIGNATURE_FILENAME, options)?;
+                zip.write_all(signed_index.signature.as_bytes())?;
             }
 
-            for i in 0..zip_len {
-                if sender.send(i).is_err() { break; }
+            cache.files = files;
+            let manifest = RepoPackageManifest {
+                format_version: PACKAGE_FORMAT_VERSION,
+                index_url,
+                exported_at: chrono::Utc::now().to_rfc3339(),
+                cache,
+                total_size
+            };
+            zip.start_file(PACKAGE_MANIFEST_FILENAME, options)?;
+            zip.write_all(&serde_json::to_vec_pretty(&manifest)?)?;
+            zip.finish()?;
+            Ok(())
+        })();
+
+        if let Err(e) = result {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(e);
+        }
+        fs::rename(&tmp_path, &path)?;
+        Ok(path)
+    }
+
+    /// Installs an offline repo package, adding its repo to the repo list if needed.
+    pub fn import_package(self: Arc<Self>, path: PathBuf) {
+        thread::spawn(move || {
+            Self::notify_import(&path, self.import_package_internal(&path));
+        });
+    }
+
+    /// Imports the packages dropped into the data dir, then calls the callback.
+    pub fn import_dropped_packages(self: Arc<Self>, callback: impl FnOnce() + Send + 'static) {
+        thread::spawn(move || {
+            for path in find_repo_packages(&Hachimi::instance().get_data_path("")) {
+                info!("Importing translation repo package '{}'", path.display());
+                let result = self.clone().import_package_internal(&path);
+                if result.is_ok() {
+                    // Don't import it again on the next start
+                    if let Err(e) = fs::rename(&path, path.with_extension("imported")) {
+                        warn!("Failed to rename '{}': {}", path.display(), e);
+                    }
+                }
+                Self::notify_import(&path, result);
             }
-            drop(sender);
+            callback();
+        });
+    }
 
-            for handle in handles {
-                handle.join().unwrap();
+    fn notify_import(path: &Path, result: Result<u32, Error>) {
+        if let Err(e) = &result {
+            error!("Failed to import '{}': {}", path.display(), e);
+        }
+        if let Some(mutex) = Gui::instance() {
+            let mut gui = mutex.lock().unwrap();
+            match result {
+                Ok(_) => gui.show_notification(&t!("notification.tl_package_imported")),
+                Err(e) => gui.show_notification(&t!("notification.tl_package_import_failed", reason = e.to_string()))
             }
+        }
+    }
 
-            if let Some(err) = fatal_error.lock().unwrap().take() { return Err(err); }
-            error_count = non_fatal_error_count.load(atomic::Ordering::Relaxed);
+    // Replaces dir with new_dir. The old dir is moved to old_dir first and put back if the new one
+    // can't be moved in (e.g. a file in it is locked), so dir is never left missing.
+    fn swap_in_dir(new_dir: &Path, dir: &Path, old_dir: &Path) -> Result<(), Error> {
+        if old_dir.is_dir() {
+            fs::remove_dir_all(old_dir)?;
+        }
+        let had_dir = dir.is_dir();
+        if had_dir {
+            fs::rename(dir, old_dir)?;
         }
 
-        if let Err(e) = fs::remove_file(&zip_path) {
-            error!("Failed to remove temporary file '{}': {}", zip_path.display(), e);
-            error_count += 1;
+        if let Err(e) = fs::rename(new_dir, dir) {
+            if had_dir {
+                if let Err(e) = fs::rename(old_dir, dir) {
+                    error!("Failed to restore '{}': {}", dir.display(), e);
+                }
+            }
+            return Err(e.into());
         }
 
-        Ok(error_count)
+        if had_dir {
+            if let Err(e) = fs::remove_dir_all(old_dir) {
+                warn!("Failed to remove '{}': {}", old_dir.display(), e);
+            }
+        }
+        Ok(())
     }
 
-    pub fn progress(&self) -> Option<UpdateProgress> {
-        (**self.progress.load()).clone()
+    // Returns the id of the imported repo
+    fn import_package_internal(self: Arc<Self>, path: &Path) -> Result<u32, Error> {
+        let Ok(_run_guard) = self.run_mutex.try_lock() else {
+            return Err(Error::RuntimeError("An update is in progress".to_owned()));
+        };
+
+        let (manifest, signed_index): (RepoPackageManifest, _) = {
+            let mut archive = zip::ZipArchive::new(fs::File::open(path)?)?;
+            let manifest = {
+                let entry = archive.by_name(PACKAGE_MANIFEST_FILENAME)
+                    .map_err(|_| Error::RuntimeError("Not a translation repo package".to_owned()))?;
+                serde_json::from_reader(entry)?
+            };
+
+            let mut index = Vec::new();
+            let mut signature = String::new();
+            let has_index = match archive.by_name(PACKAGE_INDEX_FILENAME) {
+                Ok(mut entry) => { entry.read_to_end(&mut index)?; true }
+                Err(_) => false
+            };
+            let has_signature = match archive.by_name(PACKAGE_SIGNATURE_FILENAME) {
+                Ok(mut entry) => { entry.read_to_string(&mut signature)?; true }
+                Err(_) => false
+            };
+            (manifest, (has_index && has_signature).then_some(SignedIndex { index, signature }))
+        };
+        if manifest.format_version > PACKAGE_FORMAT_VERSION {
+            return Err(Error::RuntimeError(format!("Unsupported package version {}", manifest.format_version)));
+        }
+
+        let hachimi = Hachimi::instance();
+        let (existing_id, public_key) = {
+            let manager = hachimi.tl_repo_manager.lock().unwrap();
+            let entry = manager.repos.iter().find(|r| r.index == manifest.index_url);
+            (entry.map(|e| e.id), entry.and_then(|e| e.public_key.clone()))
+        };
+
+        // A repo with a pinned key only accepts files listed in an index signed with that key
+        let index_hashes = match &public_key {
+            Some(key) => {
+                let Some(signed_index) = &signed_index else {
+                    return Err(Error::SignatureVerification("the package has no signed index".to_owned()));
+                };
+                Some(signed_index.verify(key)?)
+            }
+            None => None
+        };
+        if let Some(index_hashes) = &index_hashes {
+            let matches_index = manifest.cache.files.iter()
+                .all(|(path, hash)| index_hashes.get(path) == Some(hash));
+            if !matches_index {
+                return Err(Error::SignatureVerification("the package doesn't match its signed index".to_owned()));
+            }
+        }
+
+        let repo_id = match existing_id {
+            Some(id) => id,
+            None => {
+                let mut manager = hachimi.tl_repo_manager.lock().unwrap();
+                let id = manager.add(manifest.index_url.clone());
+                manager.save(&hachimi.get_data_path(".tl_repos"))?;
+                id
+            }
+        };
+
+        // Extract next to the repo dir so the installed files stay usable if anything fails
+        let staging_dir = hachimi.get_data_path(format!(".tl_repo_import_{}", repo_id));
+        Self::create_dir(&staging_dir, true)?;
+        check_available_disk_space(&staging_dir, manifest.total_size as u64)?;
+
+        let files: Vec<RepoFile> = manifest.cache.files.iter()
+            .filter(|(path, _)| !path.contains("..") && !Path::new(path).has_root())
+            .map(|(path, hash)| RepoFile {
+                path: path.clone(),
+                hash: hash.clone(),
+                size: 0,
+                patches: Vec::new(),
+                delta_patch: None
+            })
+            .collect();
+        let cached_files = Arc::new(Mutex::new(FnvHashMap::default()));
+
+        self.last_progress_ms.store(0, atomic::Ordering::Relaxed);
+        self.progress.store(Arc::new(Some(UpdateProgress::new(0, manifest.total_size))));
+        if let Some(mutex) = Gui::instance() {
+            mutex.lock().unwrap().update_progress_visible = true;
+        }
+        let result = self.clone().extract_zip(
+            path, PACKAGE_FILES_DIR, &files, manifest.total_size, &staging_dir, cached_files.clone()
+        );
+        self.progress.store(Arc::new(None));
+
+        let result = result.and_then(|error_count| {
+            if error_count > 0 || cached_files.lock().unwrap().len() != files.len() {
+                return Err(Error::RuntimeError("The package is incomplete".to_owned()));
+            }
+            Ok(())
+        });
+        if let Err(e) = result {
+            let _ = fs::remove_dir_all(&staging_dir);
+            return Err(e);
+        }
+
+        let is_active = hachimi.get_active_tl_repos().iter().any(|r| r.id == repo_id);
+        if is_active {
+            hachimi.localized_data.store(Arc::new(LocalizedData::default()));
+        }
+
+        let repo_dir = hachimi.get_repo_dir(repo_id);
+        let result = Self::swap_in_dir(&staging_dir, &repo_dir, &hachimi.get_data_path(format!(".tl_repo_import_{}_old", repo_id)))
+            .and_then(|_| {
+                // Keeping the base url and hashes lets online updates pick up from here
+                let cache = RepoCache {
+                    base_url: manifest.cache.base_url,
+                    index_etag: None,
+                    revision: manifest.cache.revision,
+                    files: cached_files.lock().unwrap().clone()
+                };
+                utils::write_json_file(&cache, Self::get_repo_cache_path(repo_id))?;
+                if index_hashes.is_some() {
+                    if let Some(signed_index) = &signed_index {
+                        signed_index.save(repo_id)?;
+                    }
+                }
+
+                // The backup belonged to the replaced files
+                let backup_dir = get_repo_backup_dir(repo_id);
+                if backup_dir.is_dir() {
+                    fs::remove_dir_all(backup_dir)?;
+                }
+                Ok(())
+            });
+        if let Err(e) = result {
+            let _ = fs::remove_dir_all(&staging_dir);
+            if is_active {
+                hachimi.load_localized_data();
+            }
+            return Err(e);
+        }
+
+        let config = hachimi.config.load();
+        if config.selected_tl_repo_id.is_none() {
+            let mut new_config = (**config).clone();
+            new_config.selected_tl_repo_id = Some(repo_id);
+            new_config.translation_repo_index = Some(manifest.index_url);
+            hachimi.save_and_reload_config(new_config)?;
+        }
+        else if is_active {
+            hachimi.load_localized_data();
+        }
+
+        Ok(repo_id)
     }
 }
 
@@ -1173,12 +2181,21 @@ impl Updater {
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
 pub struct RepoList {
     pub repos: Vec<RepoEntry>,
+    // Repos layered below the selected repo, highest priority first
+    #[serde(default)]
+    pub fallback_ids: Vec<u32>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
 pub struct RepoEntry {
     pub id: u32,
     pub index: String,
+    // Base64 ed25519 key the repo's index must be signed with, pinned on first use
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+    // Revision the repo is held at; an empty string freezes it at whatever is installed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pinned_revision: Option<String>,
 }
 
 impl RepoList {
@@ -1203,10 +2220,22 @@ impl RepoList {
 
     pub fn add(&mut self, index: String) -> u32 {
         let id = self.next_id();
-        self.repos.push(RepoEntry { id, index });
+        self.repos.push(RepoEntry { id, index, ..Default::default() });
         id
     }
 
+    /// Pins the repo's signing key, unless it already has one. Returns true if the key was pinned.
+    pub fn pin_public_key(&mut self, id: u32, public_key: &str) -> bool {
+        let Some(entry) = self.repos.iter_mut().find(|r| r.id == id) else {
+            return false;
+        };
+        if entry.public_key.is_some() {
+            return false;
+        }
+        entry.public_key = Some(public_key.trim().to_owned());
+        true
+    }
+
     pub fn find_by_index(&self, index: &str) -> Option<u32> {
         self.repos.iter().find(|r| r.index == index).map(|r| r.id)
     }
@@ -1214,4 +2243,67 @@ impl RepoList {
     pub fn find_by_id(&self, id: u32) -> Option<&str> {
         self.repos.iter().find(|r| r.id == id).map(|r| r.index.as_str())
     }
+
+    pub fn remove(&mut self, id: u32) {
+        self.repos.retain(|r| r.id != id);
+        self.fallback_ids.retain(|&i| i != id);
+    }
+
+    pub fn is_fallback(&self, id: u32) -> bool {
+        self.fallback_ids.contains(&id)
+    }
+
+    /// Adds the repo to the bottom of the fallback chain, or removes it from the chain.
+    pub fn set_fallback(&mut self, id: u32, enabled: bool) {
+        self.fallback_ids.retain(|&i| i != id);
+        if enabled && self.find_by_id(id).is_some() {
+            self.fallback_ids.push(id);
+        }
+    }
+
+    /// Moves a fallback repo up (negative offset) or down the chain. Returns false if it can't be moved.
+    pub fn move_fallback(&mut self, id: u32, offset: isize) -> bool {
+        let Some(pos) = self.fallback_ids.iter().position(|&i| i == id) else {
+            return false;
+        };
+        let Some(new_pos) = pos.checked_add_signed(offset).filter(|&p| p < self.fallback_ids.len()) else {
+            return false;
+        };
+        let id = self.fallback_ids.remove(pos);
+        self.fallback_ids.insert(new_pos, id);
+        true
+    }
+
+    /// Makes a fallback repo the selected one, the previously selected repo becomes the first fallback.
+    pub fn promote_fallback(&mut self, id: u32, selected_id: Option<u32>) {
+        self.fallback_ids.retain(|&i| i != id);
+        if let Some(selected_id) = selected_id.filter(|&i| i != id) {
+            self.fallback_ids.retain(|&i| i != selected_id);
+            self.fallback_ids.insert(0, selected_id);
+        }
+    }
+
+    /// Active repos in priority order: the selected repo followed by its fallbacks.
+    pub fn active_chain(&self, selected_id: Option<u32>) -> Vec<RepoEntry> {
+        let mut chain: Vec<RepoEntry> = Vec::new();
+        for id in selected_id.into_iter().chain(self.fallback_ids.iter().copied()) {
+            if chain.iter().any(|r| r.id == id) {
+                continue;
+            }
+            if let Some(repo) = self.repos.iter().find(|r| r.id == id) {
+                chain.push(repo.clone());
+            }
+        }
+        chain
+    }
+
+    /// Drops fallback ids of repos that no longer exist. Returns true if anything was removed.
+    pub fn prune_fallbacks(&mut self) -> bool {
+        let len = self.fallback_ids.len();
+        let repos = &self.repos;
+        self.fallback_ids.retain(|id| repos.iter().any(|r| r.id == *id));
+        let mut seen = HashSet::new();
+        self.fallback_ids.retain(|id| seen.insert(*id));
+        self.fallback_ids.len() != len
+    }
 }
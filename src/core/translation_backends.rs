@@ -0,0 +1,462 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Error, translator::TranslationBackend};
+
+// All backends take their agent and endpoint from the caller so they can be pointed at any
+// server, including a local mock one.
+
+/// Sugoi Translator's "translate sentences" endpoint.
+pub struct SugoiBackend {
+    agent: ureq::Agent,
+    url: String
+}
+
+impl SugoiBackend {
+    pub const DEFAULT_URL: &'static str = "http://127.0.0.1:14366";
+
+    pub fn new(agent: ureq::Agent, url: String) -> Self {
+        SugoiBackend { agent, url }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "message")]
+enum SugoiMessage<'a> {
+    #[serde(rename = "translate sentences")]
+    TranslateSentences {
+        content: &'a [String]
+    }
+}
+
+impl TranslationBackend for SugoiBackend {
+    fn name(&self) -> &'static str { "sugoi" }
+
+    fn translate(&self, content: &[String]) -> Result<Vec<String>, Error> {
+        let res = self.agent.post(&self.url)
+            .header("Content-Type", "application/json")
+            .header("Connection", "close")
+            .send_json(SugoiMessage::TranslateSentences { content })?;
+
+        let body_str = res.into_body().read_to_string()?;
+        Ok(serde_json::from_str(&body_str)?)
+    }
+}
+
+/// Any server implementing the OpenAI chat completions API (OpenAI, llama.cpp, Ollama, vLLM...)
+/// Sentences are sent one per request, LLMs are not reliable at keeping batched output aligned.
+pub struct OpenAiBackend {
+    agent: ureq::Agent,
+    url: String,
+    api_key: Option<String>,
+    model: String,
+    system_prompt: String
+}
+
+impl OpenAiBackend {
+    pub const DEFAULT_URL: &'static str = "http://127.0.0.1:8080/v1";
+    pub const DEFAULT_MODEL: &'static str = "gpt-4o-mini";
+
+    pub fn new(agent: ureq::Agent, base_url: &str, api_key: Option<String>, model: String, system_prompt: String) -> Self {
+        let base_url = base_url.trim_end_matches('/');
+        let url = if base_url.ends_with("/chat/completions") {
+            base_url.to_owned()
+        }
+        else {
+            base_url.to_owned() + "/chat/completions"
+        };
+
+        OpenAiBackend { agent, url, api_key, model, system_prompt }
+    }
+
+    pub fn default_system_prompt(source_lang: &str, target_lang: &str) -> String {
+        format!(
+            "You are a translator for the game Umamusume: Pretty Derby. Translate the user's message from \
+            language code \"{}\" to language code \"{}\". Keep any markup tags and placeholders unchanged. \
+            Reply with the translation only.",
+            source_lang, target_lang
+        )
+    }
+}
+
+#[derive(Serialize)]
+struct ChatCompletionRequest<'a> {
+    model: &'a str,
+    messages: [ChatMessage<'a>; 2],
+    temperature: f32
+}
+
+#[derive(Serialize)]
+struct ChatMessage<'a> {
+    role: &'static str,
+    content: &'a str
+}
+
+#[derive(Deserialize)]
+struct ChatCompletionResponse {
+    choices: Vec<ChatCompletionChoice>
+}
+
+#[derive(Deserialize)]
+struct ChatCompletionChoice {
+    message: ChatCompletionMessage
+}
+
+#[derive(Deserialize)]
+struct ChatCompletionMessage {
+    content: Option<String>
+}
+
+impl TranslationBackend for OpenAiBackend {
+    fn name(&self) -> &'static str { "openai" }
+
+    fn cache_namespace(&self) -> String {
+        format!("openai.{}", self.model)
+    }
+
+    fn translate(&self, content: &[String]) -> Result<Vec<String>, Error> {
+        let mut translated = Vec::with_capacity(content.len());
+        for text in content {
+            let mut req = self.agent.post(&self.url)
+                .header("Content-Type", "application/json");
+            if let Some(api_key) = &self.api_key {
+                req = req.header("Authorization", &format!("Bearer {}", api_key));
+            }
+
+            let res: ChatCompletionResponse = req
+                .send_json(ChatCompletionRequest {
+                    model: &self.model,
+                    messages: [
+                        ChatMessage { role: "system", content: &self.system_prompt },
+                        ChatMessage { role: "user", content: text }
+                    ],
+                    temperature: 0.0
+                })?
+                .into_body()
+                .read_json()?;
+
+            let Some(output) = res.choices.into_iter().next().and_then(|c| c.message.content) else {
+                return Err(Error::RuntimeError("Server returned no completion choices".to_owned()));
+            };
+            translated.push(output.trim().to_owned());
+        }
+
+        Ok(translated)
+    }
+}
+
+/// LibreTranslate's /translate endpoint.
+pub struct LibreTranslateBackend {
+    agent: ureq::Agent,
+    url: String,
+    api_key: Option<String>,
+    source_lang: String,
+    target_lang: String
+}
+
+impl LibreTranslateBackend {
+    pub const DEFAULT_URL: &'static str = "http://127.0.0.1:5000";
+
+    pub fn new(agent: ureq::Agent, base_url: &str, api_key: Option<String>, source_lang: String, target_lang: String) -> Self {
+        let url = base_url.trim_end_matches('/').to_owned() + "/translate";
+        LibreTranslateBackend { agent, url, api_key, source_lang, target_lang }
+    }
+}
+
+#[derive(Serialize)]
+struct LibreTranslateRequest<'a> {
+    q: &'a [String],
+    source: &'a str,
+    target: &'a str,
+    format: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    api_key: Option<&'a str>
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LibreTranslateResponse {
+    translated_text: Vec<String>
+}
+
+impl TranslationBackend for LibreTranslateBackend {
+    fn name(&self) -> &'static str { "libretranslate" }
+
+    fn cache_namespace(&self) -> String {
+        format!("libretranslate.{}-{}", self.source_lang, self.target_lang)
+    }
+
+    fn translate(&self, content: &[String]) -> Result<Vec<String>, Error> {
+        let res: LibreTranslateResponse = self.agent.post(&self.url)
+            .header("Content-Type", "application/json")
+            .send_json(LibreTranslateRequest {
+                q: content,
+                source: &self.source_lang,
+                target: &self.target_lang,
+                format: "text",
+                api_key: self.api_key.as_deref()
+            })?
+            .into_body()
+            .read_json()?;
+
+        Ok(res.translated_text)
+    }
+}
+
+/// DeepL's v2 REST API (and servers mimicking it, such as DeepLX).
+pub struct DeepLBackend {
+    agent: ureq::Agent,
+    url: String,
+    api_key: Option<String>,
+    source_lang: String,
+    target_lang: String
+}
+
+impl DeepLBackend {
+    pub const DEFAULT_URL: &'static str = "https://api-free.deepl.com";
+
+    pub fn new(agent: ureq::Agent, base_url: &str, api_key: Option<String>, source_lang: &str, target_lang: &str) -> Self {
+        let base_url = base_url.trim_end_matches('/');
+        let url = if base_url.ends_with("/translate") {
+            base_url.to_owned()
+        }
+        else {
+            base_url.to_owned() + "/v2/translate"
+        };
+
+        DeepLBackend {
+            agent,
+            url,
+            api_key,
+            // DeepL wants uppercase language codes
+            source_lang: source_lang.to_ascii_uppercase(),
+            target_lang: target_lang.to_ascii_uppercase()
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct DeepLRequest<'a> {
+    text: &'a [String],
+    source_lang: &'a str,
+    target_lang: &'a str
+}
+
+#[derive(Deserialize)]
+struct DeepLResponse {
+    translations: Vec<DeepLTranslation>
+}
+
+#[derive(Deserialize)]
+struct DeepLTranslation {
+    text: String
+}
+
+impl TranslationBackend for DeepLBackend {
+    fn name(&self) -> &'static str { "deepl" }
+
+    fn cache_namespace(&self) -> String {
+        format!("deepl.{}-{}", self.source_lang, self.target_lang).to_ascii_lowercase()
+    }
+
+    fn translate(&self, content: &[String]) -> Result<Vec<String>, Error> {
+        let mut req = self.agent.post(&self.url)
+            .header("Content-Type", "application/json");
+        if let Some(api_key) = &self.api_key {
+            req = req.header("Authorization", &format!("DeepL-Auth-Key {}", api_key));
+        }
+
+        let res: DeepLResponse = req
+            .send_json(DeepLRequest {
+                text: content,
+                source_lang: &self.source_lang,
+                target_lang: &self.target_lang
+            })?
+            .into_body()
+            .read_json()?;
+
+        Ok(res.translations.into_iter().map(|t| t.text).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Read, thread::JoinHandle};
+
+    use tiny_http::{Response, Server};
+
+    use super::*;
+
+    struct ReceivedRequest {
+        body: serde_json::Value,
+        authorization: Option<String>
+    }
+
+    // Answers each request with the next response in order, returns the requests that were received
+    fn mock_server(responses: Vec<(u16, &'static str)>) -> (String, JoinHandle<Vec<ReceivedRequest>>) {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", server.server_addr().to_ip().unwrap());
+
+        let handle = std::thread::spawn(move || {
+            let mut received = Vec::new();
+            for (status, body) in responses {
+                let mut request = server.recv().unwrap();
+                let mut request_body = String::new();
+                request.as_reader().read_to_string(&mut request_body).unwrap();
+                received.push(ReceivedRequest {
+                    body: serde_json::from_str(&request_body).unwrap(),
+                    authorization: request.headers().iter()
+                        .find(|h| h.field.equiv("Authorization"))
+                        .map(|h| h.value.to_string())
+                });
+                request.respond(Response::from_string(body).with_status_code(status)).unwrap();
+            }
+            received
+        });
+
+        (url, handle)
+    }
+
+    fn agent() -> ureq::Agent {
+        ureq::Agent::new_with_defaults()
+    }
+
+    fn content() -> Vec<String> {
+        vec!["こんにちは".to_owned(), "さようなら".to_owned()]
+    }
+
+    #[test]
+    fn sugoi_success() {
+        let (url, server) = mock_server(vec![(200, r#"["Hello","Goodbye"]"#)]);
+        let backend = SugoiBackend::new(agent(), url);
+
+        assert_eq!(backend.translate(&content()).unwrap(), vec!["Hello", "Goodbye"]);
+
+        let received = server.join().unwrap();
+        assert_eq!(received[0].body["message"], "translate sentences");
+        assert_eq!(received[0].body["content"][1], "さようなら");
+    }
+
+    #[test]
+    fn sugoi_http_error() {
+        let (url, server) = mock_server(vec![(500, "Internal Server Error")]);
+        let backend = SugoiBackend::new(agent(), url);
+
+        assert!(backend.translate(&content()).is_err());
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn sugoi_malformed_body() {
+        let (url, server) = mock_server(vec![(200, "<html>not json</html>")]);
+        let backend = SugoiBackend::new(agent(), url);
+
+        assert!(backend.translate(&content()).is_err());
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn openai_success() {
+        let (url, server) = mock_server(vec![
+            (200, r#"{"choices":[{"message":{"role":"assistant","content":" Hello\n"}}]}"#),
+            (200, r#"{"choices":[{"message":{"role":"assistant","content":"Goodbye"}}]}"#)
+        ]);
+        let backend = OpenAiBackend::new(
+            agent(), &(url + "/v1/"), Some("key".to_owned()), "model".to_owned(), "prompt".to_owned()
+        );
+
+        assert_eq!(backend.translate(&content()).unwrap(), vec!["Hello", "Goodbye"]);
+
+        let received = server.join().unwrap();
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0].authorization.as_deref(), Some("Bearer key"));
+        assert_eq!(received[0].body["model"], "model");
+        assert_eq!(received[0].body["messages"][0]["content"], "prompt");
+        assert_eq!(received[1].body["messages"][1]["content"], "さようなら");
+    }
+
+    #[test]
+    fn openai_http_error() {
+        let (url, server) = mock_server(vec![(401, r#"{"error":{"message":"Invalid API key"}}"#)]);
+        let backend = OpenAiBackend::new(agent(), &url, None, "model".to_owned(), "prompt".to_owned());
+
+        assert!(backend.translate(&content()).is_err());
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn openai_malformed_body() {
+        let (url, server) = mock_server(vec![(200, r#"{"choices":[]}"#)]);
+        let backend = OpenAiBackend::new(agent(), &url, None, "model".to_owned(), "prompt".to_owned());
+
+        assert!(backend.translate(&content()).is_err());
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn libretranslate_success() {
+        let (url, server) = mock_server(vec![(200, r#"{"translatedText":["Hello","Goodbye"]}"#)]);
+        let backend = LibreTranslateBackend::new(
+            agent(), &url, Some("key".to_owned()), "ja".to_owned(), "en".to_owned()
+        );
+
+        assert_eq!(backend.translate(&content()).unwrap(), vec!["Hello", "Goodbye"]);
+
+        let received = server.join().unwrap();
+        assert_eq!(received[0].body["source"], "ja");
+        assert_eq!(received[0].body["target"], "en");
+        assert_eq!(received[0].body["api_key"], "key");
+        assert_eq!(received[0].body["q"][0], "こんにちは");
+    }
+
+    #[test]
+    fn libretranslate_http_error() {
+        let (url, server) = mock_server(vec![(429, r#"{"error":"Too many requests"}"#)]);
+        let backend = LibreTranslateBackend::new(agent(), &url, None, "ja".to_owned(), "en".to_owned());
+
+        assert!(backend.translate(&content()).is_err());
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn libretranslate_malformed_body() {
+        let (url, server) = mock_server(vec![(200, r#"{"translatedText":"Hello"}"#)]);
+        let backend = LibreTranslateBackend::new(agent(), &url, None, "ja".to_owned(), "en".to_owned());
+
+        assert!(backend.translate(&content()).is_err());
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn deepl_success() {
+        let (url, server) = mock_server(vec![
+            (200, r#"{"translations":[{"detected_source_language":"JA","text":"Hello"},{"text":"Goodbye"}]}"#)
+        ]);
+        let backend = DeepLBackend::new(agent(), &url, Some("key".to_owned()), "ja", "en-us");
+
+        assert_eq!(backend.translate(&content()).unwrap(), vec!["Hello", "Goodbye"]);
+
+        let received = server.join().unwrap();
+        assert_eq!(received[0].authorization.as_deref(), Some("DeepL-Auth-Key key"));
+        assert_eq!(received[0].body["source_lang"], "JA");
+        assert_eq!(received[0].body["target_lang"], "EN-US");
+        assert_eq!(received[0].body["text"][1], "さようなら");
+    }
+
+    #[test]
+    fn deepl_http_error() {
+        let (url, server) = mock_server(vec![(456, r#"{"message":"Quota exceeded"}"#)]);
+        let backend = DeepLBackend::new(agent(), &url, None, "ja", "en");
+
+        assert!(backend.translate(&content()).is_err());
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn deepl_malformed_body() {
+        let (url, server) = mock_server(vec![(200, r#"{"translations":[{"detected_source_language":"JA"}]}"#)]);
+        let backend = DeepLBackend::new(agent(), &url, None, "ja", "en");
+
+        assert!(backend.translate(&content()).is_err());
+        server.join().unwrap();
+    }
+}
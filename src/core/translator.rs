@@ -0,0 +1,317 @@
+use std::sync::{Arc, Mutex, mpsc::{self, Receiver, Sender}};
+
+use fnv::{FnvHashMap, FnvHashSet};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    glossary::Glossary,
+    hachimi::{LocalizedData, MtConfig},
+    http::ureq_config,
+    mt_cache::MtCache,
+    translation_backends::{DeepLBackend, LibreTranslateBackend, OpenAiBackend, SugoiBackend},
+    Error, Hachimi
+};
+
+/// A machine translation service. Implementations must return exactly one output per input, in order.
+pub trait TranslationBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Identifies the cached output of this backend in the persistent MT cache.
+    /// Should change whenever the output would (e.g. different target language).
+    fn cache_namespace(&self) -> String {
+        self.name().to_owned()
+    }
+
+    fn translate(&self, content: &[String]) -> Result<Vec<String>, Error>;
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TranslationBackendKind {
+    #[default]
+    Sugoi,
+    OpenAi,
+    LibreTranslate,
+    DeepL
+}
+
+/// Creates a backend from the current config.
+pub fn create_backend(config: &MtConfig, sugoi_url: Option<&str>) -> Box<dyn TranslationBackend> {
+    let agent = ureq::Agent::new_with_config(ureq_config());
+    let url = config.mt_url.as_deref().filter(|s| !s.is_empty());
+    let api_key = config.mt_api_key.clone().filter(|s| !s.is_empty());
+
+    match config.mt_backend {
+        TranslationBackendKind::Sugoi => Box::new(SugoiBackend::new(
+            agent,
+            url.or(sugoi_url).unwrap_or(SugoiBackend::DEFAULT_URL).to_owned()
+        )),
+
+        TranslationBackendKind::OpenAi => Box::new(OpenAiBackend::new(
+            agent,
+            url.unwrap_or(OpenAiBackend::DEFAULT_URL),
+            api_key,
+            config.mt_model.clone()
+                .filter(|s| !s.is_empty())
+                .unwrap_or_else(|| OpenAiBackend::DEFAULT_MODEL.to_owned()),
+            config.mt_system_prompt.clone()
+                .filter(|s| !s.is_empty())
+                .unwrap_or_else(|| OpenAiBackend::default_system_prompt(&config.mt_source_lang, &config.mt_target_lang))
+        )),
+
+        TranslationBackendKind::LibreTranslate => Box::new(LibreTranslateBackend::new(
+            agent,
+            url.unwrap_or(LibreTranslateBackend::DEFAULT_URL),
+            api_key,
+            config.mt_source_lang.clone(),
+            config.mt_target_lang.clone()
+        )),
+
+        TranslationBackendKind::DeepL => Box::new(DeepLBackend::new(
+            agent,
+            url.unwrap_or(DeepLBackend::DEFAULT_URL),
+            api_key,
+            &config.mt_source_lang,
+            &config.mt_target_lang
+        ))
+    }
+}
+
+struct BackendSlot {
+    mt_config: MtConfig,
+    sugoi_url: Option<String>,
+    backend: Arc<dyn TranslationBackend>,
+    namespace: Arc<str>
+}
+
+struct GlossarySlot {
+    // Rebuilt when the localized data is reloaded or the seed data is loaded
+    localized_data: Arc<LocalizedData>,
+    seed_len: (usize, usize),
+    glossary: Arc<Glossary>
+}
+
+pub struct Translator {
+    // Recreated whenever the MT config changes
+    backend: Mutex<Option<BackendSlot>>,
+    glossary: Mutex<Option<GlossarySlot>>,
+    request_lock: Mutex<()>,
+}
+
+static INSTANCE: Lazy<Arc<Translator>> = Lazy::new(|| {
+    if Hachimi::instance().config.load().mt_cache_enabled {
+        MtCache::preload();
+    }
+
+    Arc::new(Translator {
+        backend: Mutex::new(None),
+        glossary: Mutex::new(None),
+        request_lock: Mutex::new(()),
+    })
+});
+
+pub static TRANSLATION_QUEUE: Lazy<(Sender<(String, String)>, Mutex<Receiver<(String, String)>>)> = Lazy::new(|| {
+    let (tx, rx) = mpsc::channel();
+    (tx, Mutex::new(rx))
+});
+
+pub static TRANSLATION_CACHE: Lazy<Mutex<FnvHashMap<String, String>>> = Lazy::new(|| {
+    Mutex::new(FnvHashMap::default())
+});
+
+pub static PENDING_TRANSLATIONS: Lazy<Mutex<FnvHashSet<String>>> = Lazy::new(|| {
+    Mutex::new(FnvHashSet::default())
+});
+
+pub static REQUEST_QUEUE: Lazy<Sender<String>> = Lazy::new(|| {
+    let (tx, rx) = mpsc::channel::<String>();
+    let translation_tx = TRANSLATION_QUEUE.0.clone();
+
+    std::thread::Builder::new()
+        .name("translator_worker".into())
+        .spawn(move || {
+            while let Ok(original) = rx.recv() {
+                let mut batch = vec![original];
+
+                while batch.len() < 50 {
+                    if let Ok(next) = rx.try_recv() {
+                        batch.push(next);
+                    } else {
+                        break;
+                    }
+                }
+
+                let translator = Translator::instance();
+
+                match translator.translate(&batch) {
+                    Ok(translated) => {
+                        let mut pending = PENDING_TRANSLATIONS.lock().unwrap_or_else(|e| e.into_inner());
+                        for (orig, trans) in batch.into_iter().zip(translated.into_iter()) {
+                            let _ = translation_tx.send((orig.clone(), trans));
+                            pending.remove(&orig);
+                        }
+                    }
+                    Err(e) => {
+                        warn!("Machine translation failed: {}", e);
+                        let mut pending = PENDING_TRANSLATIONS.lock().unwrap_or_else(|e| e.into_inner());
+                        for orig in batch {
+                            pending.remove(&orig);
+                        }
+                    }
+                }
+            }
+        })
+        .expect("Failed to spawn translator_worker thread");
+
+    tx
+});
+
+impl Translator {
+    pub fn instance() -> Arc<Self> {
+        INSTANCE.clone()
+    }
+
+    /// Returns the active backend and its cache namespace.
+    pub fn backend(&self) -> (Arc<dyn TranslationBackend>, Arc<str>) {
+        let config = Hachimi::instance().config.load();
+        let mut guard = self.backend.lock().unwrap();
+
+        if let Some(slot) = guard.as_ref() {
+            if slot.mt_config == config.mt && slot.sugoi_url == config.sugoi_url {
+                return (slot.backend.clone(), slot.namespace.clone());
+            }
+        }
+
+        let backend: Arc<dyn TranslationBackend> = create_backend(&config.mt, config.sugoi_url.as_deref()).into();
+        let namespace: Arc<str> = backend.cache_namespace().into();
+        info!("Using machine translation backend: {} ({})", backend.name(), namespace);
+        *guard = Some(BackendSlot {
+            mt_config: config.mt.clone(),
+            sugoi_url: config.sugoi_url.clone(),
+            backend: backend.clone(),
+            namespace: namespace.clone()
+        });
+        (backend, namespace)
+    }
+
+    pub fn glossary(&self) -> Arc<Glossary> {
+        let hachimi = Hachimi::instance();
+        let localized_data = hachimi.localized_data.load_full();
+        let seed_len = (hachimi.chara_data.load().chara_names.len(), hachimi.skill_info.load().skill_names.len());
+        let mut guard = self.glossary.lock().unwrap();
+
+        if let Some(slot) = guard.as_ref() {
+            if Arc::ptr_eq(&slot.localized_data, &localized_data) && slot.seed_len == seed_len {
+                return slot.glossary.clone();
+            }
+        }
+
+        let glossary = Arc::new(Glossary::build());
+        debug!("Glossary built: {} terms", glossary.len());
+        *guard = Some(GlossarySlot { localized_data, seed_len, glossary: glossary.clone() });
+        glossary
+    }
+
+    pub fn get_cached(&self, original: &str) -> Option<String> {
+        if let Some(translated) = TRANSLATION_CACHE.lock().unwrap_or_else(|e| e.into_inner()).get(original) {
+            return Some(translated.clone());
+        }
+
+        // Only use the persistent cache if it's been loaded, this might be called from the main thread
+        if !Hachimi::instance().config.load().mt_cache_enabled {
+            return None;
+        }
+        let cache = MtCache::loaded_instance()?;
+        let (_, namespace) = self.backend();
+        let translated = cache.lock().unwrap().get(&namespace, original)?;
+        TRANSLATION_CACHE.lock().unwrap_or_else(|e| e.into_inner()).insert(original.to_owned(), translated.clone());
+        Some(translated)
+    }
+
+    /// Remembers which TextId a source string came from, for exporting the cache as a localize dict.
+    pub fn register_localize_key(&self, key: &str, source: &str) {
+        if !Hachimi::instance().config.load().mt_cache_enabled {
+            return;
+        }
+        if let Some(cache) = MtCache::loaded_instance() {
+            cache.lock().unwrap().register_localize_key(key, source);
+        }
+    }
+
+    pub fn translate_async(&self, original: String) {
+        if self.get_cached(&original).is_some() {
+            return;
+        }
+
+        let mut pending = PENDING_TRANSLATIONS.lock().unwrap_or_else(|e| e.into_inner());
+        if pending.insert(original.clone()) {
+            let _ = REQUEST_QUEUE.send(original);
+        }
+    }
+
+    pub fn translate(&self, content: &[String]) -> Result<Vec<String>, Error> {
+        let (backend, namespace) = self.backend();
+        if !Hachimi::instance().config.load().mt_cache_enabled {
+            let _guard = self.request_lock.lock().unwrap();
+            return self.translate_with(backend.as_ref(), content);
+        }
+
+        // Only send what's not in the persistent cache yet
+        let cache = MtCache::instance();
+        let mut results: Vec<Option<String>> = {
+            let mut cache = cache.lock().unwrap();
+            content.iter().map(|s| cache.get(&namespace, s)).collect()
+        };
+        let missing: Vec<String> = content.iter().zip(&results)
+            .filter(|(_, r)| r.is_none())
+            .map(|(s, _)| s.clone())
+            .collect();
+
+        if !missing.is_empty() {
+            let translated = {
+                let _guard = self.request_lock.lock().unwrap();
+                self.translate_with(backend.as_ref(), &missing)?
+            };
+
+            {
+                let mut cache = cache.lock().unwrap();
+                let mut translated_iter = translated.into_iter();
+                for (source, result) in content.iter().zip(results.iter_mut()) {
+                    if result.is_none() {
+                        let translation = translated_iter.next().unwrap();
+                        cache.insert(&namespace, source.clone(), translation.clone());
+                        *result = Some(translation);
+                    }
+                }
+            }
+            if let Err(e) = MtCache::save_if_due(cache) {
+                error!("Failed to save MT cache: {}", e);
+            }
+        }
+
+        Ok(results.into_iter().map(|r| r.unwrap()).collect())
+    }
+
+    fn translate_with(&self, backend: &dyn TranslationBackend, content: &[String]) -> Result<Vec<String>, Error> {
+        let glossary = self.glossary();
+        if glossary.is_empty() {
+            return Self::check_len(backend.translate(content)?, content.len());
+        }
+
+        let (masked, masks): (Vec<String>, Vec<Vec<&str>>) = content.iter().map(|s| glossary.mask(s)).unzip();
+        let res = Self::check_len(backend.translate(&masked)?, content.len())?;
+        Ok(res.iter().zip(masks).map(|(s, masks)| Glossary::restore(s, &masks)).collect())
+    }
+
+    fn check_len(res: Vec<String>, expected: usize) -> Result<Vec<String>, Error> {
+        if res.len() != expected {
+            return Err(Error::RuntimeError("Server returned invalid amount of translated content".to_owned()));
+        }
+        Ok(res)
+    }
+
+    pub fn translate_one(&self, content: String) -> Result<String, Error> {
+        let mut res = self.translate(&[content])?;
+        Ok(res.pop().unwrap())
+    }
+}
@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use chrono::Timelike;
+
+use crate::il2cpp::hook::umamusume::SceneManager;
+
+use super::hachimi::Config;
+
+// Views that shouldn't be interrupted by an update, as named in Gallop.SceneDefine.ViewId
+const BUSY_VIEWS: &[&str] = &[
+    "Live", "LiveTheater",
+    "Race", "DailyRace", "LegendRace", "TeamStadiumRace", "ChampionsRace", "HeroesRace",
+    "Story", "StoryEvent", "MainStory", "CharacterStory"
+];
+const MAX_BACKOFF: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Reason for holding back an automatic translation update, or None if it can run now.
+pub fn deferral_reason(config: &Config) -> Option<&'static str> {
+    if config.tl_auto_updater_avoid_busy_scenes && is_in_busy_scene() {
+        return Some("in a live, race or story scene");
+    }
+
+    if config.tl_auto_updater_quiet_hours && is_quiet_hour(
+        chrono::Local::now().hour(),
+        config.tl_auto_updater_quiet_start_hour,
+        config.tl_auto_updater_quiet_end_hour
+    ) {
+        return Some("quiet hours");
+    }
+
+    #[cfg(target_os = "android")]
+    if config.android.tl_auto_updater_unmetered_only &&
+        crate::android::utils::is_active_network_metered().unwrap_or(true)
+    {
+        return Some("on a metered network");
+    }
+
+    None
+}
+
+fn is_in_busy_scene() -> bool {
+    let Some(view_id) = SceneManager::current_view_id() else {
+        return false;
+    };
+    SceneManager::view_name(view_id)
+        .is_some_and(|name| BUSY_VIEWS.contains(&name))
+}
+
+// The range wraps around midnight if start > end
+fn is_quiet_hour(hour: u32, start: u32, end: u32) -> bool {
+    if start <= end {
+        hour >= start && hour < end
+    }
+    else {
+        hour >= start || hour < end
+    }
+}
+
+/// Delay until the next check after the given number of consecutive failures.
+pub fn backoff_delay(interval: Duration, failures: u32) -> Duration {
+    interval.saturating_mul(1 << failures.min(16))
+        .min(MAX_BACKOFF)
+        .max(interval)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quiet_hour_same_day() {
+        assert!(is_quiet_hour(9, 9, 17));
+        assert!(is_quiet_hour(16, 9, 17));
+        assert!(!is_quiet_hour(17, 9, 17));
+        assert!(!is_quiet_hour(8, 9, 17));
+        assert!(!is_quiet_hour(12, 9, 9));
+    }
+
+    #[test]
+    fn quiet_hour_wraps_midnight() {
+        assert!(is_quiet_hour(23, 23, 7));
+        assert!(is_quiet_hour(0, 23, 7));
+        assert!(is_quiet_hour(6, 23, 7));
+        assert!(!is_quiet_hour(7, 23, 7));
+        assert!(!is_quiet_hour(22, 23, 7));
+        assert!(!is_quiet_hour(12, 23, 7));
+    }
+
+    #[test]
+    fn backoff_grows_exponentially() {
+        let interval = Duration::from_secs(60);
+        assert_eq!(backoff_delay(interval, 0), interval);
+        assert_eq!(backoff_delay(interval, 1), Duration::from_secs(120));
+        assert_eq!(backoff_delay(interval, 3), Duration::from_secs(480));
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        let interval = Duration::from_secs(60 * 60);
+        assert_eq!(backoff_delay(interval, 10), MAX_BACKOFF);
+        assert_eq!(backoff_delay(interval, u32::MAX), MAX_BACKOFF);
+        assert_eq!(backoff_delay(Duration::MAX, 5), Duration::MAX);
+    }
+}
@@ -5,6 +5,13 @@ use serde::Deserialize;
 
 use crate::core::{gui::{NotificationGuard, SimpleYesNoDialog}, hachimi::{REPO_PATH, CODEBERG_API, GITHUB_API}, http, Error, Gui, Hachimi};
 
+#[cfg(target_os = "windows")]
+const INSTALLER_DOWNLOAD_THREADS: usize = 4;
+#[cfg(target_os = "windows")]
+const INSTALLER_MIN_CHUNK_SIZE: u64 = 1024 * 1024; // 1MiB
+#[cfg(target_os = "windows")]
+const INSTALLER_BUFFER_SIZE: usize = 8192; // 8KiB
+
 #[derive(Default)]
 pub struct Updater {
     update_check_mutex: Mutex<()>,
@@ -137,31 +144,18 @@ impl Updater {
                 UI::{Shell::ShellExecuteW, WindowsAndMessaging::{PostMessageW, SW_NORMAL, WM_CLOSE}}
             }
         };
-        use std::{fs::File, io::Read};
 
-        // Download the installer
+        // Download and verify the installer, resuming a previous attempt if there is one
         let installer_path = utils::get_tmp_installer_path();
-
-        let res = ureq::get(&asset.browser_download_url).call()?;
-        let mut reader = res.into_body().into_reader();
-        std::io::copy(&mut reader, &mut File::create(&installer_path)?)?;
-
-        // Verify the installer
-        if let Some(expected_hash) = &asset.expected_hash {
-            let mut file = File::open(&installer_path)?;
-            let mut hasher = blake3::Hasher::new();
-            let mut buffer = [0u8; 8192];
-
-            while let Ok(n) = file.read(&mut buffer) {
-                if n == 0 { break; }
-                hasher.update(&buffer[..n]);
-            }
-
-            if hasher.finalize().to_hex().as_str() != expected_hash {
-                let _ = std::fs::remove_file(&installer_path);
-                return Err(Error::FileHashMismatch(installer_path.to_string_lossy().into()));
-            }
-        }
+        http::download_file_parallel(
+            &asset.browser_download_url,
+            &installer_path,
+            asset.expected_hash.as_deref(),
+            INSTALLER_DOWNLOAD_THREADS,
+            INSTALLER_MIN_CHUNK_SIZE,
+            INSTALLER_BUFFER_SIZE,
+            Arc::new(|_| {})
+        )?;
 
         // Launch the installer
         let hachimi_path_str = unsafe { get_module_file_name(DLL_HMODULE) };
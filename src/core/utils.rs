@@ -1,7 +1,7 @@
-use std::{borrow::Cow, fs::File, io::Write, sync::Mutex, path::Path, time::SystemTime};
+use std::{borrow::Cow, cell::Cell, fs::File, io::Write, sync::Mutex, path::Path, time::SystemTime};
 
 use serde::Serialize;
-use textwrap::{core::Word, wrap_algorithms, WordSeparator::UnicodeBreakProperties};
+use textwrap::{core::Word, wrap_algorithms::{self, Penalties}, WordSeparator::UnicodeBreakProperties};
 use unicode_width::UnicodeWidthChar;
 use fnv::FnvHashMap;
 use once_cell::sync::Lazy;
@@ -17,6 +17,11 @@ pub struct SendPtr(pub *mut Il2CppObject);
 unsafe impl Send for SendPtr {}
 unsafe impl Sync for SendPtr {}
 
+thread_local! {
+    // Used instead of the loaded localized data's penalties while wrapping text for another repo
+    static WRAPPER_PENALTIES_OVERRIDE: Cell<Option<Penalties>> = const { Cell::new(None) };
+}
+
 static LOCALIZE_ID_CACHE: Lazy<Mutex<FnvHashMap<String, i32>>> =
     Lazy::new(|| Mutex::new(FnvHashMap::default()));
 
@@ -106,10 +111,6 @@ pub fn concat_unix_path(left: &str, right: &str) -> String {
     str
 }
 
-pub fn print_json_entry(key: &str, value: &str) {
-    info!("{}: {},", serde_json::to_string(key).unwrap(), serde_json::to_string(value).unwrap());
-}
-
 pub struct IsolateTags<'a> {
     s: &'a str,
     bytes: std::str::Bytes<'a>,
@@ -313,8 +314,8 @@ fn custom_wrap_algorithm<'a, 'b>(words: &'b [Word<'a>], line_widths: &'b [usize]
         clean_fragments.push(words[i]);
     }
 
-    let config = &Hachimi::instance().localized_data.load();
-    let penalties = &config.wrapper_penalties;
+    let penalties = &WRAPPER_PENALTIES_OVERRIDE.get()
+        .unwrap_or_else(|| Hachimi::instance().localized_data.load().wrapper_penalties);
     // quick escape!!!11
     let f64_line_widths = line_widths.iter().map(|w| *w as f64).collect::<Vec<_>>();
     if remove_offset == 0 {
@@ -376,6 +377,14 @@ fn wrap_text_internal(string: &str, base_line_width: i32, line_width_multiplier:
     return textwrap::wrap(string, &options);
 }
 
+/// Wraps text with explicit settings instead of the ones from the loaded localized data.
+pub fn wrap_text_with<'a>(string: &'a str, base_line_width: i32, line_width_multiplier: f32, penalties: Penalties) -> Vec<Cow<'a, str>> {
+    WRAPPER_PENALTIES_OVERRIDE.set(Some(penalties));
+    let wrapped = wrap_text_internal(string, base_line_width, line_width_multiplier);
+    WRAPPER_PENALTIES_OVERRIDE.set(None);
+    wrapped
+}
+
 pub fn wrap_text_il2cpp(string: *mut Il2CppString, base_line_width: i32) -> Option<*mut Il2CppString> {
     let config = &Hachimi::instance().localized_data.load().config;
     if !config.use_text_wrapper { return None; }
@@ -679,6 +688,24 @@ pub fn load_rgba_png_file<P: AsRef<Path>>(path: P) -> Option<(Vec<u8>, png::Outp
     load_rgba_png(File::open(path).ok()?)
 }
 
+pub fn encode_rgba_png(pixels: &[u8], width: u32, height: u32) -> Result<Vec<u8>, Error> {
+    // 1MiB should be enough for most images
+    let mut png_buffer = Vec::with_capacity(std::cmp::min(pixels.len(), 1048576));
+    let mut encoder = png::Encoder::new(&mut png_buffer, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_compression(png::Compression::Fast);
+
+    { // Scope to drop writer and release borrow to png buffer
+        let mut writer = encoder.write_header()
+            .map_err(|e| Error::RuntimeError(format!("Failed to write PNG header: {}", e)))?;
+        writer.write_image_data(pixels)
+            .map_err(|e| Error::RuntimeError(format!("Failed to write PNG image: {}", e)))?;
+    }
+
+    Ok(png_buffer)
+}
+
 pub fn notify_error(message: impl AsRef<str>) {
     let s = message.as_ref();
     error!("{}", s);
@@ -2,13 +2,13 @@ use std::ptr::null_mut;
 
 use widestring::Utf16Str;
 
-use crate::{core::{ext::Utf16StringExt, Hachimi}, il2cpp::{
+use crate::{core::{asset_dump, ext::Utf16StringExt, Hachimi}, il2cpp::{
     hook::{
         UnityEngine_AssetBundleModule::AssetBundle,
         UnityEngine_CoreModule::Sprite
     },
     symbols::{get_field_from_name, get_field_object_value, Array},
-    types::*, utils::replace_texture_with_diff
+    types::*, utils::{dump_texture, replace_texture_with_diff}
 }};
 
 static mut CLASS: *mut Il2CppClass = null_mut();
@@ -23,36 +23,44 @@ fn get_sprites(this: *mut Il2CppObject) -> Array {
 
 // hook::UnityEngine_AssetBundleModule::AssetBundle
 // name: assets/_gallopresources/bundle/resources/atlas/**.asset
-pub fn on_LoadAsset(bundle: *mut Il2CppObject, this: *mut Il2CppObject, name: &Utf16Str) {
+pub fn on_LoadAsset(bundle: *mut Il2CppObject, this: *mut Il2CppObject, name: &Utf16Str) -> bool {
     if !name.starts_with(AssetBundle::ASSET_PATH_PREFIX) {
         debug!("non-resource atlas: {}", name);
-        return;
-    }
-
-    if Hachimi::instance().config.load().apply_atlas_workaround {
-        return;
+        return false;
     }
 
     let base_path = name[AssetBundle::ASSET_PATH_PREFIX.len()..].path_basename();
     if !base_path.starts_with("atlas/") {
         debug!("bad path: {}", name);
-        return;
+        return false;
     }
     let rel_replace_path = base_path.to_string() + ".png";
+
+    // All of the sprites in the atlas uses the same texture so we just need to replace one of them
+    let sprites = get_sprites(this);
+    let Some(texture) = (unsafe { sprites.as_slice().get(0) }).map(|sprite| Sprite::get_texture(*sprite)) else {
+        return false;
+    };
+
+    if asset_dump::is_enabled() {
+        dump_texture(texture, rel_replace_path.clone());
+    }
+
+    if Hachimi::instance().config.load().apply_atlas_workaround {
+        return false;
+    }
+
     let localized_data = Hachimi::instance().localized_data.load();
     let Some(replace_path) = localized_data.get_assets_path(&rel_replace_path) else {
-        return;
+        return false;
     };
     let metadata = localized_data.load_asset_metadata(&rel_replace_path);
     if !AssetBundle::check_asset_bundle_name(bundle, &metadata) {
-        return;
+        return false;
     }
 
-    // All of the sprites in the atlas uses the same texture so we just need to replace one of them
-    let sprites = get_sprites(this);
-    if let Some(sprite) = unsafe { sprites.as_slice().get(0) } {
-        replace_texture_with_diff(Sprite::get_texture(*sprite), replace_path, true);
-    }
+    replace_texture_with_diff(texture, replace_path, true);
+    false
 }
 
 pub fn init(Cute_UI_Assembly: *const Il2CppImage) {
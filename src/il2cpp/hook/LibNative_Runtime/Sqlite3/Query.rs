@@ -1,11 +1,22 @@
-use crate::il2cpp::{symbols::get_method_addr, types::*};
+use crate::{
+    core::missing_tl,
+    il2cpp::{ext::Il2CppStringExt, symbols::get_method_addr, types::*}
+};
 
 use super::Connection::SELECT_QUERIES;
 
 type GetTextFn = extern "C" fn(this: *mut Il2CppObject, idx: i32) -> *mut Il2CppString;
 pub extern "C" fn GetText(this: *mut Il2CppObject, idx: i32) -> *mut Il2CppString {
     if let Some(query) = SELECT_QUERIES.lock().unwrap().get(&(this as usize)) {
-        return query.get_text(this, idx).unwrap_or_else(|| get_orig_fn!(GetText, GetTextFn)(this, idx));
+        if let Some(text) = query.get_text(this, idx) {
+            return text;
+        }
+
+        let text = get_orig_fn!(GetText, GetTextFn)(this, idx);
+        if missing_tl::is_enabled() && !text.is_null() {
+            query.on_missing(this, idx, &unsafe { (*text).as_utf16str().to_string() });
+        }
+        return text;
     }
     get_orig_fn!(GetText, GetTextFn)(this, idx)
 }
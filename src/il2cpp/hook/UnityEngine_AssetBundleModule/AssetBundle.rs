@@ -1,4 +1,4 @@
-use std::sync::Mutex;
+use std::{ffi::{c_void, CStr}, sync::{Arc, Mutex}};
 
 use fnv::FnvHashMap;
 use once_cell::sync::Lazy;
@@ -75,40 +75,111 @@ extern "C" fn LoadAssetAsync_Internal(this: *mut Il2CppObject, name: *mut Il2Cpp
     request
 }
 
-type OnLoadAssetFn = fn(bundle: *mut Il2CppObject, asset: *mut Il2CppObject, name: &Utf16Str);
-pub fn on_LoadAsset(bundle: *mut Il2CppObject, asset: *mut Il2CppObject, name: *mut Il2CppString) {
-    let class = unsafe { (*asset).klass() };
-    //debug!("{} {}", unsafe { std::ffi::CStr::from_ptr((*class).name).to_str().unwrap() }, unsafe { (*name).as_utf16str() });
+/// Returns true to stop the handlers after it from running.
+pub type OnLoadAssetFn = fn(bundle: *mut Il2CppObject, asset: *mut Il2CppObject, name: &Utf16Str) -> bool;
+/// Returns true to stop the handlers after it from running.
+pub type PluginLoadAssetCallback = extern "C" fn(
+    bundle: *mut Il2CppObject, asset: *mut Il2CppObject, name: *mut Il2CppString, userdata: *mut c_void
+) -> bool;
+
+#[derive(Clone)]
+pub enum AssetClassFilter {
+    // Resolved on every call since hooks are initialized after the registry is set up
+    Getter(fn() -> *mut Il2CppClass),
+    Class(usize), // *mut Il2CppClass
+    // "Namespace.ClassName" or just "ClassName"
+    Name(String)
+}
 
-    let handler: OnLoadAssetFn = if class == GameObject::class() {
-        GameObject::on_LoadAsset
-    }
-    else if class == StoryTimelineData::class() {
-        StoryTimelineData::on_LoadAsset
-    }
-    else if class == Texture2D::class() {
-        Texture2D::on_LoadAsset
-    }
-    else if class == AtlasReference::class() {
-        AtlasReference::on_LoadAsset
-    }
-    else if class == StoryRaceTextAsset::class() {
-        StoryRaceTextAsset::on_LoadAsset
-    }
-    else if class == TextRubyData::class() {
-        TextRubyData::on_LoadAsset
+impl AssetClassFilter {
+    fn matches(&self, class: *mut Il2CppClass) -> bool {
+        match self {
+            Self::Getter(get_class) => get_class() == class,
+            Self::Class(ptr) => *ptr == class as usize,
+            Self::Name(full_name) => {
+                let (namespace, name) = full_name.rsplit_once('.').unwrap_or(("", full_name));
+                let (class_namespace, class_name) = unsafe { (CStr::from_ptr((*class).namespaze), CStr::from_ptr((*class).name)) };
+                class_name.to_bytes() == name.as_bytes() &&
+                    (namespace.is_empty() || class_namespace.to_bytes() == namespace.as_bytes())
+            }
+        }
     }
-    else if class == TextDotData::class() {
-        TextDotData::on_LoadAsset
+}
+
+#[derive(Clone)]
+pub enum LoadAssetCallback {
+    Builtin(OnLoadAssetFn),
+    Plugin {
+        callback: PluginLoadAssetCallback,
+        userdata: usize
     }
-    else if class == StoryParamChangeEffect::class() {
-        StoryParamChangeEffect::on_LoadAsset
+}
+
+#[derive(Clone)]
+pub struct LoadAssetHandler {
+    pub class: AssetClassFilter,
+    // Matched against the full asset name (assets/_gallopresources/bundle/resources/...)
+    pub path_prefix: Option<String>,
+    // Lower runs first, builtin handlers use 0
+    pub priority: i32,
+    pub callback: LoadAssetCallback
+}
+
+// Copied on write so the handlers can be called without holding the lock
+static LOAD_ASSET_HANDLERS: Lazy<Mutex<Arc<Vec<LoadAssetHandler>>>> = Lazy::new(|| Mutex::default());
+
+/// Adds a handler that runs when an asset of a matching class is loaded.
+/// Handlers with the same priority run in the order they were registered.
+pub fn register_load_asset_handler(handler: LoadAssetHandler) {
+    let mut handlers = LOAD_ASSET_HANDLERS.lock().unwrap();
+    let handlers = Arc::make_mut(&mut handlers);
+    let index = handlers.partition_point(|h| h.priority <= handler.priority);
+    handlers.insert(index, handler);
+}
+
+fn register_builtin_handlers() {
+    let builtin: [(fn() -> *mut Il2CppClass, OnLoadAssetFn); 8] = [
+        (GameObject::class, GameObject::on_LoadAsset),
+        (StoryTimelineData::class, StoryTimelineData::on_LoadAsset),
+        (Texture2D::class, Texture2D::on_LoadAsset),
+        (AtlasReference::class, AtlasReference::on_LoadAsset),
+        (StoryRaceTextAsset::class, StoryRaceTextAsset::on_LoadAsset),
+        (TextRubyData::class, TextRubyData::on_LoadAsset),
+        (TextDotData::class, TextDotData::on_LoadAsset),
+        (StoryParamChangeEffect::class, StoryParamChangeEffect::on_LoadAsset)
+    ];
+
+    for (class, handler) in builtin {
+        register_load_asset_handler(LoadAssetHandler {
+            class: AssetClassFilter::Getter(class),
+            path_prefix: None,
+            priority: 0,
+            callback: LoadAssetCallback::Builtin(handler)
+        });
     }
-    else {
-        return;
-    };
+}
 
-    handler(bundle, asset, unsafe { (*name).as_utf16str() });
+pub fn on_LoadAsset(bundle: *mut Il2CppObject, asset: *mut Il2CppObject, name: *mut Il2CppString) {
+    let class = unsafe { (*asset).klass() };
+    //debug!("{} {}", unsafe { std::ffi::CStr::from_ptr((*class).name).to_str().unwrap() }, unsafe { (*name).as_utf16str() });
+
+    let handlers = LOAD_ASSET_HANDLERS.lock().unwrap().clone();
+    let name_str = unsafe { (*name).as_utf16str() };
+    for handler in handlers.iter() {
+        if !handler.class.matches(class) ||
+            handler.path_prefix.as_ref().is_some_and(|prefix| !name_str.starts_with(prefix))
+        {
+            continue;
+        }
+
+        let handled = match &handler.callback {
+            LoadAssetCallback::Builtin(callback) => callback(bundle, asset, name_str),
+            LoadAssetCallback::Plugin { callback, userdata } => callback(bundle, asset, name, *userdata as _)
+        };
+        if handled {
+            break;
+        }
+    }
 }
 
 type LoadFromFileInternalFn = extern "C" fn(path: *mut Il2CppString, crc: u32, offset: u64) -> *mut Il2CppObject;
@@ -133,6 +204,8 @@ pub fn init(_UnityEngine_AssetBundleModule: *const Il2CppImage) {
         c"UnityEngine.AssetBundle::LoadFromFile_Internal(System.String,System.UInt32,System.UInt64)".as_ptr()
     );
 
+    register_builtin_handlers();
+
     new_hook!(LoadAsset_Internal_addr, LoadAsset_Internal);
     new_hook!(LoadAssetAsync_Internal_addr, LoadAssetAsync_Internal);
     new_hook!(LoadFromFile_Internal_addr, LoadFromFile_Internal);
@@ -80,9 +80,9 @@ impl_addr_wrapper_fn!(get_transform, GET_TRANSFORM_ADDR, *mut Il2CppObject, this
 
 // hook::UnityEngine_AssetBundleModule::AssetBundle
 // Generic GameObject handler for prefabs. Used for ui flash and combined ui flash
-pub fn on_LoadAsset(bundle: *mut Il2CppObject, this: *mut Il2CppObject, name: &Utf16Str) {
+pub fn on_LoadAsset(bundle: *mut Il2CppObject, this: *mut Il2CppObject, name: &Utf16Str) -> bool {
     if !name.starts_with(AssetBundle::ASSET_PATH_PREFIX) {
-        return;
+        return false;
     }
     let path = &name[AssetBundle::ASSET_PATH_PREFIX.len()..];
 
@@ -104,6 +104,7 @@ pub fn on_LoadAsset(bundle: *mut Il2CppObject, this: *mut Il2CppObject, name: &U
             TweenAnimationTimelineComponent::on_LoadAsset(bundle, component, name);
         }
     }
+    false
 }
 
 fn customize(component: *mut Il2CppObject) {
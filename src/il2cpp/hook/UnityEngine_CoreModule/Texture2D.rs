@@ -2,7 +2,7 @@ use std::{path::Path, ptr::null_mut};
 
 use widestring::Utf16Str;
 
-use crate::{core::{ext::Utf16StringExt, Hachimi}, il2cpp::{
+use crate::{core::{asset_dump, ext::Utf16StringExt, Hachimi}, il2cpp::{
     api::{il2cpp_object_new, il2cpp_resolve_icall},
     hook::{
         mscorlib,
@@ -112,17 +112,21 @@ pub fn render_to_texture(this: *mut Il2CppObject) -> *mut Il2CppObject {
 }
 
 // hook::UnityEngine_AssetBundleModule::AssetBundle
-pub fn on_LoadAsset(_bundle: *mut Il2CppObject, this: *mut Il2CppObject, name: &Utf16Str) {
+pub fn on_LoadAsset(_bundle: *mut Il2CppObject, this: *mut Il2CppObject, name: &Utf16Str) -> bool {
     if !name.starts_with(ASSET_PATH_PREFIX) {
         debug!("non-resource texture: {}", name);
-        return;
+        return false;
     }
 
     let orig_path = &name[ASSET_PATH_PREFIX.len()..];
+    if asset_dump::is_enabled() {
+        utils::dump_texture(this, format!("textures/{}", orig_path));
+    }
+
     let rel_replace_path = Path::new("textures").join(orig_path.to_string());
     let localized_data = Hachimi::instance().localized_data.load();
     let Some(replace_path) = localized_data.get_assets_path(&rel_replace_path) else {
-        return;
+        return false;
     };
 
     // Common diff handling
@@ -134,26 +138,28 @@ pub fn on_LoadAsset(_bundle: *mut Il2CppObject, this: *mut Il2CppObject, name: &
             // Don't allow direct loading fallback here, otherwise the common diff would be skipped
             // after the initial patch (when the texture has already been created)
             if utils::replace_texture_with_diff_ex(
-                this, &replace_path, utils::get_texture_diff_path(&replace_path), true, false
+                this, &replace_path, utils::find_texture_diff_path(&replace_path), true, false
             ) {
-                return;
+                return false;
             }
 
             // Try to load common diff for "Train" buttons
-            let rel_common_diff_path = Path::new("textures")
-                .join(format!("chara/_chr/petit/petit_chr_{}.diff.png", petit_type));
+            let rel_common_path = Path::new("textures")
+                .join(format!("chara/_chr/petit/petit_chr_{}.png", petit_type));
 
-            let Some(common_diff_path) = localized_data.get_assets_path(&rel_common_diff_path) else {
-                return;
+            let Some(common_path) = localized_data.get_assets_path(&rel_common_path) else {
+                return false;
             };
+            let common_diff_path = utils::find_texture_diff_path(&common_path);
 
             utils::replace_texture_with_diff_ex(this, &replace_path, &common_diff_path, true, true);
-            return;
+            return false;
         }
     }
 
     // Normal replacement procedure
     utils::replace_texture_with_diff(this, &replace_path, true);
+    false
 }
 
 static mut GETPIXELS32_ADDR: usize = 0;
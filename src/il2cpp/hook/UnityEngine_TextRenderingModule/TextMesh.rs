@@ -1,7 +1,7 @@
 use std::sync::Mutex;
 use fnv::FnvHashMap;
 use once_cell::sync::Lazy;
-use crate::core::sugoi_client::SugoiClient;
+use crate::core::translator::Translator;
 use crate::il2cpp::{ext::{Il2CppStringExt, StringExt}, hook::UnityEngine_CoreModule::Object, symbols::get_method_addr, types::*};
 
 pub static ACTIVE_TEXT_MESH_COMPONENTS: Lazy<Mutex<FnvHashMap<usize, String>>> = Lazy::new(|| {
@@ -23,7 +23,7 @@ pub extern "C" fn set_text_hook(this: *mut Il2CppObject, value: *mut Il2CppStrin
 
     ACTIVE_TEXT_MESH_COMPONENTS.lock().unwrap().insert(this as usize, orig_str.clone());
 
-    if let Some(trans) = SugoiClient::instance().get_cached(&orig_str) {
+    if let Some(trans) = Translator::instance().get_cached(&orig_str) {
         return get_orig_fn!(set_text_hook, SetTextFn)(this, trans.to_il2cpp_string());
     }
 
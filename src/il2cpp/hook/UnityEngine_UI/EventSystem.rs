@@ -1,4 +1,4 @@
-use crate::core::sugoi_client;
+use crate::core::translator;
 use crate::il2cpp::{symbols::{get_method_addr}, types::*};
 
 static mut GET_CURRENT_ADDR: usize = 0;
@@ -13,7 +13,7 @@ extern "C" fn Update(this: *mut Il2CppObject) {
 
     let mut completed = Vec::new();
     {
-        let rx = sugoi_client::TRANSLATION_QUEUE.1.lock().unwrap();
+        let rx = translator::TRANSLATION_QUEUE.1.lock().unwrap();
         while let Ok(msg) = rx.try_recv() {
             completed.push(msg);
         }
@@ -30,7 +30,7 @@ extern "C" fn Update(this: *mut Il2CppObject) {
     }
 
     {
-        let mut cache = sugoi_client::TRANSLATION_CACHE.lock().unwrap();
+        let mut cache = translator::TRANSLATION_CACHE.lock().unwrap();
         for (orig, trans) in &completed {
             cache.insert(orig.clone(), trans.clone());
         }
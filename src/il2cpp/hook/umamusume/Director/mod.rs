@@ -1,5 +1,5 @@
 use crate::{
-    core::{gui::IS_LIVE_SCENE, Hachimi},
+    core::{gui::IS_LIVE_SCENE, plugin_events, Hachimi},
     il2cpp::{
         ext::StringExt,
         sql,
@@ -135,6 +135,18 @@ extern "C" fn Awake(this: *mut Il2CppObject) {
     if Hachimi::instance().config.load().champions_live_show_text {
         patch_champions_live(this);
     }
+
+    if plugin_events::has_subscribers(plugin_events::EventType::LiveStarted) {
+        let load_settings = get_LoadSettings(this);
+        let music_id = if load_settings.is_null() { 0 } else { LiveLoadSettings::get_MusicId(load_settings) };
+        plugin_events::emit(plugin_events::Event::LiveStarted { music_id });
+    }
+}
+
+type OnDestroyFn = extern "C" fn(this: *mut Il2CppObject);
+extern "C" fn OnDestroy(this: *mut Il2CppObject) {
+    get_orig_fn!(OnDestroy, OnDestroyFn)(this);
+    plugin_events::emit(plugin_events::Event::LiveStopped);
 }
 
 pub fn init(umamusume: *const Il2CppImage) {
@@ -158,4 +170,7 @@ pub fn init(umamusume: *const Il2CppImage) {
 
     let pause_live_addr = get_method_addr(Director, c"PauseLive", 1);
     new_hook!(pause_live_addr, PauseLive);
+
+    let on_destroy_addr = get_method_addr(Director, c"OnDestroy", 0);
+    new_hook!(on_destroy_addr, OnDestroy);
 }
@@ -3,7 +3,7 @@ use std::{cell::LazyCell, collections::{hash_map::Entry, BTreeMap}};
 use fnv::FnvHashMap;
 
 use crate::{
-    core::{utils, Hachimi, SugoiClient},
+    core::{ipc, missing_tl::{self, MissingSource}, Hachimi, Translator},
     il2cpp::{ext::{Il2CppStringExt, StringExt}, symbols::{get_method_overload_addr, unbox}, types::*}
 };
 
@@ -42,18 +42,23 @@ pub extern "C" fn Get(id: i32) -> *mut Il2CppString {
     }
     else {
         let str = get_orig_fn!(Get, GetFn)(id);
-        if Hachimi::instance().config.load().translator_mode && id != 1109 && id != 1032 {
-            // 1109 and 1032 seems to be debugging strings (they're annoying)
-            utils::print_json_entry(name, unsafe { &(*str).as_utf16str().to_string() });
+        // 1109 and 1032 seems to be debugging strings (they're annoying)
+        let is_debug_string = id == 1109 || id == 1032;
+        if missing_tl::is_enabled() && !is_debug_string && !str.is_null() {
+            missing_tl::record(MissingSource::Localize { key: name }, &unsafe { (*str).as_utf16str().to_string() });
+        }
+        if !is_debug_string && !str.is_null() {
+            ipc::emit_missing_translation("localize_dict", name, || unsafe { (*str).as_utf16str().to_string() });
         }
         if hachimi.config.load().auto_translate_localize && !str.is_null() && unsafe { (*str).length > 0 } {
             let s = unsafe { (*str).as_utf16str().to_string() };
 
-            let sugoi = SugoiClient::instance();
-            if let Some(translated) = sugoi.get_cached(&s) {
+            let translator = Translator::instance();
+            translator.register_localize_key(name, &s);
+            if let Some(translated) = translator.get_cached(&s) {
                 return translated.to_il2cpp_string();
             } else {
-                sugoi.translate_async(s);
+                translator.translate_async(s);
             }
         }
         str
@@ -1,13 +1,19 @@
-use std::sync::atomic::{self, AtomicBool};
+use std::{ffi::CStr, sync::atomic::{self, AtomicBool, AtomicI32}};
+
+use fnv::FnvHashMap;
+use once_cell::sync::OnceCell;
 
 use crate::{
-    core::{Hachimi, game::Region},
+    core::{ipc, plugin_events, Hachimi, game::Region},
     il2cpp::{
-        symbols::{get_field_from_name, get_method_addr, SingletonLike},
+        api::il2cpp_field_get_name,
+        symbols::{find_nested_class, get_class, get_field_from_name, get_method_addr, get_static_field_value, FieldsIter, SingletonLike},
         types::*
     }
 };
 
+use super::StoryTimelineController;
+
 static SPLASH_SHOWN: AtomicBool = AtomicBool::new(false);
 pub fn is_splash_shown() -> bool {
     SPLASH_SHOWN.load(atomic::Ordering::Acquire)
@@ -18,6 +24,40 @@ pub fn is_home_init() -> bool {
     HOME_INIT.load(atomic::Ordering::Acquire)
 }
 
+static CURRENT_VIEW_ID: AtomicI32 = AtomicI32::new(-1);
+/// Last view id passed to ChangeView, or None if no view change has happened yet
+pub fn current_view_id() -> Option<i32> {
+    let id = CURRENT_VIEW_ID.load(atomic::Ordering::Acquire);
+    if id == -1 { None } else { Some(id) }
+}
+
+static VIEW_NAMES: OnceCell<FnvHashMap<i32, String>> = OnceCell::new();
+/// Name of a ViewId value, as declared in Gallop.SceneDefine.ViewId
+pub fn view_name(view_id: i32) -> Option<&'static str> {
+    VIEW_NAMES.get()?.get(&view_id).map(|s| s.as_str())
+}
+
+// Read the enum at runtime, the values differ between game versions
+fn load_view_names(umamusume: *const Il2CppImage) -> FnvHashMap<i32, String> {
+    let mut names = FnvHashMap::default();
+    let Ok(scene_define) = get_class(umamusume, c"Gallop", c"SceneDefine") else {
+        return names;
+    };
+    let Ok(view_id) = find_nested_class(scene_define, c"ViewId") else {
+        return names;
+    };
+
+    for field in FieldsIter::new(view_id) {
+        let name = unsafe { CStr::from_ptr(il2cpp_field_get_name(field)) }.to_string_lossy();
+        // Instance field holding the value itself
+        if name == "value__" {
+            continue;
+        }
+        names.insert(get_static_field_value::<i32>(field), name.into_owned());
+    }
+    names
+}
+
 static mut CLASS: *mut Il2CppClass = 0 as _;
 pub fn class() -> *mut Il2CppClass {
     unsafe { CLASS }
@@ -40,6 +80,11 @@ static mut GETCURRENTVIEWCONTROLLER_ADDR: usize = 0;
 impl_addr_wrapper_fn!(GetCurrentViewController, GETCURRENTVIEWCONTROLLER_ADDR, *mut Il2CppObject, this: *mut Il2CppObject);
 
 fn ChangeViewCommon(next_view_id: i32) {
+    CURRENT_VIEW_ID.store(next_view_id, atomic::Ordering::Release);
+    // Leaving the story view also ends the story
+    StoryTimelineController::clear_template_variables();
+    ipc::emit_event(ipc::Event::SceneChanged { view_id: next_view_id });
+    plugin_events::emit(plugin_events::Event::ViewChanged { view_id: next_view_id });
     if next_view_id == 1 { // ViewId.Splash
         SPLASH_SHOWN.store(true, atomic::Ordering::Release);
     }
@@ -93,6 +138,12 @@ extern "C" fn ChangeViewOther(
 pub fn init(umamusume: *const Il2CppImage) {
     get_class_or_return!(umamusume, Gallop, SceneManager);
 
+    let view_names = load_view_names(umamusume);
+    if view_names.is_empty() {
+        warn!("Failed to read SceneDefine.ViewId");
+    }
+    _ = VIEW_NAMES.set(view_names);
+
     unsafe {
         CLASS = SceneManager;
         GETCURRENTVIEWID_ADDR = get_method_addr(SceneManager, c"GetCurrentViewId", 0);
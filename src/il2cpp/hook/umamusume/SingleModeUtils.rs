@@ -27,6 +27,14 @@ extern "C" fn GetMonthTextByTurn(turn_set_id: i32, turn: i32) -> *mut Il2CppStri
                     _ => None
                 }
             }
+
+            fn get_variable(&mut self, name: &str) -> Option<template::Token> {
+                match name {
+                    "month" | "half" => template::Context::on_filter_eval(self, name, &[]).map(template::Token::StringLit),
+                    "month_number" => Some(template::Token::NumberLit(SingleModeTurn::get_Month(self.turn) as f64)),
+                    _ => template::get_global_variable(name)
+                }
+            }
         }
 
         let turn = GetMasterTurn(turn_set_id, turn);
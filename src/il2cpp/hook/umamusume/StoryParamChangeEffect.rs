@@ -78,7 +78,7 @@ pub fn set__animationText(this: *mut Il2CppObject, value: *mut Il2CppString) {
 }
 
 // name: assets/_gallopresources/bundle/resources/story/data/xx/yyyy/ast_param_change_effect_xxyyyyzzz.asset
-pub fn on_LoadAsset(bundle: *mut Il2CppObject, this: *mut Il2CppObject, name: &Utf16Str) {
+pub fn on_LoadAsset(bundle: *mut Il2CppObject, this: *mut Il2CppObject, name: &Utf16Str) -> bool {
     let base_path = name[ASSET_PATH_PREFIX.len()..].path_basename();
     debug!("Looking for ast_param_change_effect in {}", base_path);
 
@@ -86,9 +86,10 @@ pub fn on_LoadAsset(bundle: *mut Il2CppObject, this: *mut Il2CppObject, name: &U
     let asset_info: AssetInfo<StoryParamChangeEffectScriptableObjectData> =
         localized_data.load_asset_info(&base_path.to_string());
     if !AssetBundle::check_asset_bundle_name(bundle, asset_info.metadata_ref()) {
-        return;
+        return false;
     }
     patch_asset(this, asset_info.data.as_ref());
+    false
 }
 
 pub fn patch_asset(
@@ -32,17 +32,17 @@ fn Key_set_text(key: *mut Il2CppObject, value: *mut Il2CppString) {
 
 // hook::UnityEngine_AssetBundleModule::AssetBundle
 // name: assets/_gallopresources/bundle/resources/race/storyrace/text/storyrace_xxxxxxxxx.asset
-pub fn on_LoadAsset(_bundle: *mut Il2CppObject, this: *mut Il2CppObject, name: &Utf16Str) {
+pub fn on_LoadAsset(_bundle: *mut Il2CppObject, this: *mut Il2CppObject, name: &Utf16Str) -> bool {
     if !name.starts_with(ASSET_PATH_PREFIX) {
         // ???
-        return;
+        return false;
     }
 
     let base_path = name[ASSET_PATH_PREFIX.len()..].path_basename();
     let dict_path = base_path.to_string() + ".json";
     let localized_data = Hachimi::instance().localized_data.load();
     let Some(dict): Option<Vec<String>> = localized_data.load_assets_dict(Some(&dict_path)) else {
-        return;
+        return false;
     };
 
     let text_data = get_textData(this);
@@ -50,6 +50,7 @@ pub fn on_LoadAsset(_bundle: *mut Il2CppObject, this: *mut Il2CppObject, name: &
         let Some(text) = dict.get(i) else { continue };
         Key_set_text(*key, text.to_il2cpp_string());
     }
+    false
 }
 
 pub fn init(umamusume: *const Il2CppImage) {
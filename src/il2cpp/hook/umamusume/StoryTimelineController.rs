@@ -1,6 +1,14 @@
-use std::sync::{atomic::{self, AtomicI32}, Mutex};
+use std::sync::{atomic::{self, AtomicBool, AtomicI32}, Mutex};
 
-use crate::{core::Hachimi, il2cpp::{symbols::{get_method_addr, GCHandle}, types::*}};
+use crate::{
+    core::{ipc, plugin_events::{self, EventType}, template, Hachimi},
+    il2cpp::{
+        ext::Il2CppStringExt, hook::UnityEngine_CoreModule::Object,
+        symbols::{get_method_addr, GCHandle, IList}, types::*
+    }
+};
+
+use super::{StoryTimelineBlockData, StoryTimelineData, StoryTimelineTextClipData};
 
 static mut GET_ISFINISHED_ADDR: usize = 0;
 impl_addr_wrapper_fn!(get_IsFinished, GET_ISFINISHED_ADDR, bool, this: *mut Il2CppObject);
@@ -10,23 +18,180 @@ impl_addr_wrapper_fn!(get_TimelineData, GET_TIMELINEDATA_ADDR, *mut Il2CppObject
 
 pub static CURRENT: Mutex<Option<GCHandle>> = Mutex::new(None);
 static LAST_BLOCK_ID: AtomicI32 = AtomicI32::new(-1);
+static CURRENT_TIMELINE_ID: Mutex<Option<String>> = Mutex::new(None);
+static CURRENT_BLOCK_COUNT: AtomicI32 = AtomicI32::new(0);
+// Set while a hot reload shows the current block again, it isn't a new block to listeners
+static RELOADING_BLOCK: AtomicBool = AtomicBool::new(false);
 
 pub fn last_block_id() -> i32 {
     LAST_BLOCK_ID.load(atomic::Ordering::Relaxed)
 }
 
+/// Asset name of the timeline data used by the current controller (e.g. storytimeline_xxyyyyzzz)
+pub fn current_timeline_id() -> Option<String> {
+    CURRENT_TIMELINE_ID.lock().unwrap().clone()
+}
+
+pub fn current_block_count() -> i32 {
+    CURRENT_BLOCK_COUNT.load(atomic::Ordering::Relaxed)
+}
+
+fn on_controller_changed(this: *mut Il2CppObject) {
+    let timeline_data = get_TimelineData(this);
+    if timeline_data.is_null() {
+        *CURRENT_TIMELINE_ID.lock().unwrap() = None;
+        CURRENT_BLOCK_COUNT.store(0, atomic::Ordering::Relaxed);
+        return;
+    }
+
+    let name = Object::get_name(timeline_data);
+    *CURRENT_TIMELINE_ID.lock().unwrap() = if name.is_null() {
+        None
+    }
+    else {
+        Some(unsafe { (*name).as_utf16str().to_string() })
+    };
+
+    let block_count = <IList>::new(StoryTimelineData::get_BlockList(timeline_data))
+        .map(|l| l.count())
+        .unwrap_or(0);
+    CURRENT_BLOCK_COUNT.store(block_count, atomic::Ordering::Relaxed);
+}
+
 type GotoBlockFn = extern "C" fn(this: *mut Il2CppObject, block_id: i32, weaken_cy_spring: bool, is_update: bool, is_choice: bool);
 pub extern "C" fn GotoBlock(this: *mut Il2CppObject, block_id: i32, weaken_cy_spring: bool, is_update: bool, is_choice: bool) {
-    if Hachimi::instance().config.load().enable_ipc {
+    // Also tracked for previewing hot reloaded dicts
+    let has_plugin_subscribers = plugin_events::has_subscribers(EventType::StoryBlockChanged) ||
+        plugin_events::has_subscribers(EventType::TextShown);
+    let track_current = {
+        let config = Hachimi::instance().config.load();
+        config.enable_ipc || config.hot_reload_localized_data || has_plugin_subscribers
+    };
+    if track_current {
         let mut guard = CURRENT.lock().unwrap();
 
-        if !(*guard).as_ref().is_none_or(|h| h.target() == this) {
+        if !(*guard).as_ref().is_some_and(|h| h.target() == this) {
             *guard = Some(GCHandle::new_weak_ref(this, false));
+            on_controller_changed(this);
         }
         LAST_BLOCK_ID.store(block_id, atomic::Ordering::Relaxed);
     }
 
+    set_template_variables(this, block_id);
+
     get_orig_fn!(GotoBlock, GotoBlockFn)(this, block_id, weaken_cy_spring, is_update, is_choice);
+    if get_IsFinished(this) {
+        clear_template_variables();
+    }
+
+    if (ipc::has_event_subscribers() || has_plugin_subscribers) && !RELOADING_BLOCK.load(atomic::Ordering::Relaxed) {
+        emit_block_events(this, block_id);
+    }
+}
+
+const TEMPLATE_VARIABLES: &[&str] = &["chara_name", "chara_gender"];
+
+// Exposes the block's speaker (name and gender) to templates in the block's text
+fn set_template_variables(this: *mut Il2CppObject, block_id: i32) {
+    let Some(clip_data) = get_text_clip(this, block_id) else {
+        clear_template_variables();
+        return;
+    };
+
+    let name = StoryTimelineTextClipData::get_Name(clip_data);
+    let name = if name.is_null() { None } else { Some(unsafe { (*name).as_utf16str().to_string() }) };
+    let gender = name.as_ref().and_then(|name| {
+        let chara_data = Hachimi::instance().chara_data.load();
+        chara_data.find_id_by_name(name).and_then(|id| chara_data.get_gender(id))
+    });
+
+    template::set_global_variable("chara_name", name.map(template::Token::StringLit));
+    template::set_global_variable("chara_gender", gender.map(|g| template::Token::StringLit(g.to_owned())));
+}
+
+/// Removes the story variables so they don't leak into templates outside of the story.
+pub fn clear_template_variables() {
+    for name in TEMPLATE_VARIABLES {
+        template::set_global_variable(name, None);
+    }
+}
+
+/// Re-applies the story dict to the block being shown and shows it again. Must be called from the main thread.
+pub fn reload_current_block() {
+    let controller = {
+        let guard = CURRENT.lock().unwrap();
+        let Some(controller) = (*guard).as_ref()
+            .map(|h| h.target())
+            .filter(|c| !c.is_null() && !get_IsFinished(*c))
+        else {
+            return;
+        };
+        controller
+    };
+
+    let block_id = last_block_id();
+    let timeline_data = get_TimelineData(controller);
+    let Some(dict_path) = current_timeline_id().and_then(|id| StoryTimelineData::dict_path_from_timeline_id(&id)) else {
+        return;
+    };
+    if timeline_data.is_null() || !StoryTimelineData::reapply_block(timeline_data, &dict_path, block_id) {
+        return;
+    }
+
+    RELOADING_BLOCK.store(true, atomic::Ordering::Relaxed);
+    GotoBlock(controller, block_id, false, false, false);
+    RELOADING_BLOCK.store(false, atomic::Ordering::Relaxed);
+}
+
+fn get_text_clip(this: *mut Il2CppObject, block_id: i32) -> Option<*mut Il2CppObject> {
+    let timeline_data = get_TimelineData(this);
+    if timeline_data.is_null() {
+        return None;
+    }
+
+    <IList>::new(StoryTimelineData::get_BlockList(timeline_data))
+        .and_then(|l| l.get(block_id))
+        .and_then(StoryTimelineBlockData::get_text_clip)
+}
+
+fn emit_block_events(this: *mut Il2CppObject, block_id: i32) {
+    let timeline_id = current_timeline_id();
+    let block_count = current_block_count();
+    plugin_events::emit(plugin_events::Event::StoryBlockChanged {
+        timeline_id: timeline_id.as_deref(),
+        block_id,
+        block_count
+    });
+    ipc::emit_event(ipc::Event::StoryBlockChanged {
+        timeline_id: timeline_id.clone(),
+        block_id,
+        block_count
+    });
+
+    let Some(clip_data) = get_text_clip(this, block_id) else {
+        return;
+    };
+
+    let text = StoryTimelineTextClipData::get_Text(clip_data);
+    if text.is_null() {
+        return;
+    }
+    let name = StoryTimelineTextClipData::get_Name(clip_data);
+    let name = if name.is_null() { None } else { Some(unsafe { (*name).as_utf16str().to_string() }) };
+    let text = unsafe { (*text).as_utf16str().to_string() };
+
+    plugin_events::emit(plugin_events::Event::TextShown {
+        timeline_id: timeline_id.as_deref(),
+        block_id,
+        name: name.as_deref(),
+        text: &text
+    });
+    ipc::emit_event(ipc::Event::TextShown {
+        timeline_id,
+        block_id,
+        name,
+        text
+    });
 }
 
 pub fn init(umamusume: *const Il2CppImage) {
@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 use widestring::Utf16Str;
 
 use crate::{
-    core::{ext::Utf16StringExt, utils, Hachimi, SugoiClient}, 
+    core::{asset_dump, ext::Utf16StringExt, hachimi::LocalizedData, missing_tl, mt_cache::MtCache, utils, Hachimi, Translator}, 
     il2cpp::{
         ext::{Il2CppStringExt, StringExt}, hook::{umamusume::{StoryTimelineCharaTrackData, StoryTimelineClipData}, UnityEngine_AssetBundleModule::AssetBundle::ASSET_PATH_PREFIX}, symbols::{get_field_from_name, get_field_object_value, get_field_value, set_field_object_value, set_field_value, IList}, types::*
     }
@@ -12,8 +12,8 @@ use crate::{
 
 use super::{StoryTimelineBlockData, StoryTimelineTextClipData, StoryTimelineTrackData};
 
-const CLIP_TEXT_LINE_WIDTH: i32 = 21;
-const CLIP_TEXT_LINE_COUNT: i32 = 3;
+pub const CLIP_TEXT_LINE_WIDTH: i32 = 21;
+pub const CLIP_TEXT_LINE_COUNT: i32 = 3;
 const CLIP_TEXT_FONT_SIZE_DEFAULT: i32 = 42;
 /*
 const CLIP_TEXT_FONT_SIZE_LARGE: i32 = 84;
@@ -22,7 +22,7 @@ const CLIP_TEXT_FONT_SIZE_BOLD_CAPTION: i32 = 64;
 */
 
 // probably?
-const STORY_VIEW_CLIP_TEXT_LINE_WIDTH: i32 = 32;
+pub const STORY_VIEW_CLIP_TEXT_LINE_WIDTH: i32 = 32;
 
 static mut CLASS: *mut Il2CppClass = null_mut();
 pub fn class() -> *mut Il2CppClass {
@@ -60,45 +60,65 @@ fn set_Length(this: *mut Il2CppObject, value: i32) {
 
 // (Aliases are there for tlg compatibility)
 #[derive(Serialize, Deserialize, Default)]
-struct StoryTimelineDataDict {
+pub struct StoryTimelineDataDict {
     #[serde(alias = "Title")]
-    title: Option<String>,
+    pub title: Option<String>,
 
     #[serde(alias = "TextBlockList")]
     #[serde(default)]
-    text_block_list: Vec<TextBlockDict>,
+    pub text_block_list: Vec<TextBlockDict>,
 
     #[serde(default)]
-    no_wrap: bool
+    pub no_wrap: bool
 }
 
 #[derive(Serialize, Deserialize, Default)]
-struct TextBlockDict {
+pub struct TextBlockDict {
     #[serde(alias = "Name")]
-    name: Option<String>,
+    pub name: Option<String>,
 
     #[serde(alias = "Text")]
-    text: Option<String>,
+    pub text: Option<String>,
 
     #[serde(alias = "ChoiceDataList")]
     #[serde(default)]
-    choice_data_list: Vec<String>,
+    pub choice_data_list: Vec<String>,
 
     #[serde(alias = "ColorTextInfoList")]
     #[serde(default)]
-    color_text_info_list: Vec<String>,
+    pub color_text_info_list: Vec<String>,
 
-    new_clip_length: Option<i32>
+    pub new_clip_length: Option<i32>
+}
+
+/// Whether the story is shown in the vertical story view (main stories), which doesn't limit the line count.
+/// `path` is relative to the assets dir (e.g. story/data/02/0001/storytimeline_020001001.json).
+pub fn is_story_view(path: &str) -> bool {
+    path.starts_with("story/data/") && (
+        path[11..].starts_with("02/") ||
+        path[11..].starts_with("04/") ||
+        path[11..].starts_with("09/")
+    )
 }
 
 // hook::UnityEngine_AssetBundleModule::AssetBundle
 // name:
 // - assets/_gallopresources/bundle/resources/home/data/xxxxx/yy/hometimeline_xxxxx_yy_zzzzzzz.asset
 // - assets/_gallopresources/bundle/resources/story/data/xx/yyyy/storytimeline_xxyyyyzzz.asset
-pub fn on_LoadAsset(_bundle: *mut Il2CppObject, this: *mut Il2CppObject, name: &Utf16Str) {
+pub fn on_LoadAsset(_bundle: *mut Il2CppObject, this: *mut Il2CppObject, name: &Utf16Str) -> bool {
     if !name.starts_with(ASSET_PATH_PREFIX) {
         // ???
-        return;
+        return false;
+    }
+
+    let base_path = name[ASSET_PATH_PREFIX.len()..].path_basename();
+    let dict_path = base_path.to_string() + ".json";
+
+    // Dumped before anything gets patched
+    if asset_dump::is_enabled() && asset_dump::should_dump(&dict_path) {
+        if let Some(dict) = build_source_dict(this) {
+            asset_dump::write_json(&dict_path, &dict);
+        }
     }
 
     let hachimi = Hachimi::instance();
@@ -109,40 +129,9 @@ pub fn on_LoadAsset(_bundle: *mut Il2CppObject, this: *mut Il2CppObject, name: &
         set_TypewriteCountPerSecond(this, tcps as i32);
     }
 
-    let base_path = name[ASSET_PATH_PREFIX.len()..].path_basename();
-    let dict_path = base_path.to_string() + ".json";
-
     let localized_data = hachimi.localized_data.load();
 
-    let is_story_view = base_path.starts_with("story/data/") && (
-        base_path[11..].starts_with("02/") ||
-        base_path[11..].starts_with("04/") ||
-        base_path[11..].starts_with("09/")
-    );
-
-    // Init wrapping parameters
-    let mut line_count = CLIP_TEXT_LINE_COUNT;
-    if let Some(offset) = localized_data.config.story_line_count_offset {
-        line_count += offset;
-    }
-
-    let mut font_size = CLIP_TEXT_FONT_SIZE_DEFAULT;
-    let mut line_width = CLIP_TEXT_LINE_WIDTH;
-    let mut story_view_line_width = STORY_VIEW_CLIP_TEXT_LINE_WIDTH;
-    if let Some(mult) = localized_data.config.text_frame_font_size_multiplier {
-        font_size = (font_size as f32 * mult).round() as i32;
-        line_width = (line_width as f32 / mult).round() as i32;
-        story_view_line_width = (story_view_line_width as f32 / mult).round() as i32;
-    }
-
-    let wp = WrapParams {
-        is_story_view,
-        line_count,
-        font_size,
-        line_width,
-        story_view_line_width,
-        size: StoryTimelineTextClipData::get_Size(this),
-    };
+    let wp = WrapParams::new(&localized_data, is_story_view(&dict_path), StoryTimelineTextClipData::get_Size(this));
 
     let Some(dict): Option<StoryTimelineDataDict> = localized_data.load_assets_dict(Some(&dict_path)).or_else(|| {
         if hachimi.config.load().auto_translate_stories {
@@ -155,19 +144,23 @@ pub fn on_LoadAsset(_bundle: *mut Il2CppObject, this: *mut Il2CppObject, name: &
                 return None;
             }
 
-            dispatch_auto_tl_async(this, full_dict_path, wp.clone());
+            dispatch_auto_tl_async(this, dict_path.clone(), full_dict_path, wp.clone());
             None
         }
         else {
             None
         }
     }) else {
+        if missing_tl::is_enabled() {
+            missing_tl::record_story(&dict_path, || build_source_dict(this));
+        }
+
         // Clip length adjustment independent of story patching
         // No need to adjust length if speed is faster
         if tcps_mult < 1.0 {
             adjust_clips_length_with_tcps(this, tcps);
         }
-        return;
+        return false;
     };
     debug!("{}", dict_path);
 
@@ -176,7 +169,7 @@ pub fn on_LoadAsset(_bundle: *mut Il2CppObject, this: *mut Il2CppObject, name: &
     }
 
     let Some(block_list) = IList::new(get_BlockList(this)) else {
-        return;
+        return false;
     };
 
     let mut total_len = 0;
@@ -203,25 +196,7 @@ pub fn on_LoadAsset(_bundle: *mut Il2CppObject, this: *mut Il2CppObject, name: &
         }
 
         if let Some(text) = &text_block_dict.text {
-            let mut modified_text = None;
-            if !dict.no_wrap {
-                if is_story_view {
-                    // Sizing tags are not used at all in main stories, simply wrap it
-                    // Add an extra space to each line because the vertical log screen ignores newlines
-                    if let Some(wrapped) = utils::wrap_text(text, story_view_line_width) {
-                        modified_text = Some(wrapped.join(" \n"));
-                    }
-                }
-                else {
-                    let size = StoryTimelineTextClipData::get_Size(this);
-                    if size == StoryTimelineTextClipData::FontSize_Default {
-                        if let Some(fitted) = utils::wrap_fit_text(text, line_width, line_count, font_size) {
-                            modified_text = Some(fitted);
-                        }
-                    }
-                    // not doing anything with text of other sizes for now...
-                }
-            }
+            let modified_text = if dict.no_wrap { None } else { wp.wrap(text) };
             let new_text = modified_text.as_ref().unwrap_or(text);
             StoryTimelineTextClipData::set_Text(clip_data, new_text.to_il2cpp_string());
 
@@ -253,39 +228,96 @@ pub fn on_LoadAsset(_bundle: *mut Il2CppObject, this: *mut Il2CppObject, name: &
             }
         }
 
-        // IList::new checks for null, no need to do so explicitly
-        let choice_data_list_obj = StoryTimelineTextClipData::get_ChoiceDataList(clip_data);
-        if let Some(choice_data_list) = IList::new(choice_data_list_obj) {
-            for (j, choice_data) in choice_data_list.iter().enumerate() {
-                if let Some(text) = text_block_dict.choice_data_list.get(j) {
-                    if !text.is_empty() {
-                        StoryTimelineTextClipData::ChoiceData::set_Text(choice_data, text.to_il2cpp_string())
-                    }
-                }
-                else {
-                    warn!("choice data {} of block {} not found in dict: {}", j, i, dict_path);
+        apply_option_texts(clip_data, text_block_dict, i, &dict_path);
+    }
+
+    if total_len_changed {
+        set_Length(this, total_len);
+    }
+    false
+}
+
+fn apply_option_texts(clip_data: *mut Il2CppObject, text_block_dict: &TextBlockDict, i: usize, dict_path: &str) {
+    // IList::new checks for null, no need to do so explicitly
+    let choice_data_list_obj = StoryTimelineTextClipData::get_ChoiceDataList(clip_data);
+    if let Some(choice_data_list) = IList::new(choice_data_list_obj) {
+        for (j, choice_data) in choice_data_list.iter().enumerate() {
+            if let Some(text) = text_block_dict.choice_data_list.get(j) {
+                if !text.is_empty() {
+                    StoryTimelineTextClipData::ChoiceData::set_Text(choice_data, text.to_il2cpp_string())
                 }
             }
+            else {
+                warn!("choice data {} of block {} not found in dict: {}", j, i, dict_path);
+            }
         }
+    }
 
-        let color_text_info_list_obj = StoryTimelineTextClipData::get_ColorTextInfoList(clip_data);
-        if let Some(color_text_info_list) = IList::new(color_text_info_list_obj) {
-            for (j, color_text_info) in color_text_info_list.iter().enumerate() {
-                if let Some(text) = text_block_dict.color_text_info_list.get(j) {
-                    if !text.is_empty() {
-                        StoryTimelineTextClipData::ColorTextInfo::set_Text(color_text_info, text.to_il2cpp_string())
-                    }
-                }
-                else {
-                    warn!("color text info {} of block {} not found in dict: {}", j, i, dict_path);
+    let color_text_info_list_obj = StoryTimelineTextClipData::get_ColorTextInfoList(clip_data);
+    if let Some(color_text_info_list) = IList::new(color_text_info_list_obj) {
+        for (j, color_text_info) in color_text_info_list.iter().enumerate() {
+            if let Some(text) = text_block_dict.color_text_info_list.get(j) {
+                if !text.is_empty() {
+                    StoryTimelineTextClipData::ColorTextInfo::set_Text(color_text_info, text.to_il2cpp_string())
                 }
             }
+            else {
+                warn!("color text info {} of block {} not found in dict: {}", j, i, dict_path);
+            }
         }
     }
+}
 
-    if total_len_changed {
-        set_Length(this, total_len);
+/// Dict path of a timeline asset name (e.g. storytimeline_xxyyyyzzz -> story/data/xx/yyyy/storytimeline_xxyyyyzzz.json)
+pub fn dict_path_from_timeline_id(timeline_id: &str) -> Option<String> {
+    if let Some(id) = timeline_id.strip_prefix("storytimeline_") {
+        if id.len() < 6 || !id.is_ascii() { return None; }
+        return Some(format!("story/data/{}/{}/{}.json", &id[0..2], &id[2..6], timeline_id));
+    }
+    if let Some(id) = timeline_id.strip_prefix("hometimeline_") {
+        let mut parts = id.split('_');
+        let (Some(set_id), Some(group_id)) = (parts.next(), parts.next()) else { return None; };
+        return Some(format!("home/data/{}/{}/{}.json", set_id, group_id, timeline_id));
     }
+    None
+}
+
+/// Re-applies the dict to a single block of an already loaded timeline, used to preview edits.
+/// Clip lengths are left as they are.
+pub fn reapply_block(this: *mut Il2CppObject, dict_path: &str, block_id: i32) -> bool {
+    // First block is always empty
+    let Some(i) = usize::try_from(block_id - 1).ok() else {
+        return false;
+    };
+
+    let localized_data = Hachimi::instance().localized_data.load();
+    let Some(dict): Option<StoryTimelineDataDict> = localized_data.load_assets_dict(Some(dict_path)) else {
+        return false;
+    };
+    let Some(text_block_dict) = dict.text_block_list.get(i) else {
+        return false;
+    };
+    let Some(clip_data) = <IList>::new(get_BlockList(this))
+        .and_then(|l| l.get(block_id))
+        .and_then(StoryTimelineBlockData::get_text_clip)
+    else {
+        return false;
+    };
+
+    if let Some(title) = &dict.title {
+        set_Title(this, title.to_il2cpp_string());
+    }
+    if let Some(name) = &text_block_dict.name {
+        StoryTimelineTextClipData::set_Name(clip_data, name.to_il2cpp_string());
+    }
+    if let Some(text) = &text_block_dict.text {
+        let wp = WrapParams::new(&localized_data, is_story_view(dict_path), StoryTimelineTextClipData::get_Size(this));
+        let modified_text = if dict.no_wrap { None } else { wp.wrap(text) };
+        StoryTimelineTextClipData::set_Text(clip_data, modified_text.as_ref().unwrap_or(text).to_il2cpp_string());
+    }
+    apply_option_texts(clip_data, text_block_dict, i, dict_path);
+
+    true
 }
 
 fn get_typewrite_length(text_len: usize, tcps: f32) -> i32 {
@@ -395,16 +427,56 @@ struct WrapParams {
     size: i32,
 }
 
-fn dispatch_auto_tl_async(this: *mut Il2CppObject, full_dict_path: std::path::PathBuf, wp: WrapParams) {
+impl WrapParams {
+    fn new(localized_data: &LocalizedData, is_story_view: bool, size: i32) -> WrapParams {
+        let mut line_count = CLIP_TEXT_LINE_COUNT;
+        if let Some(offset) = localized_data.config.story_line_count_offset {
+            line_count += offset;
+        }
+
+        let mut font_size = CLIP_TEXT_FONT_SIZE_DEFAULT;
+        let mut line_width = CLIP_TEXT_LINE_WIDTH;
+        let mut story_view_line_width = STORY_VIEW_CLIP_TEXT_LINE_WIDTH;
+        if let Some(mult) = localized_data.config.text_frame_font_size_multiplier {
+            font_size = (font_size as f32 * mult).round() as i32;
+            line_width = (line_width as f32 / mult).round() as i32;
+            story_view_line_width = (story_view_line_width as f32 / mult).round() as i32;
+        }
+
+        WrapParams {
+            is_story_view,
+            line_count,
+            font_size,
+            line_width,
+            story_view_line_width,
+            size
+        }
+    }
+
+    fn wrap(&self, text: &str) -> Option<String> {
+        if self.is_story_view {
+            // Sizing tags are not used at all in main stories, simply wrap it
+            // Add an extra space to each line because the vertical log screen ignores newlines
+            utils::wrap_text(text, self.story_view_line_width).map(|wrapped| wrapped.join(" \n"))
+        }
+        else if self.size == StoryTimelineTextClipData::FontSize_Default {
+            utils::wrap_fit_text(text, self.line_width, self.line_count, self.font_size)
+        }
+        else {
+            // not doing anything with text of other sizes for now...
+            None
+        }
+    }
+}
+
+// Builds a dict containing the story's original text
+fn build_source_dict(this: *mut Il2CppObject) -> Option<StoryTimelineDataDict> {
     let Some(block_list) = <IList>::new(get_BlockList(this)) else {
-        return;
+        return None;
     };
 
     let mut dict = StoryTimelineDataDict::default();
 
-    // Step 1: Prepare the tl batch and prepopulate the dict with Some()
-    // so we know which ones to fill in later
-
     let title = get_Title(this);
     if !title.is_null() && unsafe { (*title).length > 0 } {
         let title_str = unsafe { (*title).as_utf16str().to_string() };
@@ -462,10 +534,25 @@ fn dispatch_auto_tl_async(this: *mut Il2CppObject, full_dict_path: std::path::Pa
         dict.text_block_list.push(block_dict);
     }
 
+    Some(dict)
+}
+
+fn dispatch_auto_tl_async(this: *mut Il2CppObject, dict_path: String, full_dict_path: std::path::PathBuf, wp: WrapParams) {
+    // Step 1: Prepare the tl batch and prepopulate the dict with Some()
+    // so we know which ones to fill in later
+    let Some(mut dict) = build_source_dict(this) else {
+        return;
+    };
+
     // Step 2: Send it to the tl server
     std::thread::spawn(move || {
-        let sugoi = SugoiClient::instance();
-        let tx = crate::core::sugoi_client::TRANSLATION_QUEUE.0.clone();
+        let translator = Translator::instance();
+        let tx = crate::core::translator::TRANSLATION_QUEUE.0.clone();
+
+        // Keep the source layout so the MT cache can be exported as a story dict later
+        if Hachimi::instance().config.load().mt_cache_enabled {
+            MtCache::instance().lock().unwrap().register_story(&dict_path, &dict);
+        }
 
         let save_dict = |current_dict: &StoryTimelineDataDict| {
             if let Some(p) = full_dict_path.parent() {
@@ -482,25 +569,14 @@ fn dispatch_auto_tl_async(this: *mut Il2CppObject, full_dict_path: std::path::Pa
             if no_wrap || !is_text_block {
                 return text.to_string();
             }
-            if wp.is_story_view {
-                if let Some(wrapped) = crate::core::utils::wrap_text(text, wp.story_view_line_width) {
-                    return wrapped.join(" \n");
-                }
-            } else {
-                if wp.size == StoryTimelineTextClipData::FontSize_Default {
-                    if let Some(fitted) = crate::core::utils::wrap_fit_text(text, wp.line_width, wp.line_count, wp.font_size) {
-                        return fitted;
-                    }
-                }
-            }
-            text.to_string()
+            wp.wrap(text).unwrap_or_else(|| text.to_string())
         };
 
         let mut updates_made = 0;
 
         if let Some(title) = &mut dict.title {
             if !title.is_empty() {
-                let trans = sugoi.get_cached(title).or_else(|| sugoi.translate_one(title.clone()).ok());
+                let trans = translator.get_cached(title).or_else(|| translator.translate_one(title.clone()).ok());
                 if let Some(t) = trans {
                     let _ = tx.send((title.clone(), t.clone()));
                     *title = t;
@@ -516,7 +592,7 @@ fn dispatch_auto_tl_async(this: *mut Il2CppObject, full_dict_path: std::path::Pa
 
                 if let Some(name) = &mut block.name {
                     if !name.is_empty() {
-                        let trans = sugoi.get_cached(name).or_else(|| sugoi.translate_one(name.clone()).ok());
+                        let trans = translator.get_cached(name).or_else(|| translator.translate_one(name.clone()).ok());
                         if let Some(t) = trans {
                             let _ = tx.send((name.clone(), t.clone()));
                             *name = t;
@@ -527,7 +603,7 @@ fn dispatch_auto_tl_async(this: *mut Il2CppObject, full_dict_path: std::path::Pa
 
                 if let Some(text) = &mut block.text {
                     if !text.is_empty() {
-                        let trans = sugoi.get_cached(text).or_else(|| sugoi.translate_one(text.clone()).ok());
+                        let trans = translator.get_cached(text).or_else(|| translator.translate_one(text.clone()).ok());
                         if let Some(t) = trans {
                             let final_t = process_wrap(&t, true);
                             let _ = tx.send((text.clone(), final_t));
@@ -540,7 +616,7 @@ fn dispatch_auto_tl_async(this: *mut Il2CppObject, full_dict_path: std::path::Pa
 
                 for choice in block.choice_data_list.iter_mut() {
                     if !choice.is_empty() {
-                        let trans = sugoi.get_cached(choice).or_else(|| sugoi.translate_one(choice.clone()).ok());
+                        let trans = translator.get_cached(choice).or_else(|| translator.translate_one(choice.clone()).ok());
                         if let Some(t) = trans {
                             let final_t = process_wrap(&t, false);
                             let _ = tx.send((choice.clone(), final_t));
@@ -552,7 +628,7 @@ fn dispatch_auto_tl_async(this: *mut Il2CppObject, full_dict_path: std::path::Pa
 
                 for color_text in block.color_text_info_list.iter_mut() {
                     if !color_text.is_empty() {
-                        let trans = sugoi.get_cached(color_text).or_else(|| sugoi.translate_one(color_text.clone()).ok());
+                        let trans = translator.get_cached(color_text).or_else(|| translator.translate_one(color_text.clone()).ok());
                         if let Some(t) = trans {
                             let final_t = process_wrap(&t, false);
                             let _ = tx.send((color_text.clone(), final_t));
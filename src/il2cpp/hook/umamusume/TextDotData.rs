@@ -24,11 +24,12 @@ fn DotBlockData_class() -> *mut Il2CppClass {
     unsafe { DOTBLOCKDATA_CLASS }
 }
 
-pub fn on_LoadAsset(_bundle: *mut Il2CppObject, this: *mut Il2CppObject, _name: &Utf16Str) {
+pub fn on_LoadAsset(_bundle: *mut Il2CppObject, this: *mut Il2CppObject, _name: &Utf16Str) -> bool {
     if Hachimi::instance().localized_data.load().config.remove_ruby {
         let empty_array = il2cpp_array_new(DotBlockData_class(), 0);
         set_DataArray(this, empty_array);
     }
+    false
 }
 
 pub fn init(umamusume: *const Il2CppImage) {
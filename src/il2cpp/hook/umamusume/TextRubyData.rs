@@ -2,10 +2,11 @@ use widestring::Utf16Str;
 use std::ffi::c_void;
 
 use crate::{
-    core::{Hachimi, hachimi::CustomRubyBlock},
+    core::{asset_dump, Hachimi, hachimi::{CustomRubyBlock, CustomRubyDef, LocalizedData}},
     il2cpp::{
         api::{il2cpp_array_new, il2cpp_class_get_method_from_name, il2cpp_field_get_type, il2cpp_class_from_type, il2cpp_object_new, il2cpp_runtime_invoke, il2cpp_string_new_utf16},
-        symbols::{get_field_from_name, set_field_object_value, set_field_value},
+        ext::Il2CppStringExt,
+        symbols::{get_field_from_name, get_field_object_value, get_field_value, set_field_object_value, set_field_value, Array, IList},
         types::*
     }
 };
@@ -27,6 +28,10 @@ static mut RUBY_TEXT_FIELD: *mut FieldInfo = 0 as _;
 static mut BLOCK_INDEX_FIELD: *mut FieldInfo = 0 as _;
 static mut RUBY_DATA_LIST_FIELD: *mut FieldInfo = 0 as _;
 
+fn get_DataArray(this: *mut Il2CppObject) -> *mut Il2CppArray {
+    get_field_object_value(this, unsafe { DATAARRAY_FIELD })
+}
+
 fn set_DataArray(this: *mut Il2CppObject, value: *mut Il2CppArray) {
     set_field_object_value(this, unsafe { DATAARRAY_FIELD }, value as *mut Il2CppObject);
 }
@@ -83,8 +88,54 @@ unsafe fn inject_custom_ruby_blocks(this: *mut Il2CppObject, blocks: &[CustomRub
     set_DataArray(this, array);
 }
 
-pub fn on_LoadAsset(_bundle: *mut Il2CppObject, this: *mut Il2CppObject, name: &Utf16Str) {
+// Reads the original ruby data in the same format as the custom ruby dicts
+fn build_ruby_blocks(this: *mut Il2CppObject) -> Vec<CustomRubyBlock> {
+    let data_array = get_DataArray(this);
+    if data_array.is_null() {
+        return Vec::new();
+    }
+
+    let data_array = Array::<*mut Il2CppObject>::from(data_array);
+    let mut blocks = Vec::new();
+    for block_obj in unsafe { data_array.as_slice().iter() } {
+        let mut block = CustomRubyBlock {
+            block_index: get_field_value(*block_obj, unsafe { BLOCK_INDEX_FIELD }),
+            rubies: Vec::new()
+        };
+
+        let list_obj = get_field_object_value(*block_obj, unsafe { RUBY_DATA_LIST_FIELD });
+        if let Some(ruby_list) = IList::new(list_obj) {
+            for data_obj in ruby_list.iter() {
+                let ruby_text: *mut Il2CppString = get_field_object_value(data_obj, unsafe { RUBY_TEXT_FIELD });
+                block.rubies.push(CustomRubyDef {
+                    char_x: get_field_value(data_obj, unsafe { CHAR_X_FIELD }),
+                    char_y: get_field_value(data_obj, unsafe { CHAR_Y_FIELD }),
+                    ruby_text: if ruby_text.is_null() {
+                        String::new()
+                    }
+                    else {
+                        unsafe { (*ruby_text).as_utf16str().to_string() }
+                    }
+                });
+            }
+        }
+
+        blocks.push(block);
+    }
+
+    blocks
+}
+
+pub fn on_LoadAsset(_bundle: *mut Il2CppObject, this: *mut Il2CppObject, name: &Utf16Str) -> bool {
     let asset_name = name.to_string();
+    if asset_dump::is_enabled() {
+        if let Some(dict_path) = LocalizedData::custom_story_ruby_path(&asset_name) {
+            if asset_dump::should_dump(&dict_path) {
+                asset_dump::write_json(&dict_path, &build_ruby_blocks(this));
+            }
+        }
+    }
+
     let localized_data = Hachimi::instance().localized_data.load();
 
     if let Some(custom_blocks) = localized_data.load_custom_story_ruby(&asset_name) {
@@ -94,6 +145,7 @@ pub fn on_LoadAsset(_bundle: *mut Il2CppObject, this: *mut Il2CppObject, name: &
         let empty_array = unsafe { il2cpp_array_new(RUBYBLOCKDATA_CLASS, 0) };
         set_DataArray(this, empty_array);
     }
+    false
 }
 
 pub fn init(umamusume: *const Il2CppImage) {
@@ -3,7 +3,7 @@ use fnv::{FnvHashMap, FnvHashSet};
 use sqlparser::ast;
 use once_cell::sync::Lazy;
 use crate::{
-    core::{utils::{get_data_path, get_masterdb_path}, Hachimi},
+    core::{missing_tl::{self, MissingSource}, utils::{get_data_path, get_masterdb_path}, Hachimi},
     il2cpp::{ext::{StringExt, Il2CppStringExt}, hook::{LibNative_Runtime::Sqlite3::{Connection, Query}, umamusume::SceneManager}, types::{Il2CppObject, Il2CppString}}
 };
 use chrono::{Utc, Datelike};
@@ -16,19 +16,22 @@ pub static META_DATA: Lazy<RwLock<MetaData>> = Lazy::new(|| RwLock::new(MetaData
 #[derive(Default)]
 pub struct CharacterData {
     pub chara_ids: FnvHashSet<i32>,
-    pub chara_names: FnvHashMap<i32, String>
+    pub chara_names: FnvHashMap<i32, String>,
+    // chara_data.sex (1 = male, 2 = female)
+    pub chara_sexes: FnvHashMap<i32, i32>
 }
 
 impl CharacterData {
     pub fn load_from_db() -> Self {
         let mut chara_ids = FnvHashSet::default();
         let mut chara_names = FnvHashMap::default();
+        let mut chara_sexes = FnvHashMap::default();
 
         let db_path = get_masterdb_path();
         let conn = Connection::new();
 
         if Connection::Open(conn, db_path.to_il2cpp_string(), ptr::null_mut(), ptr::null_mut(), 0) {
-            let sql = "SELECT C.id, T.text FROM chara_data AS C JOIN text_data AS T ON C.id = T.\"index\" WHERE T.id = 6";
+            let sql = "SELECT C.id, T.text, C.sex FROM chara_data AS C JOIN text_data AS T ON C.id = T.\"index\" WHERE T.id = 6";
             let query = Connection::Query(conn, sql.to_il2cpp_string());
 
             if !query.is_null() {
@@ -39,6 +42,7 @@ impl CharacterData {
                     if let Some(name) = unsafe { name_ptr.as_ref() }.map(|s| s.as_utf16str().to_string()) {
                         chara_ids.insert(id);
                         chara_names.insert(id, name);
+                        chara_sexes.insert(id, Query::GetInt(query, 2));
                     }
                 }
                 Query::Dispose(query);
@@ -46,7 +50,7 @@ impl CharacterData {
             Connection::CloseDB(conn);
         }
 
-        CharacterData { chara_ids, chara_names }
+        CharacterData { chara_ids, chara_names, chara_sexes }
     }
 
     pub fn exists(&self, id: i32) -> bool {
@@ -69,6 +73,25 @@ impl CharacterData {
         // unknown character name
         "???".to_string()
     }
+
+    /// Looks up a character by their original or translated name.
+    pub fn find_id_by_name(&self, name: &str) -> Option<i32> {
+        if let Some(category_170) = Hachimi::instance().localized_data.load().text_data_dict.get(&170) {
+            if let Some((id, _)) = category_170.iter().find(|(_, n)| *n == name) {
+                return Some(*id);
+            }
+        }
+        self.chara_names.iter().find(|(_, n)| *n == name).map(|(id, _)| *id)
+    }
+
+    /// Returns "male" or "female", for templates.
+    pub fn get_gender(&self, id: i32) -> Option<&'static str> {
+        match self.chara_sexes.get(&id) {
+            Some(1) => Some("male"),
+            Some(2) => Some("female"),
+            _ => None
+        }
+    }
 }
 
 // untranslated skill info
@@ -151,6 +174,9 @@ pub trait SelectQueryState {
 
     /// Gets the resulting string on the current row's column.
     fn get_text(&self, query: *mut Il2CppObject, idx: i32) -> Option<*mut Il2CppString>;
+
+    /// Called with the original string when `get_text` returned None and translator mode is enabled.
+    fn on_missing(&self, _query: *mut Il2CppObject, _idx: i32, _text: &str) {}
 }
 
 #[derive(Default)]
@@ -294,6 +320,20 @@ impl SelectQueryState for TextDataQuery {
 
         None
     }
+
+    fn on_missing(&self, _query: *mut Il2CppObject, idx: i32, text: &str) {
+        if !self.text.is_select_idx(idx) {
+            return;
+        }
+
+        if let (Some(category), Some(index)) = (self.category.int_value, self.index.int_value) {
+            // Untranslated on purpose
+            if category == 47 && Hachimi::instance().config.load().disable_skill_name_translation {
+                return;
+            }
+            missing_tl::record(MissingSource::TextData { category, index }, text);
+        }
+    }
 }
 
 // character_system_text
@@ -348,6 +388,18 @@ impl SelectQueryState for CharacterSystemTextQuery {
 
         None
     }
+
+    fn on_missing(&self, query: *mut Il2CppObject, idx: i32, text: &str) {
+        if !self.text.is_select_idx(idx) {
+            return;
+        }
+
+        if let Some(character_id) = self.character_id.int_value {
+            if let Some(voice_id) = self.voice_id.value_or_try_get_int(query) {
+                missing_tl::record(MissingSource::CharacterSystemText { character_id, voice_id }, text);
+            }
+        }
+    }
 }
 
 // race_jikkyo_comment
@@ -385,6 +437,16 @@ impl SelectQueryState for RaceJikkyoCommentQuery {
 
         None
     }
+
+    fn on_missing(&self, query: *mut Il2CppObject, idx: i32, text: &str) {
+        if !self.message.is_select_idx(idx) {
+            return;
+        }
+
+        if let Some(id) = self.id.try_get_int(query) {
+            missing_tl::record(MissingSource::RaceJikkyoComment { id }, text);
+        }
+    }
 }
 
 // race_jikkyo_message
@@ -422,6 +484,16 @@ impl SelectQueryState for RaceJikkyoMessageQuery {
 
         None
     }
+
+    fn on_missing(&self, query: *mut Il2CppObject, idx: i32, text: &str) {
+        if !self.message.is_select_idx(idx) {
+            return;
+        }
+
+        if let Some(id) = self.id.try_get_int(query) {
+            missing_tl::record(MissingSource::RaceJikkyoMessage { id }, text);
+        }
+    }
 }
 
 
@@ -606,6 +678,40 @@ fn get_single_column_int(sql: &str) -> Vec<i32> {
     items
 }
 
+fn get_two_column_int(sql: &str) -> Vec<(i32, i32)> {
+    let mut items = Vec::new();
+    let db_path = get_masterdb_path();
+    let conn = Connection::new();
+    if Connection::Open(conn, db_path.to_il2cpp_string(), std::ptr::null_mut(), std::ptr::null_mut(), 0) {
+        let query = Connection::Query(conn, sql.to_il2cpp_string());
+        if !query.is_null() {
+            while Query::Step(query) {
+                items.push((Query::GetInt(query, 0), Query::GetInt(query, 1)));
+            }
+            Query::Dispose(query);
+        }
+        Connection::CloseDB(conn);
+    }
+    items
+}
+
+// Keys of the tables handled by the query hooks above, same as what their dicts are keyed by
+pub fn get_all_text_data_keys() -> Vec<(i32, i32)> {
+    get_two_column_int("SELECT id, \"index\" FROM text_data")
+}
+
+pub fn get_all_character_system_text_keys() -> Vec<(i32, i32)> {
+    get_two_column_int("SELECT character_id, voice_id FROM character_system_text")
+}
+
+pub fn get_all_race_jikkyo_comment_ids() -> Vec<i32> {
+    get_single_column_int("SELECT id FROM race_jikkyo_comment")
+}
+
+pub fn get_all_race_jikkyo_message_ids() -> Vec<i32> {
+    get_single_column_int("SELECT id FROM race_jikkyo_message")
+}
+
 pub fn get_all_chara_ids() -> Vec<i32> {
     get_single_column_int("SELECT id FROM chara_data")
 }
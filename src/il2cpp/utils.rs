@@ -1,11 +1,11 @@
-use std::{io::Write, path::{Path, PathBuf}};
+use std::{path::{Path, PathBuf}, sync::Mutex};
 
-use crate::{core::utils::{get_file_modified_time, load_rgba_png_file}, il2cpp::{ext::{Il2CppObjectExt, Il2CppStringExt}, hook::UnityEngine_CoreModule::{Component, RectTransform}, types::*}};
+use crate::{core::{asset_dump, texture_diff::TextureDiff, texture_diff_cache}, il2cpp::{ext::{Il2CppObjectExt, Il2CppStringExt}, hook::UnityEngine_CoreModule::{Component, Object, RectTransform}, types::*}};
 
 use super::{
     hook::{mscorlib, UnityEngine_CoreModule::{Texture, Texture2D},
     UnityEngine_ImageConversionModule::ImageConversion},
-    symbols::{get_assembly_image, get_class, get_method_addr_cached, Array}
+    symbols::{get_assembly_image, get_class, get_method_addr_cached, Array, GCHandle, Thread}
 };
 
 #[allow(dead_code)]
@@ -23,130 +23,152 @@ pub fn get_texture_diff_path<P: AsRef<Path>>(path: P) -> PathBuf {
     diff_path
 }
 
+pub fn get_texture_diff_manifest_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let mut manifest_path = path.as_ref().to_owned();
+    manifest_path.set_extension("diff.json");
+    manifest_path
+}
+
+/// Returns the diff manifest of the texture if there is one, or the plain diff path otherwise.
+pub fn find_texture_diff_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let manifest_path = get_texture_diff_manifest_path(&path);
+    if manifest_path.is_file() {
+        manifest_path
+    }
+    else {
+        get_texture_diff_path(path)
+    }
+}
+
 pub fn replace_texture_with_diff<P: AsRef<Path>>(texture: *mut Il2CppObject, path: P, mark_non_readable: bool) -> bool {
-    replace_texture_with_diff_ex(texture, &path, get_texture_diff_path(&path), mark_non_readable, true)
+    replace_texture_with_diff_ex(texture, &path, find_texture_diff_path(&path), mark_non_readable, true)
 }
 
+// Textures up to this many pixels are patched right away instead of on a worker thread
+const SYNC_PATCH_MAX_PIXELS: usize = 256 * 256;
+
+/// Replaces the texture's pixels with the texture at `path` or its diff.
+/// Returns true if the texture is being replaced. Large textures are patched on a worker thread,
+/// so they keep their original pixels for a few frames after this returns.
 pub fn replace_texture_with_diff_ex<P1: AsRef<Path>, P2: AsRef<Path>>(
     texture: *mut Il2CppObject, path: P1, diff_path: P2, mark_non_readable: bool, allow_fallback: bool
 ) -> bool {
-    let Some(diff_mtime) = get_file_modified_time(&diff_path) else {
-        // No diff, try to load image directly
-        return if allow_fallback {
-            Texture2D::load_image_file(texture, &path, mark_non_readable)
-        }
-        else {
-            false
-        }
-    };
+    let width = Texture::GetDataWidth(texture) as usize;
+    let height = Texture::GetDataHeight(texture) as usize;
 
-    if let Some(image_mtime) = get_file_modified_time(&path) {
-        if diff_mtime < image_mtime {
-            // Try to load image, otherwise generate it
-            // SAFETY: Path has been guaranteed to be a file in mtime check
-            if unsafe { Texture2D::load_image_file_unsafe(texture, &path, mark_non_readable) } {
-                return true;
-            }
+    // Cache hits skip loading the diff and reading back the original pixels
+    if let Some(key) = texture_diff_cache::get_memoized_key(path.as_ref(), diff_path.as_ref(), width, height) {
+        if let Some(png) = texture_diff_cache::load(&key) {
+            return load_png_into_texture(texture, &png, mark_non_readable);
         }
     }
 
-    let Some((mut pixels, diff_info)) = load_rgba_png_file(&diff_path) else {
-        error!("Failed to load texture diff: {}", diff_path.as_ref().display());
-        return false;
+    let diff = match TextureDiff::load(diff_path.as_ref()) {
+        Some(Ok(v)) => v,
+        Some(Err(e)) => {
+            error!("Failed to load texture diff '{}': {}", diff_path.as_ref().display(), e);
+            return false;
+        }
+        None => {
+            // No diff, try to load image directly
+            return if allow_fallback {
+                Texture2D::load_image_file(texture, &path, mark_non_readable)
+            }
+            else {
+                false
+            }
+        }
     };
 
-    let width = Texture::GetDataWidth(texture) as usize;
-    let height = Texture::GetDataHeight(texture) as usize;
-
-    if width as u32 != diff_info.width || height as u32 != diff_info.height {
-        error!(
-            "Texture diff size mismatch (expected {}x{}, got {}x{}): {}",
-            width, height, diff_info.width, diff_info.height, diff_path.as_ref().display()
-        );
+    if let Err(e) = diff.check_size(width as u32, height as u32) {
+        error!("{}: {}", e, diff_path.as_ref().display());
         return false;
     }
 
+    let key = texture_diff_cache::get_key(path.as_ref(), width, height, &diff);
+    texture_diff_cache::memoize_key(path.as_ref(), diff_path.as_ref(), width, height, &key);
+    if let Some(png) = texture_diff_cache::load(&key) {
+        return load_png_into_texture(texture, &png, mark_non_readable);
+    }
+
     let new_texture = Texture2D::render_to_texture(texture);
     let orig_pixels_array = Texture2D::GetPixels32(new_texture, 0);
-    let orig_pixels = unsafe { orig_pixels_array.as_slice() };
-
-    // Apply diff (reuse/write directly into diff pixels buffer)
-    for y in 0..height {
-        for x in 0..width {
-            let start = (y * width + x) * 4;
-            let end = start + 4;
-            let pixel = &mut pixels[start..end];
-            if pixel[3] == 0 {
-                // Use original pixel if diff pixel is transparent
-                // Original image is flipped
-                let orig_pixel = &orig_pixels[(height - y - 1) * width + x];
-                pixel.copy_from_slice(orig_pixel.as_slice());
-            }
-            else if pixel == [255, 0, 255, 255] {
-                // Make pixel transparent if it's #FF00FF
-                pixel.fill(0);
+    let orig_pixels = unsafe {
+        let slice = orig_pixels_array.as_slice();
+        std::slice::from_raw_parts(slice.as_ptr() as *const u8, slice.len() * 4).to_vec()
+    };
+    Object::Destroy(new_texture);
+
+    let diff_path = diff_path.as_ref().to_owned();
+    let patch = move || match diff.apply(&orig_pixels, width, height) {
+        Ok(v) => {
+            if let Err(e) = texture_diff_cache::store(&key, &v) {
+                error!("Failed to cache patched texture: {}", e);
             }
-            // else keep the diff pixel
+            Some(v)
+        }
+        Err(e) => {
+            error!("{}: {}", e, diff_path.display());
+            None
         }
+    };
+
+    if width * height <= SYNC_PATCH_MAX_PIXELS {
+        return patch().is_some_and(|png| load_png_into_texture(texture, &png, mark_non_readable));
     }
 
-    // 1MiB should be enough for most images
-    let mut png_buffer = Vec::with_capacity(std::cmp::min(pixels.len(), 1048576));
-    let mut encoder = png::Encoder::new(&mut png_buffer, width as u32, height as u32);
-    encoder.set_color(png::ColorType::Rgba);
-    encoder.set_depth(png::BitDepth::Eight);
-    encoder.set_compression(png::Compression::Fast);
-
-    { // Scope to drop writer and release borrow to png buffer
-        let mut writer = match encoder.write_header() {
-            Ok(v) => v,
-            Err(e) => {
-                error!("Failed to write PNG header: {}", e);
-                return false;
-            }
-        };
+    // Merge on a worker thread, the texture gets updated on the main thread once it's done
+    let handle = GCHandle::new_weak_ref(texture, false);
+    std::thread::spawn(move || {
+        let png = patch();
 
-        if let Err(e) = writer.write_image_data(&pixels) {
-            error!("Failed to write PNG image: {}", e);
-            return false;
-        }
-    }
+        // Also sent on failure so the handle gets freed on the main thread
+        PENDING_PATCHED_TEXTURES.lock().unwrap().push((handle, png, mark_non_readable));
+        Thread::main_thread().schedule(apply_pending_patched_textures);
+    });
 
-    // Reclaim some memory...
-    std::mem::drop(pixels);
+    true
+}
 
-    // Create output dir
-    let Some(path_dir) = path.as_ref().parent() else {
-        return false;
-    };
-    match std::fs::create_dir_all(path_dir) {
-        Ok(_) => (),
-        Err(e) => {
-            error!("Failed to create directory: {}", e);
-            return false;
-        }
+/// Dumps the original pixels of the texture to the asset dump dir if it hasn't been dumped yet.
+/// `rel_path` is relative to the assets dir (e.g. textures/xxx.png).
+pub fn dump_texture(texture: *mut Il2CppObject, rel_path: String) {
+    if !asset_dump::should_dump(&rel_path) {
+        return;
     }
 
-    // Write to file
-    let mut out_file = match std::fs::File::create(&path) {
-        Ok(v) => v,
-        Err(e) => {
-            error!("Failed to create file: {}", e);
-            return false;
-        }
+    let width = Texture::GetDataWidth(texture) as usize;
+    let height = Texture::GetDataHeight(texture) as usize;
+
+    // Textures in bundles are usually non-readable, read them back through a render texture
+    let new_texture = Texture2D::render_to_texture(texture);
+    let pixels_array = Texture2D::GetPixels32(new_texture, 0);
+    let pixels = unsafe {
+        let slice = pixels_array.as_slice();
+        std::slice::from_raw_parts(slice.as_ptr() as *const u8, slice.len() * 4).to_vec()
     };
+    Object::Destroy(new_texture);
 
-    if let Err(e) = out_file.write(&png_buffer) {
-        error!("Failed to write to file: {}", e);
-        return false;
-    }
+    asset_dump::write_texture(rel_path, pixels, width, height);
+}
 
-    // And finally load image to texture
-    let png_array = Array::<u8>::new(mscorlib::Byte::class(), png_buffer.len());
-    unsafe { png_array.as_slice().copy_from_slice(&png_buffer); }
-    ImageConversion::LoadImage(texture, png_array.this, mark_non_readable);
+static PENDING_PATCHED_TEXTURES: Mutex<Vec<(GCHandle, Option<Vec<u8>>, bool)>> = Mutex::new(Vec::new());
 
-    true
+fn apply_pending_patched_textures() {
+    let pending = std::mem::take(&mut *PENDING_PATCHED_TEXTURES.lock().unwrap());
+    for (handle, png, mark_non_readable) in pending {
+        let texture = handle.target();
+        // The texture might have been destroyed in the meantime
+        if let Some(png) = png.filter(|_| !texture.is_null()) {
+            load_png_into_texture(texture, &png, mark_non_readable);
+        }
+    }
+}
+
+fn load_png_into_texture(texture: *mut Il2CppObject, png: &[u8], mark_non_readable: bool) -> bool {
+    let png_array = Array::<u8>::new(mscorlib::Byte::class(), png.len());
+    unsafe { png_array.as_slice().copy_from_slice(png); }
+    ImageConversion::LoadImage(texture, png_array.this, mark_non_readable)
 }
 
 /// Changes the "active area" of the GameObject a Component is part of.
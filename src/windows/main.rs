@@ -3,7 +3,7 @@ use std::os::raw::{c_ulong, c_void};
 use widestring::U16CString;
 use windows::{core::PCWSTR, Win32::{Foundation::{HMODULE, TRUE}, System::LibraryLoader::LoadLibraryW}};
 
-use crate::{core::{plugin_api::Plugin, Hachimi}, windows::utils};
+use crate::{core::{mt_cache::MtCache, plugin_api::Plugin, Hachimi}, windows::utils};
 
 use super::{hook, wnd_hook};
 
@@ -74,6 +74,7 @@ pub extern "C" fn DllMain(hmodule: HMODULE, call_reason: c_ulong, _reserved: *mu
     }
     else if call_reason == DLL_PROCESS_DETACH && Hachimi::is_initialized() {
         wnd_hook::uninit();
+        MtCache::flush();
 
         info!("Unhooking everything");
         Hachimi::instance().interceptor.unhook_all();